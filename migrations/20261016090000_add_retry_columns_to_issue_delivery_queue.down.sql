@@ -0,0 +1,4 @@
+ALTER TABLE issue_delivery_queue
+  DROP COLUMN n_attempts,
+  DROP COLUMN last_error,
+  DROP COLUMN next_attempt_at;
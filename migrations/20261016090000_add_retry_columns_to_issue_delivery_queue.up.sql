@@ -0,0 +1,4 @@
+ALTER TABLE issue_delivery_queue
+  ADD COLUMN n_attempts INTEGER NOT NULL DEFAULT 0,
+  ADD COLUMN last_error TEXT,
+  ADD COLUMN next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now();
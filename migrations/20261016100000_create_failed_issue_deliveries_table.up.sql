@@ -0,0 +1,13 @@
+CREATE TABLE failed_issue_deliveries (
+   failed_delivery_id uuid NOT NULL,
+   newsletter_issue_id uuid NOT NULL
+     REFERENCES newsletter_issues (newsletter_issue_id)
+     ON DELETE CASCADE,
+   subscriber_email TEXT NOT NULL,
+   n_attempts INTEGER NOT NULL,
+   failure_reason TEXT NOT NULL,
+   failed_at TIMESTAMPTZ NOT NULL,
+   PRIMARY KEY(failed_delivery_id),
+   CONSTRAINT unique_failed_issue_deliveries_issue_and_email
+     UNIQUE (newsletter_issue_id, subscriber_email)
+);
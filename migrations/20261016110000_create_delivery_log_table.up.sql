@@ -0,0 +1,14 @@
+CREATE TABLE delivery_log (
+   delivery_log_id uuid NOT NULL,
+   newsletter_issue_id uuid NOT NULL
+     REFERENCES newsletter_issues (newsletter_issue_id)
+     ON DELETE CASCADE,
+   subscriber_email TEXT NOT NULL,
+   outcome TEXT NOT NULL,
+   provider_response TEXT,
+   logged_at TIMESTAMPTZ NOT NULL,
+   PRIMARY KEY(delivery_log_id)
+);
+
+CREATE INDEX delivery_log_newsletter_issue_id_logged_at_idx
+  ON delivery_log (newsletter_issue_id, logged_at);
@@ -0,0 +1 @@
+ALTER TABLE newsletter_issues ADD COLUMN scheduled_for TIMESTAMPTZ;
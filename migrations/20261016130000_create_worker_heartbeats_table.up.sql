@@ -0,0 +1,5 @@
+CREATE TABLE worker_heartbeats (
+   worker_id uuid NOT NULL,
+   last_heartbeat_at TIMESTAMPTZ NOT NULL,
+   PRIMARY KEY(worker_id)
+);
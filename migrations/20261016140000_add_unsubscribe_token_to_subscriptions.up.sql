@@ -0,0 +1,9 @@
+ALTER TABLE subscriptions ADD COLUMN unsubscribe_token TEXT NULL;
+
+UPDATE subscriptions
+  SET unsubscribe_token = replace(gen_random_uuid()::text, '-', '');
+
+ALTER TABLE subscriptions
+  ALTER COLUMN unsubscribe_token SET NOT NULL,
+  ADD CONSTRAINT unique_subscriptions_unsubscribe_token
+    UNIQUE (unsubscribe_token);
@@ -0,0 +1 @@
+ALTER TABLE subscription_tokens DROP COLUMN created_at;
@@ -0,0 +1,12 @@
+CREATE TABLE confirmation_email_outbox (
+   outbox_id uuid NOT NULL,
+   subscriber_id uuid NOT NULL
+     REFERENCES subscriptions (id)
+     ON DELETE CASCADE,
+   subscription_token TEXT NOT NULL,
+   n_attempts INTEGER NOT NULL DEFAULT 0,
+   last_error TEXT,
+   next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+   created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+   PRIMARY KEY(outbox_id)
+);
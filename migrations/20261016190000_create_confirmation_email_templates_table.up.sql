@@ -0,0 +1,9 @@
+CREATE TABLE confirmation_email_templates (
+   user_id uuid NOT NULL
+     REFERENCES users (user_id)
+     ON DELETE CASCADE,
+   subject TEXT NOT NULL,
+   body TEXT NOT NULL,
+   updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+   PRIMARY KEY(user_id)
+);
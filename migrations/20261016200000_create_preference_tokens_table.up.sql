@@ -0,0 +1,6 @@
+CREATE TABLE preference_tokens (
+   preference_token TEXT NOT NULL,
+   email TEXT NOT NULL,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+   PRIMARY KEY (preference_token)
+);
@@ -0,0 +1 @@
+DROP INDEX subscriptions_lower_email_idx;
@@ -0,0 +1 @@
+CREATE INDEX subscriptions_lower_email_idx ON subscriptions (lower(email));
@@ -0,0 +1,12 @@
+CREATE TABLE subscription_consents (
+   consent_id UUID NOT NULL,
+   subscriber_id UUID NOT NULL REFERENCES subscriptions (id) ON DELETE CASCADE,
+   source TEXT NOT NULL,
+   ip_address TEXT,
+   user_agent TEXT,
+   consent_text TEXT NOT NULL,
+   consented_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+   confirmed_at TIMESTAMPTZ,
+   PRIMARY KEY (consent_id)
+);
+CREATE INDEX subscription_consents_subscriber_id_idx ON subscription_consents (subscriber_id);
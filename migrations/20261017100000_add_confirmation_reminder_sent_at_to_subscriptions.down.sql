@@ -0,0 +1,2 @@
+DROP INDEX subscriptions_pending_confirmation_idx;
+ALTER TABLE subscriptions DROP COLUMN confirmation_reminder_sent_at;
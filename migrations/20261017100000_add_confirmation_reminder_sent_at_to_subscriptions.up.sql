@@ -0,0 +1,3 @@
+ALTER TABLE subscriptions ADD COLUMN confirmation_reminder_sent_at TIMESTAMPTZ;
+CREATE INDEX subscriptions_pending_confirmation_idx ON subscriptions (subscribed_at)
+   WHERE status = 'pending_confirmation';
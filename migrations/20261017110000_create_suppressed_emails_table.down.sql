@@ -0,0 +1 @@
+DROP TABLE suppressed_emails;
@@ -0,0 +1,6 @@
+CREATE TABLE suppressed_emails (
+   email TEXT NOT NULL,
+   reason TEXT NOT NULL,
+   suppressed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+   PRIMARY KEY (email)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE newsletter_issues DROP COLUMN scheduled_segment_operator;
+ALTER TABLE newsletter_issues DROP COLUMN scheduled_segment_tags;
@@ -0,0 +1,2 @@
+ALTER TABLE newsletter_issues ADD COLUMN scheduled_segment_tags TEXT[];
+ALTER TABLE newsletter_issues ADD COLUMN scheduled_segment_operator TEXT;
@@ -0,0 +1 @@
+DROP TABLE preference_link_outbox;
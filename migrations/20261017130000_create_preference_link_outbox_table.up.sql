@@ -0,0 +1,9 @@
+CREATE TABLE preference_link_outbox (
+   outbox_id uuid NOT NULL,
+   email TEXT NOT NULL,
+   n_attempts INTEGER NOT NULL DEFAULT 0,
+   last_error TEXT,
+   next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+   created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+   PRIMARY KEY(outbox_id)
+);
@@ -1,12 +1,20 @@
-use aes_gcm::aead::{Aead, KeyInit, OsRng};
-use aes_gcm::{AeadCore, Aes256Gcm, Key, Nonce};
+use super::{CaptchaResponse, Challenger, cipher};
 use anyhow::Context;
-use base64::Engine;
-use base64::engine::general_purpose::STANDARD;
 use captcha::Captcha;
 use captcha::filters::{Dots, Grid, Noise, Wave};
+use chrono::{DateTime, Duration, Utc};
 use secrecy::{ExposeSecret, Secret};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The contents of an encrypted challenge. `id` and `issued_at` let a challenge
+/// expire and be redeemed only once.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ChallengePayload {
+    pub answer: String,
+    pub id: Uuid,
+    pub issued_at: DateTime<Utc>,
+}
 
 #[derive(Deserialize, Debug)]
 pub struct Base64Challenger {
@@ -42,70 +50,91 @@ impl Base64Challenger {
         })
     }
 
+    pub fn answer(&self) -> &str {
+        &self.answer
+    }
+
     pub fn encrypt(&self) -> Result<String, anyhow::Error> {
-        // Create cipher from 32-byte key. (Panics if key is not 32 bytes)
-        let key = Key::<Aes256Gcm>::from_slice(self.secret.expose_secret().as_bytes());
-        let cipher = Aes256Gcm::new(key);
-        // Nonce is a 12-byte value.
-        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
-        let ciphertext = match cipher.encrypt(&nonce, self.answer.as_bytes()) {
-            Ok(cipher) => cipher,
-            Err(_) => anyhow::bail!("Failed to encrypt challenge."),
-        };
-
-        // Base64 encode nonce + ciphertext for output.
-        let mut out = Vec::with_capacity(nonce.as_slice().len() + ciphertext.len());
-        out.extend_from_slice(nonce.as_slice());
-        out.extend_from_slice(&ciphertext);
-
-        Ok(STANDARD.encode(out))
-    }
-
-    pub fn decrypt(encoded: &str, secret: Secret<String>) -> Result<String, anyhow::Error> {
-        let data = STANDARD
-            .decode(encoded)
-            .context("Failed to base64 decode challenge.")?;
-
-        if data.len() < 12 {
-            anyhow::bail!("Ciphertext is too short.")
-        }
+        cipher::seal(
+            &ChallengePayload {
+                answer: self.answer.clone(),
+                id: Uuid::new_v4(),
+                issued_at: Utc::now(),
+            },
+            &self.secret,
+        )
+    }
 
-        // Split nonce and ciphertext for decryption.
-        let (nonce_bytes, ciphertext) = data.split_at(12);
-        let nonce = Nonce::from_slice(nonce_bytes);
-        let key = Key::<Aes256Gcm>::from_slice(secret.expose_secret().as_bytes());
-        let cipher = Aes256Gcm::new(key);
-
-        match cipher.decrypt(nonce, ciphertext.as_ref()) {
-            Ok(bytes_vec) => {
-                String::from_utf8(bytes_vec).context("Failed to convert answer to string.")
-            }
-            Err(_) => anyhow::bail!("Failed to decrypt answer."),
-        }
+    pub fn decrypt(
+        encoded: &str,
+        secret: Secret<String>,
+    ) -> Result<ChallengePayload, anyhow::Error> {
+        cipher::open(encoded, &secret)
     }
 
+    /// Check `answer` against a challenge that must have been issued less than `ttl` before `now`.
+    /// Returns the challenge id, which the caller should redeem to prevent replays.
     pub fn verify(
         encoded: &str,
         answer: String,
         secret: Secret<String>,
-    ) -> Result<(), anyhow::Error> {
-        if Self::decrypt(encoded, secret)? == answer {
-            Ok(())
+        ttl: Duration,
+        now: DateTime<Utc>,
+    ) -> Result<Uuid, anyhow::Error> {
+        let payload = Self::decrypt(encoded, secret)?;
+        if now - payload.issued_at > ttl {
+            anyhow::bail!("The challenge has expired.")
+        }
+        if payload.answer == answer {
+            Ok(payload.id)
         } else {
             anyhow::bail!("Incorrect answer.")
         }
     }
 }
 
-#[derive(Serialize, Debug)]
-pub struct CaptchaResponse {
-    pub challenge_image: String,
-    pub challenge: String,
+/// Issues image captchas that the user solves by typing the characters they see.
+pub struct ImageChallenger {
+    secret: Secret<String>,
+    ttl: Duration,
+}
+
+impl ImageChallenger {
+    pub fn new(secret: Secret<String>, ttl: Duration) -> Self {
+        Self { secret, ttl }
+    }
+}
+
+impl Challenger for ImageChallenger {
+    fn issue(&self) -> Result<CaptchaResponse, anyhow::Error> {
+        let challenger = Base64Challenger::new(self.secret.clone())?;
+
+        Ok(CaptchaResponse::Image {
+            challenge: challenger.encrypt()?,
+            challenge_image: format!("data:image/png;base64,{}", challenger.base64_image),
+        })
+    }
+
+    fn verify(
+        &self,
+        encoded: &str,
+        answer: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Uuid, anyhow::Error> {
+        Base64Challenger::verify(
+            encoded,
+            answer.to_string(),
+            self.secret.clone(),
+            self.ttl,
+            now,
+        )
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::challenge::Base64Challenger;
+    use chrono::{Duration, Utc};
     use claims::{assert_err, assert_ok};
     use secrecy::Secret;
 
@@ -140,7 +169,7 @@ mod tests {
         let encrypted = challenge.encrypt().unwrap();
         let decrypted = Base64Challenger::decrypt(&encrypted, challenge.secret).unwrap();
 
-        assert_eq!(challenge.answer, decrypted);
+        assert_eq!(challenge.answer, decrypted.answer);
     }
 
     #[test]
@@ -152,7 +181,9 @@ mod tests {
         assert_ok!(Base64Challenger::verify(
             &encrypted,
             challenge.answer,
-            challenge.secret
+            challenge.secret,
+            Duration::minutes(10),
+            Utc::now(),
         ));
     }
 
@@ -165,7 +196,34 @@ mod tests {
         assert_err!(Base64Challenger::verify(
             &encrypted,
             String::from("badanswer"),
-            challenge.secret
+            challenge.secret,
+            Duration::minutes(10),
+            Utc::now(),
         ));
     }
+
+    #[test]
+    fn can_reject_expired_challenge() {
+        let secret = Secret::from("q1Fz8VUd0nK3sA2eYw7L5mRbT9xCj4Ho".to_string());
+        let challenge = Base64Challenger::new(secret).expect("Creating challenge.");
+        let encrypted = challenge.encrypt().unwrap();
+
+        assert_err!(Base64Challenger::verify(
+            &encrypted,
+            challenge.answer,
+            challenge.secret,
+            Duration::minutes(10),
+            Utc::now() + Duration::minutes(11),
+        ));
+    }
+
+    #[test]
+    fn each_encrypted_challenge_has_a_unique_id() {
+        let secret = Secret::from("Xc3Lr0Tq8WbN1uYs5GdK7hPz2Ve9Aj6M".to_string());
+        let challenge = Base64Challenger::new(secret.clone()).expect("Creating challenge.");
+        let first = Base64Challenger::decrypt(&challenge.encrypt().unwrap(), secret.clone());
+        let second = Base64Challenger::decrypt(&challenge.encrypt().unwrap(), secret);
+
+        assert_ne!(first.unwrap().id, second.unwrap().id);
+    }
 }
@@ -0,0 +1,63 @@
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{AeadCore, Aes256Gcm, Key, Nonce};
+use anyhow::Context;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use secrecy::{ExposeSecret, Secret};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Encrypt `payload` with AES-256-GCM, returning the base64 encoded nonce and ciphertext.
+pub fn seal<T: Serialize>(payload: &T, secret: &Secret<String>) -> Result<String, anyhow::Error> {
+    let cipher = cipher(secret)?;
+    // Nonce is a 12-byte value.
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let plaintext = serde_json::to_vec(payload).context("Failed to serialize challenge.")?;
+    let ciphertext = match cipher.encrypt(&nonce, plaintext.as_slice()) {
+        Ok(cipher) => cipher,
+        Err(_) => anyhow::bail!("Failed to encrypt challenge."),
+    };
+
+    // Base64 encode nonce + ciphertext for output.
+    let mut out = Vec::with_capacity(nonce.as_slice().len() + ciphertext.len());
+    out.extend_from_slice(nonce.as_slice());
+    out.extend_from_slice(&ciphertext);
+
+    Ok(STANDARD.encode(out))
+}
+
+/// Decrypt a payload produced by `seal`.
+pub fn open<T: DeserializeOwned>(
+    encoded: &str,
+    secret: &Secret<String>,
+) -> Result<T, anyhow::Error> {
+    let data = STANDARD
+        .decode(encoded)
+        .context("Failed to base64 decode challenge.")?;
+
+    if data.len() < 12 {
+        anyhow::bail!("Ciphertext is too short.")
+    }
+
+    // Split nonce and ciphertext for decryption.
+    let (nonce_bytes, ciphertext) = data.split_at(12);
+    let nonce = Nonce::from_slice(nonce_bytes);
+    let cipher = cipher(secret)?;
+
+    match cipher.decrypt(nonce, ciphertext.as_ref()) {
+        Ok(bytes_vec) => {
+            serde_json::from_slice(&bytes_vec).context("Failed to deserialize challenge.")
+        }
+        Err(_) => anyhow::bail!("Failed to decrypt challenge."),
+    }
+}
+
+fn cipher(secret: &Secret<String>) -> Result<Aes256Gcm, anyhow::Error> {
+    // `Key::from_slice` panics if the key is not 32 bytes.
+    if secret.expose_secret().len() != 32 {
+        anyhow::bail!("Secret must be 32 bytes in length.")
+    }
+    let key = Key::<Aes256Gcm>::from_slice(secret.expose_secret().as_bytes());
+
+    Ok(Aes256Gcm::new(key))
+}
@@ -1,3 +1,48 @@
 mod base64_challenger;
+mod cipher;
+mod proof_of_work_challenger;
+mod redeemed_challenges;
 
 pub use base64_challenger::*;
+pub use proof_of_work_challenger::*;
+pub use redeemed_challenges::*;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Issues the challenges served by `GET /captcha` and verifies the answers
+/// posted alongside a subscription.
+pub trait Challenger: Send + Sync {
+    fn issue(&self) -> Result<CaptchaResponse, anyhow::Error>;
+
+    /// Check `answer` against a challenge issued by `issue`, rejecting it once it has expired.
+    /// Returns the challenge id, which the caller should redeem to prevent replays.
+    fn verify(
+        &self,
+        encoded: &str,
+        answer: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Uuid, anyhow::Error>;
+}
+
+/// The kind of challenge subscribers have to solve.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChallengeKind {
+    Image,
+    ProofOfWork,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CaptchaResponse {
+    Image {
+        challenge: String,
+        challenge_image: String,
+    },
+    ProofOfWork {
+        challenge: String,
+        difficulty: u8,
+    },
+}
@@ -0,0 +1,133 @@
+use super::{CaptchaResponse, Challenger, cipher};
+use chrono::{DateTime, Duration, Utc};
+use secrecy::Secret;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use uuid::Uuid;
+
+/// The contents of an encrypted proof-of-work challenge.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ProofOfWorkPayload {
+    pub difficulty: u8,
+    pub id: Uuid,
+    pub issued_at: DateTime<Utc>,
+}
+
+/// Issues hashcash-style challenges: the client must find an answer for which
+/// `SHA-1("{challenge}:{answer}")` starts with `difficulty` zero bits.
+/// Solving one needs no user interaction, only CPU time that grows with `difficulty`.
+pub struct ProofOfWorkChallenger {
+    difficulty: u8,
+    secret: Secret<String>,
+    ttl: Duration,
+}
+
+impl ProofOfWorkChallenger {
+    pub fn new(secret: Secret<String>, ttl: Duration, difficulty: u8) -> Self {
+        Self {
+            difficulty,
+            secret,
+            ttl,
+        }
+    }
+
+    pub fn encrypt(&self) -> Result<String, anyhow::Error> {
+        cipher::seal(
+            &ProofOfWorkPayload {
+                difficulty: self.difficulty,
+                id: Uuid::new_v4(),
+                issued_at: Utc::now(),
+            },
+            &self.secret,
+        )
+    }
+
+    /// Brute force an answer to `challenge`, as a client would.
+    pub fn solve(challenge: &str, difficulty: u8) -> String {
+        (0u64..)
+            .map(|counter| counter.to_string())
+            .find(|answer| leading_zero_bits(challenge, answer) >= u32::from(difficulty))
+            .expect("Exhausted the search space without solving the challenge.")
+    }
+}
+
+impl Challenger for ProofOfWorkChallenger {
+    fn issue(&self) -> Result<CaptchaResponse, anyhow::Error> {
+        Ok(CaptchaResponse::ProofOfWork {
+            challenge: self.encrypt()?,
+            difficulty: self.difficulty,
+        })
+    }
+
+    fn verify(
+        &self,
+        encoded: &str,
+        answer: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Uuid, anyhow::Error> {
+        let payload: ProofOfWorkPayload = cipher::open(encoded, &self.secret)?;
+        if now - payload.issued_at > self.ttl {
+            anyhow::bail!("The challenge has expired.")
+        }
+        if leading_zero_bits(encoded, answer) >= u32::from(payload.difficulty) {
+            Ok(payload.id)
+        } else {
+            anyhow::bail!("Insufficient proof of work.")
+        }
+    }
+}
+
+fn leading_zero_bits(challenge: &str, answer: &str) -> u32 {
+    let digest = Sha1::digest(format!("{}:{}", challenge, answer).as_bytes());
+    let mut zeros = 0;
+    for byte in digest {
+        zeros += byte.leading_zeros();
+        if byte != 0 {
+            break;
+        }
+    }
+
+    zeros
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::challenge::{Challenger, ProofOfWorkChallenger};
+    use chrono::{Duration, Utc};
+    use claims::{assert_err, assert_ok};
+    use secrecy::Secret;
+
+    fn challenger() -> ProofOfWorkChallenger {
+        let secret = Secret::from("Pw0Rk9sEcReT4tEsTiNgHaShCaSh1234".to_string());
+
+        ProofOfWorkChallenger::new(secret, Duration::minutes(10), 8)
+    }
+
+    #[test]
+    fn can_verify_solved_challenge() {
+        let challenger = challenger();
+        let challenge = challenger.encrypt().unwrap();
+        let answer = ProofOfWorkChallenger::solve(&challenge, 8);
+
+        assert_ok!(challenger.verify(&challenge, &answer, Utc::now()));
+    }
+
+    #[test]
+    fn can_reject_insufficient_work() {
+        let secret = Secret::from("Pw0Rk9sEcReT4tEsTiNgHaShCaSh1234".to_string());
+        let challenger = ProofOfWorkChallenger::new(secret, Duration::minutes(10), 160);
+        let challenge = challenger.encrypt().unwrap();
+        let answer = ProofOfWorkChallenger::solve(&challenge, 8);
+
+        assert_err!(challenger.verify(&challenge, &answer, Utc::now()));
+    }
+
+    #[test]
+    fn can_reject_expired_challenge() {
+        let challenger = challenger();
+        let challenge = challenger.encrypt().unwrap();
+        let answer = ProofOfWorkChallenger::solve(&challenge, 8);
+
+        assert_err!(challenger.verify(&challenge, &answer, Utc::now() + Duration::minutes(11)));
+    }
+}
@@ -0,0 +1,33 @@
+use redis::aio::ConnectionManager;
+use uuid::Uuid;
+
+/// Records redeemed challenge ids in Redis so each challenge can only be used once.
+/// Ids are kept for as long as the challenge could still be verified.
+#[derive(Clone)]
+pub struct RedeemedChallenges {
+    connection: ConnectionManager,
+    ttl: chrono::Duration,
+}
+
+impl RedeemedChallenges {
+    pub async fn new(redis_uri: &str, ttl: chrono::Duration) -> Result<Self, anyhow::Error> {
+        let client = redis::Client::open(redis_uri)?;
+        let connection = ConnectionManager::new(client).await?;
+
+        Ok(Self { connection, ttl })
+    }
+
+    /// Mark a challenge as used. Returns `false` if it had already been redeemed.
+    pub async fn redeem(&self, challenge_id: Uuid) -> Result<bool, redis::RedisError> {
+        let stored: Option<String> = redis::cmd("SET")
+            .arg(format!("captcha:redeemed:{challenge_id}"))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(self.ttl.num_seconds().max(1))
+            .query_async(&mut self.connection.clone())
+            .await?;
+
+        Ok(stored.is_some())
+    }
+}
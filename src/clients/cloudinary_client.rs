@@ -257,14 +257,14 @@ mod tests {
 
     fn bucket() -> String {
         let bucket: String = Word().fake();
-        let bucket = bucket.to_lowercase();
-        bucket
+
+        bucket.to_lowercase()
     }
 
     fn public_id() -> String {
         let public_id: String = Word().fake();
-        let public_id = public_id.to_lowercase();
-        public_id
+
+        public_id.to_lowercase()
     }
 
     fn file() -> String {
@@ -75,6 +75,21 @@ impl S3Client {
         Ok(response)
     }
 
+    /// Fail unless the images bucket can be reached and exists.
+    pub async fn check_images_bucket(&self) -> Result<(), anyhow::Error> {
+        let exists = self
+            .buckets
+            .images
+            .exists()
+            .await
+            .context("Failed to reach the images bucket.")?;
+        if !exists {
+            anyhow::bail!("The images bucket does not exist.");
+        }
+
+        Ok(())
+    }
+
     async fn initialize_buckets(
         region: String,
         endpoint: String,
@@ -219,14 +234,14 @@ mod tests {
                     .set_body_raw(NONEXISTING_BUCKET_LIST_ALL_RESULT, "application/xml"),
             )
             .expect(1)
-            .mount(&mock_server)
+            .mount(mock_server)
             .await;
 
         Mock::given(method("PUT"))
             .and(path("/images"))
             .respond_with(ResponseTemplate::new(200))
             .expect(1)
-            .mount(&mock_server)
+            .mount(mock_server)
             .await;
     }
 
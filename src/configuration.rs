@@ -1,12 +1,20 @@
+use crate::challenge::{ChallengeKind, Challenger, ImageChallenger, ProofOfWorkChallenger};
 use crate::clients::cloudinary_client::CloudinaryClient;
 use crate::clients::s3_client::S3Client;
 use crate::domain::SubscriberEmail;
-use crate::email_client::{EmailClient, EmailServer, deserialize_email_server_from_string};
+use crate::email_client::{
+    EmailClient, EmailServer, SmtpTls, deserialize_email_server_from_string,
+};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, Tokio1Executor};
 use secrecy::{ExposeSecret, Secret};
 use serde::Deserialize;
-use serde_aux::field_attributes::deserialize_number_from_string;
+use serde_aux::field_attributes::{
+    deserialize_number_from_string, deserialize_option_number_from_string,
+};
 use sqlx::postgres::{PgConnectOptions, PgSslMode};
 use std::convert::{TryFrom, TryInto};
+use std::sync::Arc;
 
 #[derive(Deserialize, Clone)]
 pub struct Settings {
@@ -17,19 +25,60 @@ pub struct Settings {
     pub s3_client: S3ClientSettings,
     pub hosts: HostnameSettings,
     pub redis_uri: Secret<String>,
+    pub worker: WorkerSettings,
 }
 
 #[derive(Deserialize, Clone)]
 pub struct ApplicationSettings {
     pub base_url: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub captcha_difficulty: u8,
+    pub captcha_kind: ChallengeKind,
     pub captcha_secret: Secret<String>,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub captcha_ttl_seconds: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub confirmation_token_ttl_seconds: i64,
     pub hmac_secret: Secret<String>,
     pub host: String,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub preference_token_ttl_seconds: i64,
     pub session_key: String,
 }
 
+impl ApplicationSettings {
+    /// How long after being issued a captcha challenge can be redeemed.
+    pub fn captcha_ttl(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.captcha_ttl_seconds)
+    }
+
+    /// How long after being issued a subscription confirmation token can be used.
+    pub fn confirmation_token_ttl(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.confirmation_token_ttl_seconds)
+    }
+
+    /// How long after being issued a preference centre magic link can be used.
+    pub fn preference_token_ttl(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.preference_token_ttl_seconds)
+    }
+
+    pub fn challenger(&self) -> Arc<dyn Challenger> {
+        let secret = self.captcha_secret.clone();
+        let ttl = self.captcha_ttl();
+
+        match self.captcha_kind {
+            ChallengeKind::Image => Arc::new(ImageChallenger::new(secret, ttl)),
+            ChallengeKind::ProofOfWork => Arc::new(ProofOfWorkChallenger::new(
+                secret,
+                ttl,
+                self.captcha_difficulty,
+            )),
+        }
+    }
+}
+
 #[derive(Deserialize, Clone)]
 pub struct CloudinaryClientSettings {
     pub api_key: String,
@@ -110,19 +159,32 @@ pub struct EmailClientSettings {
     pub timeout_milliseconds: u64,
     #[serde(deserialize_with = "deserialize_email_server_from_string")]
     pub server: EmailServer,
+    pub smtp: SmtpSettings,
+    /// Password the email provider authenticates its bounce and spam-complaint webhooks with.
+    /// Has no default, so every environment must set its own.
+    pub webhook_secret: Secret<String>,
 }
 
 impl EmailClientSettings {
     pub fn client(self) -> EmailClient {
         let sender_email = self.sender().expect("Invalid sender email address.");
         let timeout = self.timeout();
-        EmailClient::new(
+        let email_client = EmailClient::new(
             self.base_url,
             sender_email,
             self.authorization_token,
             timeout,
-            self.server,
-        )
+            self.server.clone(),
+        );
+
+        match self.server {
+            EmailServer::Smtp => email_client.with_smtp_transport(
+                self.smtp
+                    .transport(timeout)
+                    .expect("Invalid SMTP relay configuration."),
+            ),
+            EmailServer::Postmark | EmailServer::Mailpit => email_client,
+        }
     }
 
     pub fn sender(&self) -> Result<SubscriberEmail, String> {
@@ -134,11 +196,103 @@ impl EmailClientSettings {
     }
 }
 
+#[derive(Deserialize, Clone)]
+pub struct SmtpSettings {
+    pub host: String,
+    pub password: Secret<String>,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub port: u16,
+    pub tls: SmtpTls,
+    pub username: String,
+}
+
+impl SmtpSettings {
+    /// Build the SMTP transport, authenticating only when a username is configured.
+    pub fn transport(
+        &self,
+        timeout: std::time::Duration,
+    ) -> Result<AsyncSmtpTransport<Tokio1Executor>, lettre::transport::smtp::Error> {
+        let builder = match self.tls {
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.host),
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host)?,
+        }
+        .port(self.port)
+        .timeout(Some(timeout));
+        let builder = if self.username.is_empty() {
+            builder
+        } else {
+            builder.credentials(Credentials::new(
+                self.username.clone(),
+                self.password.expose_secret().clone(),
+            ))
+        };
+
+        Ok(builder.build())
+    }
+}
+
 #[derive(Deserialize, Clone)]
 pub struct HostnameSettings {
     pub client: String,
 }
 
+#[derive(Deserialize, Clone)]
+pub struct WorkerSettings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub batch_size: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub concurrency: usize,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub global_send_burst: u32,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub global_send_rate_per_second: u32,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_delivery_attempts: i32,
+    /// How often the retention job looks for stale unconfirmed subscribers.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub retention_interval_milliseconds: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub retry_base_delay_milliseconds: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub retry_max_delay_milliseconds: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub scheduler_interval_milliseconds: u64,
+    /// Subscribers still pending confirmation this many days after signing up are asked to
+    /// confirm once more. Leave unset to purge them without a reminder.
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub unconfirmed_reminder_days: Option<i64>,
+    /// Subscribers still pending confirmation this many days after signing up are deleted.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub unconfirmed_retention_days: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub writer_send_burst: u32,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub writer_send_rate_per_second: u32,
+}
+
+impl WorkerSettings {
+    /// Exponential backoff for a delivery that has failed `n_attempts` times,
+    /// capped at `retry_max_delay_milliseconds`.
+    pub fn retry_delay(&self, n_attempts: i32) -> std::time::Duration {
+        let exponent = n_attempts.saturating_sub(1).clamp(0, 32) as u32;
+        let delay = self
+            .retry_base_delay_milliseconds
+            .saturating_mul(2u64.saturating_pow(exponent))
+            .min(self.retry_max_delay_milliseconds);
+
+        std::time::Duration::from_millis(delay)
+    }
+
+    pub fn scheduler_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.scheduler_interval_milliseconds)
+    }
+
+    pub fn retention_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.retention_interval_milliseconds)
+    }
+}
+
 pub fn get_configuration() -> Result<Settings, config::ConfigError> {
     let base_path = std::env::current_dir().expect("Failed to determine the current directory");
     let configuration_directory = base_path.join("configuration");
@@ -210,3 +364,44 @@ impl TryFrom<String> for Environment {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::configuration::WorkerSettings;
+    use std::time::Duration;
+
+    fn worker_settings() -> WorkerSettings {
+        WorkerSettings {
+            batch_size: 50,
+            concurrency: 4,
+            global_send_burst: 50,
+            global_send_rate_per_second: 50,
+            max_delivery_attempts: 5,
+            retention_interval_milliseconds: 3600000,
+            retry_base_delay_milliseconds: 1000,
+            retry_max_delay_milliseconds: 10000,
+            scheduler_interval_milliseconds: 60000,
+            unconfirmed_reminder_days: Some(7),
+            unconfirmed_retention_days: 30,
+            writer_send_burst: 20,
+            writer_send_rate_per_second: 10,
+        }
+    }
+
+    #[test]
+    fn retry_delay_doubles_with_each_attempt() {
+        let settings = worker_settings();
+
+        assert_eq!(settings.retry_delay(1), Duration::from_secs(1));
+        assert_eq!(settings.retry_delay(2), Duration::from_secs(2));
+        assert_eq!(settings.retry_delay(3), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn retry_delay_is_capped_at_the_configured_maximum() {
+        let settings = worker_settings();
+
+        assert_eq!(settings.retry_delay(5), Duration::from_secs(10));
+        assert_eq!(settings.retry_delay(i32::MAX), Duration::from_secs(10));
+    }
+}
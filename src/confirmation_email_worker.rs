@@ -0,0 +1,192 @@
+use crate::configuration::WorkerSettings;
+use crate::domain::SubscriberEmail;
+use crate::email_client::EmailClient;
+use crate::issue_delivery_worker::ExecutionOutcome;
+use crate::models::ConfirmationEmailTemplate;
+use crate::outbox::{Outbox, outbox_loop};
+use crate::rate_limiter::RateLimiter;
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use tokio::sync::watch;
+use tracing::{Span, field::display};
+use uuid::Uuid;
+
+pub async fn confirmation_email_loop(
+    pool: PgPool,
+    email_client: Arc<EmailClient>,
+    rate_limiter: Arc<RateLimiter>,
+    settings: Arc<WorkerSettings>,
+    base_url: Arc<String>,
+    shutdown: watch::Receiver<bool>,
+) -> Result<(), anyhow::Error> {
+    outbox_loop(
+        |shutdown| {
+            try_send_confirmation_email(
+                &pool,
+                &email_client,
+                &rate_limiter,
+                &settings,
+                &base_url,
+                shutdown,
+            )
+        },
+        shutdown,
+    )
+    .await
+}
+
+/// Send the next due confirmation email from the outbox.
+/// Failed sends are retried with the same backoff as issue deliveries; emails to subscribers
+/// who are no longer pending confirmation, or whose address bounced or complained, are dropped.
+#[tracing::instrument(skip_all, fields(outbox_id=tracing::field::Empty), err)]
+pub async fn try_send_confirmation_email(
+    pool: &PgPool,
+    email_client: &EmailClient,
+    rate_limiter: &RateLimiter,
+    settings: &WorkerSettings,
+    base_url: &str,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<ExecutionOutcome, anyhow::Error> {
+    let mut transaction = pool.begin().await?;
+    let Some(task) = dequeue_task(&mut transaction).await? else {
+        return Ok(ExecutionOutcome::EmptyQueue);
+    };
+    Span::current().record("outbox_id", display(task.outbox_id));
+    if task.status != "pending_confirmation" {
+        tracing::info!("Dropping a confirmation email to a subscriber who is no longer pending");
+        OUTBOX.delete(&mut *transaction, task.outbox_id).await?;
+        transaction.commit().await?;
+        return Ok(ExecutionOutcome::TaskCompleted);
+    }
+    if task.suppressed {
+        tracing::info!("Dropping a confirmation email to a suppressed address");
+        OUTBOX.delete(&mut *transaction, task.outbox_id).await?;
+        transaction.commit().await?;
+        return Ok(ExecutionOutcome::TaskCompleted);
+    }
+    let email = match SubscriberEmail::parse(task.email.clone()) {
+        Ok(email) => email,
+        Err(e) => {
+            tracing::error!(
+                error.message = %e,
+                "Dropping a confirmation email. The subscriber's email address is invalid",
+            );
+            OUTBOX.delete(&mut *transaction, task.outbox_id).await?;
+            transaction.commit().await?;
+            return Ok(ExecutionOutcome::TaskCompleted);
+        }
+    };
+    let confirmation_link = format!(
+        "{}/subscriptions/confirm?subscription_token={}",
+        base_url, task.subscription_token
+    );
+    let content = ConfirmationEmailTemplate::find_by_user_id_txn(task.user_id, &mut transaction)
+        .await?
+        .render(&task.name, &task.writer_name, &confirmation_link);
+    OUTBOX.lease(&mut *transaction, task.outbox_id).await?;
+    transaction.commit().await?;
+    if !rate_limiter.acquire(task.user_id, &mut shutdown).await {
+        OUTBOX.release(pool, task.outbox_id).await?;
+        return Ok(ExecutionOutcome::TaskCompleted);
+    }
+    let outcome = email_client
+        .send_email(
+            &email,
+            &content.subject,
+            &content.html_content,
+            &content.text_content,
+        )
+        .await;
+    OUTBOX
+        .record_outcome(
+            pool,
+            task.outbox_id,
+            task.n_attempts,
+            outcome,
+            rate_limiter,
+            settings,
+        )
+        .await?;
+    Ok(ExecutionOutcome::TaskCompleted)
+}
+
+/// Queue a confirmation email as part of the transaction that stores the subscription token,
+/// so the email is sent if and only if the subscriber is stored.
+#[tracing::instrument(skip(transaction, subscription_token))]
+pub async fn enqueue_confirmation_email(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+    subscription_token: &str,
+) -> Result<(), sqlx::Error> {
+    transaction
+        .execute(sqlx::query!(
+            r#"
+            INSERT INTO confirmation_email_outbox (
+                outbox_id,
+                subscriber_id,
+                subscription_token
+            )
+            VALUES ($1, $2, $3)
+            "#,
+            Uuid::new_v4(),
+            subscriber_id,
+            subscription_token
+        ))
+        .await?;
+    Ok(())
+}
+
+type PgTransaction = Transaction<'static, Postgres>;
+
+const OUTBOX: Outbox = Outbox::new("confirmation_email_outbox", "a confirmation email");
+
+struct OutboxTask {
+    outbox_id: Uuid,
+    subscription_token: String,
+    n_attempts: i32,
+    email: String,
+    name: String,
+    status: String,
+    suppressed: bool,
+    user_id: Uuid,
+    writer_name: String,
+}
+
+#[tracing::instrument(skip_all)]
+async fn dequeue_task(
+    transaction: &mut PgTransaction,
+) -> Result<Option<OutboxTask>, anyhow::Error> {
+    let task = sqlx::query_as!(
+        OutboxTask,
+        r#"
+        SELECT
+          confirmation_email_outbox.outbox_id,
+          confirmation_email_outbox.subscription_token,
+          confirmation_email_outbox.n_attempts,
+          subscriptions.email,
+          subscriptions.name,
+          subscriptions.status,
+          EXISTS (
+            SELECT 1 FROM suppressed_emails
+            WHERE suppressed_emails.email = lower(subscriptions.email)
+          ) AS "suppressed!",
+          subscriptions.user_id,
+          COALESCE(NULLIF(user_profiles.display_name, ''), users.username) AS "writer_name!"
+        FROM confirmation_email_outbox
+        JOIN subscriptions
+          ON subscriptions.id = confirmation_email_outbox.subscriber_id
+        JOIN users
+          ON users.user_id = subscriptions.user_id
+        LEFT JOIN user_profiles
+          ON user_profiles.user_id = subscriptions.user_id
+        WHERE confirmation_email_outbox.next_attempt_at <= now()
+        ORDER BY confirmation_email_outbox.created_at
+        FOR UPDATE OF confirmation_email_outbox
+        SKIP LOCKED
+        LIMIT 1
+        "#,
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+    Ok(task)
+}
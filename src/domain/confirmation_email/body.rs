@@ -0,0 +1,54 @@
+use super::CONFIRMATION_LINK_PLACEHOLDER;
+use crate::utils::is_too_long;
+
+/// Markdown body of a confirmation email.
+#[derive(Debug)]
+pub struct Body(String);
+
+impl AsRef<str> for Body {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Body {
+    pub fn parse(s: String) -> Result<Body, String> {
+        if !s.contains(CONFIRMATION_LINK_PLACEHOLDER) {
+            Err(format!(
+                "Body must include {} so subscribers can confirm.",
+                CONFIRMATION_LINK_PLACEHOLDER
+            ))
+        } else if is_too_long(&s, 10_000) {
+            Err(String::from("Body exceeds character limit."))
+        } else {
+            Ok(Self(s))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::confirmation_email::Body;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn a_body_without_the_confirmation_link_is_rejected() {
+        let body = "Welcome to my newsletter!".to_string();
+
+        assert_err!(Body::parse(body));
+    }
+
+    #[test]
+    fn a_body_longer_than_10000_graphemes_is_rejected() {
+        let body = format!("{{{{confirmation_link}}}}{}", "a".repeat(10_000));
+
+        assert_err!(Body::parse(body));
+    }
+
+    #[test]
+    fn a_valid_body_is_parsed_successfully() {
+        let body = "Hi {{subscriber_name}}, [confirm here]({{confirmation_link}})".to_string();
+
+        assert_ok!(Body::parse(body));
+    }
+}
@@ -0,0 +1,9 @@
+mod body;
+mod subject;
+
+pub use body::*;
+pub use subject::*;
+
+pub const SUBSCRIBER_NAME_PLACEHOLDER: &str = "{{subscriber_name}}";
+pub const WRITER_NAME_PLACEHOLDER: &str = "{{writer_name}}";
+pub const CONFIRMATION_LINK_PLACEHOLDER: &str = "{{confirmation_link}}";
@@ -0,0 +1,49 @@
+use crate::utils::{is_empty_or_whitespace, is_too_long};
+
+#[derive(Debug)]
+pub struct Subject(String);
+
+impl AsRef<str> for Subject {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Subject {
+    pub fn parse(s: String) -> Result<Subject, String> {
+        if is_empty_or_whitespace(&s) {
+            Err(String::from("A subject is required."))
+        } else if is_too_long(&s, 150) {
+            Err(String::from("Subject exceeds character limit."))
+        } else {
+            Ok(Self(s))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::confirmation_email::Subject;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn a_150_grapheme_long_subject_is_valid() {
+        let subject = "ё".repeat(150);
+
+        assert_ok!(Subject::parse(subject));
+    }
+
+    #[test]
+    fn a_subject_longer_than_150_graphemes_is_rejected() {
+        let subject = "a".repeat(151);
+
+        assert_err!(Subject::parse(subject));
+    }
+
+    #[test]
+    fn whitespace_only_subjects_are_rejected() {
+        let subject = " ".to_string();
+
+        assert_err!(Subject::parse(subject));
+    }
+}
@@ -0,0 +1,57 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Shown next to the signup form when the form doesn't supply its own wording.
+pub const DEFAULT_CONSENT_TEXT: &str =
+    "I agree to receive this newsletter by email. I can unsubscribe at any time.";
+
+/// The exact wording a reader agreed to when subscribing, kept as evidence of consent.
+#[derive(Debug)]
+pub struct ConsentText(String);
+
+impl ConsentText {
+    pub fn parse(s: String) -> Result<ConsentText, String> {
+        let is_empty_or_whitespace = s.trim().is_empty();
+        let is_too_long = s.graphemes(true).count() > 2000;
+
+        if is_empty_or_whitespace || is_too_long {
+            Err(String::from(
+                "The consent text must be between 1 and 2000 characters long.",
+            ))
+        } else {
+            Ok(Self(s))
+        }
+    }
+}
+
+impl Default for ConsentText {
+    fn default() -> Self {
+        Self(String::from(DEFAULT_CONSENT_TEXT))
+    }
+}
+
+impl AsRef<str> for ConsentText {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::ConsentText;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn a_2000_grapheme_long_consent_text_is_valid() {
+        assert_ok!(ConsentText::parse("a̐".repeat(2000)));
+    }
+
+    #[test]
+    fn a_consent_text_longer_than_2000_graphemes_is_rejected() {
+        assert_err!(ConsentText::parse("a".repeat(2001)));
+    }
+
+    #[test]
+    fn an_empty_consent_text_is_rejected() {
+        assert_err!(ConsentText::parse(String::from(" ")));
+    }
+}
@@ -1,15 +1,20 @@
 mod base64_image_url;
+mod consent_text;
 mod image_url;
 mod new_subscriber;
 mod subscriber_email;
 mod subscriber_name;
+mod subscriber_tag;
 
+pub mod confirmation_email;
 pub mod newsletter_issue;
 pub mod user;
 pub mod user_profile;
 
 pub use base64_image_url::Base64ImageUrl;
+pub use consent_text::{ConsentText, DEFAULT_CONSENT_TEXT};
 pub use image_url::ImageUrl;
 pub use new_subscriber::NewSubscriber;
 pub use subscriber_email::SubscriberEmail;
 pub use subscriber_name::SubscriberName;
+pub use subscriber_tag::SubscriberTag;
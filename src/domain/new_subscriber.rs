@@ -1,10 +1,12 @@
 use crate::domain::SubscriberEmail;
 use crate::domain::SubscriberName;
+use crate::domain::SubscriberTag;
 use uuid::Uuid;
 
 pub struct NewSubscriber {
     // We are not using `String` anymore!
     pub email: SubscriberEmail,
     pub name: SubscriberName,
+    pub tags: Vec<SubscriberTag>,
     pub user_id: Uuid,
 }
@@ -0,0 +1,82 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A label a subscriber carries, used to target issues at readers of a given topic.
+/// Tags are case-insensitive, so they are stored lowercased.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SubscriberTag(String);
+
+impl SubscriberTag {
+    pub fn parse(s: String) -> Result<SubscriberTag, String> {
+        let tag = s.trim().to_lowercase();
+        let is_empty = tag.is_empty();
+        let is_too_long = tag.graphemes(true).count() > 50;
+        let contains_forbidden_characters = tag
+            .chars()
+            .any(|c| !(c.is_alphanumeric() || c == '-' || c == '_' || c == ' '));
+
+        if is_empty || is_too_long || contains_forbidden_characters {
+            Err(format!("{} is not a valid subscriber tag.", s))
+        } else {
+            Ok(Self(tag))
+        }
+    }
+
+    /// Parse every tag, dropping duplicates.
+    pub fn parse_all(tags: Vec<String>) -> Result<Vec<SubscriberTag>, String> {
+        let mut tags = tags
+            .into_iter()
+            .map(SubscriberTag::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        tags.sort();
+        tags.dedup();
+
+        Ok(tags)
+    }
+}
+
+impl AsRef<str> for SubscriberTag {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::SubscriberTag;
+    use claims::{assert_err, assert_ok_eq};
+
+    #[test]
+    fn tags_are_trimmed_and_lowercased() {
+        let tag = SubscriberTag::parse(String::from("  Rust ")).map(|t| t.as_ref().to_owned());
+        assert_ok_eq!(tag, "rust");
+    }
+
+    #[test]
+    fn empty_tags_are_rejected() {
+        assert_err!(SubscriberTag::parse(String::from("  ")));
+    }
+
+    #[test]
+    fn a_tag_longer_than_50_graphemes_is_rejected() {
+        assert_err!(SubscriberTag::parse("a".repeat(51)));
+    }
+
+    #[test]
+    fn tags_containing_punctuation_are_rejected() {
+        for tag in ["rust,go", "<b>", "a/b"] {
+            assert_err!(SubscriberTag::parse(tag.to_string()));
+        }
+    }
+
+    #[test]
+    fn duplicate_tags_are_dropped() {
+        let tags = SubscriberTag::parse_all(vec![
+            String::from("Rust"),
+            String::from("go"),
+            String::from("rust"),
+        ])
+        .unwrap();
+        let tags: Vec<&str> = tags.iter().map(|t| t.as_ref()).collect();
+        assert_eq!(tags, vec!["go", "rust"]);
+    }
+}
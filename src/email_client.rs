@@ -1,16 +1,50 @@
 use crate::domain::SubscriberEmail;
-use reqwest::Client;
+use crate::utils::error_chain_fmt;
+use lettre::message::header::{HeaderName, HeaderValue};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, Response, StatusCode};
 use secrecy::{ExposeSecret, Secret};
 use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
 
 pub struct EmailClient {
     http_client: Client,
     base_url: String,
     sender: SubscriberEmail,
     authorization_token: Secret<String>,
+    smtp_transport: Option<AsyncSmtpTransport<Tokio1Executor>>,
     pub server: EmailServer,
 }
 
+#[derive(thiserror::Error)]
+pub enum EmailClientError {
+    #[error("Failed to send the email through the HTTP API.")]
+    Http(#[from] reqwest::Error),
+    #[error("Failed to build the email message.")]
+    Message(#[from] lettre::error::Error),
+    #[error("Invalid email address.")]
+    Address(#[from] lettre::address::AddressError),
+    #[error("Failed to send the email over SMTP.")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+    #[error("No SMTP transport has been configured.")]
+    MissingSmtpTransport,
+    #[error("The batch request failed: {0}")]
+    BatchRequest(String),
+    #[error("The email provider rejected the message ({error_code}): {message}")]
+    Rejected { error_code: i64, message: String },
+    #[error("The email provider is rate limiting us, retry after {retry_after:?}.")]
+    RateLimited { retry_after: Duration },
+}
+
+impl std::fmt::Debug for EmailClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
 impl EmailClient {
     pub fn new(
         base_url: String,
@@ -25,39 +59,272 @@ impl EmailClient {
             base_url,
             sender,
             authorization_token,
+            smtp_transport: None,
             server,
         }
     }
 
+    /// Use `transport` to relay emails when the client is configured for `EmailServer::Smtp`.
+    pub fn with_smtp_transport(self, transport: AsyncSmtpTransport<Tokio1Executor>) -> Self {
+        Self {
+            smtp_transport: Some(transport),
+            ..self
+        }
+    }
+
+    /// Send an email and return the body of the email provider's response.
     pub async fn send_email(
         &self,
         recipient: &SubscriberEmail,
         subject: &str,
         html_content: &str,
         text_content: &str,
-    ) -> Result<(), reqwest::Error> {
-        let url: String = self.server.url(&self.base_url);
-        let request_body = SendEmailRequest {
-            from: self.sender.as_ref(),
-            to: recipient.as_ref(),
+    ) -> Result<String, EmailClientError> {
+        self.send_message(&EmailMessage {
+            recipient,
             subject,
-            html_body: html_content,
-            text_body: text_content,
-        };
+            html_content,
+            text_content,
+            unsubscribe_url: None,
+        })
+        .await
+    }
+
+    /// Send a single message and return the body of the email provider's response.
+    pub async fn send_message(
+        &self,
+        message: &EmailMessage<'_>,
+    ) -> Result<String, EmailClientError> {
+        let request_body = self.request_body(message);
+        if let EmailServer::Smtp = self.server {
+            return self.send_smtp_email(request_body).await;
+        }
+        let url: String = self.server.url(&self.base_url);
         let builder = self.http_client.post(&url).header(
             "X-Postmark-Server-Token",
             self.authorization_token.expose_secret(),
         );
 
-        match self.server {
+        let response = match self.server {
             EmailServer::Mailpit => builder.json(&MailpitSendEmailRequest::from(request_body)),
-            EmailServer::Postmark => builder.json(&request_body),
+            EmailServer::Postmark | EmailServer::Smtp => builder.json(&request_body),
         }
         .send()
-        .await?
-        .error_for_status()?;
+        .await?;
 
-        Ok(())
+        Ok(check_rate_limit(response)?
+            .error_for_status()?
+            .text()
+            .await?)
+    }
+
+    /// Send several emails, returning one result per message in the order they were given.
+    /// Postmark receives them through its batch API, other servers one request at a time.
+    pub async fn send_email_batch(
+        &self,
+        messages: &[EmailMessage<'_>],
+    ) -> Vec<Result<String, EmailClientError>> {
+        match self.server {
+            EmailServer::Postmark => {
+                let mut results = Vec::with_capacity(messages.len());
+                for chunk in messages.chunks(POSTMARK_BATCH_LIMIT) {
+                    results.extend(self.send_postmark_batch(chunk).await);
+                }
+                results
+            }
+            EmailServer::Mailpit | EmailServer::Smtp => {
+                let mut results = Vec::with_capacity(messages.len());
+                let mut rate_limited: Option<Duration> = None;
+                for message in messages {
+                    // Once the provider asks us to back off there is no point in
+                    // sending the rest of the batch.
+                    if let Some(retry_after) = rate_limited {
+                        results.push(Err(EmailClientError::RateLimited { retry_after }));
+                        continue;
+                    }
+                    let result = self.send_message(message).await;
+                    if let Err(EmailClientError::RateLimited { retry_after }) = result {
+                        rate_limited = Some(retry_after);
+                    }
+                    results.push(result);
+                }
+                results
+            }
+        }
+    }
+
+    async fn send_postmark_batch(
+        &self,
+        messages: &[EmailMessage<'_>],
+    ) -> Vec<Result<String, EmailClientError>> {
+        let request_body: Vec<SendEmailRequest> = messages
+            .iter()
+            .map(|message| self.request_body(message))
+            .collect();
+        let response: Result<Vec<PostmarkBatchResponseEntry>, EmailClientError> = async {
+            let response = self
+                .http_client
+                .post(format!("{}/email/batch", self.base_url))
+                .header(
+                    "X-Postmark-Server-Token",
+                    self.authorization_token.expose_secret(),
+                )
+                .json(&request_body)
+                .send()
+                .await?;
+
+            Ok(check_rate_limit(response)?
+                .error_for_status()?
+                .json()
+                .await?)
+        }
+        .await;
+
+        match response {
+            Ok(entries) if entries.len() == messages.len() => entries
+                .into_iter()
+                .map(|entry| entry.into_result())
+                .collect(),
+            Ok(entries) => messages
+                .iter()
+                .map(|_| {
+                    Err(EmailClientError::BatchRequest(format!(
+                        "Expected {} results, received {}.",
+                        messages.len(),
+                        entries.len()
+                    )))
+                })
+                .collect(),
+            Err(EmailClientError::RateLimited { retry_after }) => messages
+                .iter()
+                .map(|_| Err(EmailClientError::RateLimited { retry_after }))
+                .collect(),
+            Err(e) => messages
+                .iter()
+                .map(|_| Err(EmailClientError::BatchRequest(e.to_string())))
+                .collect(),
+        }
+    }
+
+    fn request_body<'a>(&'a self, message: &EmailMessage<'a>) -> SendEmailRequest<'a> {
+        SendEmailRequest {
+            from: self.sender.as_ref(),
+            to: message.recipient.as_ref(),
+            subject: message.subject,
+            html_body: message.html_content,
+            text_body: message.text_content,
+            headers: message
+                .unsubscribe_url
+                .map(unsubscribe_headers)
+                .unwrap_or_default(),
+        }
+    }
+
+    async fn send_smtp_email(
+        &self,
+        email_request: SendEmailRequest<'_>,
+    ) -> Result<String, EmailClientError> {
+        let transport = self
+            .smtp_transport
+            .as_ref()
+            .ok_or(EmailClientError::MissingSmtpTransport)?;
+        let mut builder = Message::builder()
+            .from(email_request.from.parse::<Mailbox>()?)
+            .to(email_request.to.parse::<Mailbox>()?)
+            .subject(email_request.subject);
+        for header in email_request.headers {
+            builder = builder.raw_header(HeaderValue::new(
+                HeaderName::new_from_ascii_str(header.name),
+                header.value,
+            ));
+        }
+        let message = builder.multipart(MultiPart::alternative_plain_html(
+            email_request.text_body.to_string(),
+            email_request.html_body.to_string(),
+        ))?;
+        let response = transport.send(message).await?;
+
+        Ok(format!(
+            "{} {}",
+            response.code(),
+            response.message().collect::<Vec<&str>>().join(" ")
+        ))
+    }
+}
+
+/// How long to back off when a 429 response does not carry a usable `Retry-After` header.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Turn a `429 Too Many Requests` response into `EmailClientError::RateLimited`,
+/// honoring the delay requested through its `Retry-After` header.
+fn check_rate_limit(response: Response) -> Result<Response, EmailClientError> {
+    if response.status() != StatusCode::TOO_MANY_REQUESTS {
+        return Ok(response);
+    }
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_RETRY_AFTER);
+
+    Err(EmailClientError::RateLimited { retry_after })
+}
+
+/// Maximum number of messages accepted by a single call to Postmark's batch API.
+const POSTMARK_BATCH_LIMIT: usize = 500;
+
+/// A single message of a `send_email_batch` call.
+/// Messages with an `unsubscribe_url` carry `List-Unsubscribe` headers supporting
+/// one-click unsubscription (RFC 8058).
+pub struct EmailMessage<'a> {
+    pub recipient: &'a SubscriberEmail,
+    pub subject: &'a str,
+    pub html_content: &'a str,
+    pub text_content: &'a str,
+    pub unsubscribe_url: Option<&'a str>,
+}
+
+fn unsubscribe_headers(unsubscribe_url: &str) -> Vec<EmailHeader> {
+    vec![
+        EmailHeader {
+            name: "List-Unsubscribe",
+            value: format!("<{unsubscribe_url}>"),
+        },
+        EmailHeader {
+            name: "List-Unsubscribe-Post",
+            value: String::from("List-Unsubscribe=One-Click"),
+        },
+    ]
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct EmailHeader {
+    name: &'static str,
+    value: String,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct PostmarkBatchResponseEntry {
+    error_code: i64,
+    message: String,
+    #[serde(flatten)]
+    rest: serde_json::Map<String, serde_json::Value>,
+}
+
+impl PostmarkBatchResponseEntry {
+    fn into_result(self) -> Result<String, EmailClientError> {
+        if self.error_code == 0 {
+            Ok(serde_json::to_string(&self).unwrap_or(self.message))
+        } else {
+            Err(EmailClientError::Rejected {
+                error_code: self.error_code,
+                message: self.message,
+            })
+        }
     }
 }
 
@@ -69,6 +336,8 @@ struct SendEmailRequest<'a> {
     subject: &'a str,
     html_body: &'a str,
     text_body: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    headers: Vec<EmailHeader>,
 }
 
 impl From<SendEmailRequest<'_>> for MailpitSendEmailRequest {
@@ -85,6 +354,11 @@ impl From<SendEmailRequest<'_>> for MailpitSendEmailRequest {
             subject: email_request.subject.to_string(),
             text: email_request.text_body.to_string(),
             html: email_request.html_body.to_string(),
+            headers: email_request
+                .headers
+                .into_iter()
+                .map(|header| (header.name.to_string(), header.value))
+                .collect(),
         }
     }
 }
@@ -105,6 +379,8 @@ pub struct MailpitSendEmailRequest {
     pub subject: String,
     pub text: String,
     pub html: String,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
 }
 
 /// The possible email services for our application.
@@ -112,6 +388,7 @@ pub struct MailpitSendEmailRequest {
 pub enum EmailServer {
     Postmark,
     Mailpit,
+    Smtp,
 }
 
 impl EmailServer {
@@ -119,17 +396,32 @@ impl EmailServer {
         match self {
             EmailServer::Postmark => "postmark",
             EmailServer::Mailpit => "mailpit",
+            EmailServer::Smtp => "smtp",
         }
     }
 
+    /// Endpoint of the HTTP email API. SMTP servers are reached through `SmtpSettings` instead.
     pub fn url(&self, base_url: &str) -> String {
         match self {
             EmailServer::Postmark => format!("{}/email", base_url),
             EmailServer::Mailpit => format!("{}/api/v1/send", base_url),
+            EmailServer::Smtp => base_url.to_string(),
         }
     }
 }
 
+/// How the connection to an SMTP server is secured.
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    /// Plaintext connection, only suitable for local relays such as Mailpit.
+    None,
+    /// Upgrade a plaintext connection with `STARTTLS`, usually on port 587.
+    StartTls,
+    /// Implicit TLS from the start of the connection, usually on port 465.
+    Tls,
+}
+
 impl TryFrom<String> for EmailServer {
     type Error = String;
 
@@ -137,8 +429,9 @@ impl TryFrom<String> for EmailServer {
         match s.to_lowercase().as_str() {
             "postmark" => Ok(Self::Postmark),
             "mailpit" => Ok(Self::Mailpit),
+            "smtp" => Ok(Self::Smtp),
             other => Err(format!(
-                "{} is not a supported email server. Use either `postmark` or `mailpit`.",
+                "{} is not a supported email server. Use either `postmark`, `mailpit` or `smtp`.",
                 other
             )),
         }
@@ -158,14 +451,20 @@ where
 
 #[cfg(test)]
 mod tests {
+    use crate::configuration::SmtpSettings;
     use crate::domain::SubscriberEmail;
-    use crate::email_client::{EmailClient, EmailServer};
+    use crate::email_client::{
+        EmailClient, EmailClientError, EmailMessage, EmailServer, POSTMARK_BATCH_LIMIT, SmtpTls,
+    };
     use claims::{assert_err, assert_ok};
     use fake::faker::internet::en::SafeEmail;
     use fake::faker::lorem::en::{Paragraph, Sentence};
     use fake::{Fake, Faker};
     use secrecy::Secret;
-    use wiremock::matchers::{any, header, header_exists, method, path};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+    use wiremock::matchers::{any, body_partial_json, header, header_exists, method, path};
     use wiremock::{Mock, MockServer, Request, ResponseTemplate};
 
     struct SendEmailBodyMatcher;
@@ -211,6 +510,351 @@ mod tests {
         )
     }
 
+    /// Reply to a Postmark batch request with a successful entry per message.
+    struct PostmarkBatchResponder;
+
+    impl wiremock::Respond for PostmarkBatchResponder {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let messages: Vec<serde_json::Value> = serde_json::from_slice(&request.body).unwrap();
+            let entries: Vec<serde_json::Value> = messages
+                .iter()
+                .map(|message| {
+                    serde_json::json!({
+                        "ErrorCode": 0,
+                        "Message": "OK",
+                        "MessageID": uuid::Uuid::new_v4().to_string(),
+                        "To": message["To"],
+                    })
+                })
+                .collect();
+            ResponseTemplate::new(200).set_body_json(entries)
+        }
+    }
+
+    /// Generate `n` subscriber emails
+    fn emails(n: usize) -> Vec<SubscriberEmail> {
+        (0..n).map(|_| email()).collect()
+    }
+
+    fn messages<'a>(
+        recipients: &'a [SubscriberEmail],
+        subject: &'a str,
+        content: &'a str,
+    ) -> Vec<EmailMessage<'a>> {
+        recipients
+            .iter()
+            .map(|recipient| EmailMessage {
+                recipient,
+                subject,
+                html_content: content,
+                text_content: content,
+                unsubscribe_url: None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn send_email_batch_returns_a_result_per_message() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = email_client(mock_server.uri());
+        let recipients = emails(2);
+        let (subject, content) = (subject(), content());
+
+        Mock::given(header_exists("X-Postmark-Server-Token"))
+            .and(path("/email/batch"))
+            .and(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                { "ErrorCode": 0, "Message": "OK", "MessageID": "b7bc2f4a" },
+                { "ErrorCode": 406, "Message": "Address is inactive." },
+            ])))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let outcomes = email_client
+            .send_email_batch(&messages(&recipients, &subject, &content))
+            .await;
+
+        // Assert
+        assert_eq!(outcomes.len(), 2);
+        assert!(assert_ok!(&outcomes[0]).contains("b7bc2f4a"));
+        assert_err!(&outcomes[1]);
+    }
+
+    #[tokio::test]
+    async fn send_email_batch_splits_messages_into_postmark_sized_batches() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = email_client(mock_server.uri());
+        let recipients = emails(POSTMARK_BATCH_LIMIT + 1);
+        let (subject, content) = (subject(), content());
+
+        Mock::given(path("/email/batch"))
+            .respond_with(PostmarkBatchResponder)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let outcomes = email_client
+            .send_email_batch(&messages(&recipients, &subject, &content))
+            .await;
+
+        // Assert
+        assert_eq!(outcomes.len(), POSTMARK_BATCH_LIMIT + 1);
+        assert!(outcomes.iter().all(|outcome| outcome.is_ok()));
+    }
+
+    #[tokio::test]
+    async fn send_email_batch_fails_every_message_if_the_server_returns_500() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = email_client(mock_server.uri());
+        let recipients = emails(3);
+        let (subject, content) = (subject(), content());
+
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let outcomes = email_client
+            .send_email_batch(&messages(&recipients, &subject, &content))
+            .await;
+
+        // Assert
+        assert_eq!(outcomes.len(), 3);
+        assert!(outcomes.iter().all(|outcome| outcome.is_err()));
+    }
+
+    #[tokio::test]
+    async fn send_email_batch_falls_back_to_sequential_requests_for_mailpit() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = EmailClient::new(
+            mock_server.uri(),
+            email(),
+            Secret::new(Faker.fake()),
+            std::time::Duration::from_millis(200),
+            EmailServer::Mailpit,
+        );
+        let recipients = emails(3);
+        let (subject, content) = (subject(), content());
+
+        Mock::given(path("/api/v1/send"))
+            .and(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let outcomes = email_client
+            .send_email_batch(&messages(&recipients, &subject, &content))
+            .await;
+
+        // Assert
+        assert_eq!(outcomes.len(), 3);
+        assert!(outcomes.iter().all(|outcome| outcome.is_ok()));
+    }
+
+    /// Get a test instance of `EmailClient` relaying through the SMTP server on `port`.
+    fn smtp_email_client(port: u16, username: &str) -> EmailClient {
+        let settings = SmtpSettings {
+            host: String::from("127.0.0.1"),
+            password: Secret::new(Faker.fake()),
+            port,
+            tls: SmtpTls::None,
+            username: username.to_string(),
+        };
+        let timeout = std::time::Duration::from_millis(200);
+
+        EmailClient::new(
+            String::from(""),
+            email(),
+            Secret::new(Faker.fake()),
+            timeout,
+            EmailServer::Smtp,
+        )
+        .with_smtp_transport(settings.transport(timeout).unwrap())
+    }
+
+    /// Accept a single SMTP session, answering the message with `data_reply`.
+    /// Resolves to the commands received from the client.
+    async fn smtp_server(data_reply: &'static str) -> (u16, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            let mut commands = vec![];
+            writer.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let reply = match line.split(' ').next().unwrap().to_uppercase().as_str() {
+                    "EHLO" => "250-localhost\r\n250 AUTH PLAIN LOGIN\r\n",
+                    "AUTH" => "235 2.7.0 Authentication successful\r\n",
+                    "DATA" => {
+                        writer.write_all(b"354 End data with .\r\n").await.unwrap();
+                        while let Ok(Some(line)) = lines.next_line().await {
+                            if line == "." {
+                                break;
+                            }
+                            commands.push(line);
+                        }
+                        data_reply
+                    }
+                    "QUIT" => {
+                        writer.write_all(b"221 Bye\r\n").await.unwrap();
+                        commands.push(line);
+                        break;
+                    }
+                    _ => "250 OK\r\n",
+                };
+                commands.push(line);
+                writer.write_all(reply.as_bytes()).await.unwrap();
+            }
+            commands
+        });
+
+        (port, handle)
+    }
+
+    #[tokio::test]
+    async fn send_email_over_smtp_returns_the_server_response() {
+        // Arrange
+        let (port, server) = smtp_server("250 2.0.0 Ok: queued as 4F2A\r\n").await;
+        let email_client = smtp_email_client(port, "");
+        let recipient = email();
+
+        // Act
+        let outcome = email_client
+            .send_email(&recipient, &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        let provider_response = assert_ok!(outcome);
+        assert!(provider_response.starts_with("250"));
+        assert!(provider_response.contains("queued as 4F2A"));
+        let commands = server.await.unwrap();
+        assert!(
+            commands
+                .iter()
+                .any(|c| c.starts_with("RCPT TO") && c.contains(recipient.as_ref()))
+        );
+        assert!(!commands.iter().any(|c| c.starts_with("AUTH")));
+    }
+
+    #[tokio::test]
+    async fn send_email_over_smtp_authenticates_when_a_username_is_configured() {
+        // Arrange
+        let (port, server) = smtp_server("250 OK\r\n").await;
+        let email_client = smtp_email_client(port, "newsletter");
+
+        // Act
+        let outcome = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        assert_ok!(outcome);
+        let commands = server.await.unwrap();
+        assert!(commands.iter().any(|c| c.starts_with("AUTH")));
+    }
+
+    #[tokio::test]
+    async fn send_message_over_smtp_adds_one_click_unsubscribe_headers() {
+        // Arrange
+        let (port, server) = smtp_server("250 OK\r\n").await;
+        let email_client = smtp_email_client(port, "");
+        let (recipient, subject, content) = (email(), subject(), content());
+
+        // Act
+        let outcome = email_client
+            .send_message(&EmailMessage {
+                recipient: &recipient,
+                subject: &subject,
+                html_content: &content,
+                text_content: &content,
+                unsubscribe_url: Some("https://example.com/unsubscribe?token=abc"),
+            })
+            .await;
+
+        // Assert
+        assert_ok!(outcome);
+        let commands = server.await.unwrap();
+        assert!(
+            commands
+                .iter()
+                .any(|c| c == "List-Unsubscribe: <https://example.com/unsubscribe?token=abc>")
+        );
+        assert!(
+            commands
+                .iter()
+                .any(|c| c == "List-Unsubscribe-Post: List-Unsubscribe=One-Click")
+        );
+    }
+
+    #[tokio::test]
+    async fn send_email_over_smtp_fails_if_the_server_rejects_the_message() {
+        // Arrange
+        let (port, _server) = smtp_server("554 5.7.1 Message rejected\r\n").await;
+        let email_client = smtp_email_client(port, "");
+
+        // Act
+        let outcome = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        assert_err!(outcome);
+    }
+
+    #[tokio::test]
+    async fn send_message_adds_one_click_unsubscribe_headers() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = email_client(mock_server.uri());
+        let (recipient, subject, content) = (email(), subject(), content());
+
+        Mock::given(path("/email"))
+            .and(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "Headers": [
+                    {
+                        "Name": "List-Unsubscribe",
+                        "Value": "<https://example.com/unsubscribe?token=abc>"
+                    },
+                    {
+                        "Name": "List-Unsubscribe-Post",
+                        "Value": "List-Unsubscribe=One-Click"
+                    }
+                ]
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let outcome = email_client
+            .send_message(&EmailMessage {
+                recipient: &recipient,
+                subject: &subject,
+                html_content: &content,
+                text_content: &content,
+                unsubscribe_url: Some("https://example.com/unsubscribe?token=abc"),
+            })
+            .await;
+
+        // Assert
+        assert_ok!(outcome);
+    }
+
     #[tokio::test]
     async fn send_email_sends_the_expected_request() {
         // Arrange
@@ -256,6 +900,28 @@ mod tests {
         assert_ok!(outcome);
     }
 
+    #[tokio::test]
+    async fn send_email_returns_the_provider_response_body() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = email_client(mock_server.uri());
+        let provider_response = r#"{"ErrorCode":0,"Message":"OK"}"#;
+
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200).set_body_string(provider_response))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let outcome = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        assert_eq!(assert_ok!(outcome), provider_response);
+    }
+
     #[tokio::test]
     async fn send_email_fails_if_the_server_returns_500() {
         // Arrange
@@ -278,6 +944,65 @@ mod tests {
         assert_err!(outcome);
     }
 
+    #[tokio::test]
+    async fn send_email_is_rate_limited_if_the_server_returns_429() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = email_client(mock_server.uri());
+
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "30"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let outcome = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        match assert_err!(outcome) {
+            EmailClientError::RateLimited { retry_after } => {
+                assert_eq!(retry_after, std::time::Duration::from_secs(30))
+            }
+            e => panic!("Expected a rate limiting error, got {:?}", e),
+        }
+    }
+
+    #[tokio::test]
+    async fn send_email_batch_stops_sending_once_rate_limited() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = EmailClient::new(
+            mock_server.uri(),
+            email(),
+            Secret::new(Faker.fake()),
+            std::time::Duration::from_millis(200),
+            EmailServer::Mailpit,
+        );
+        let recipients = emails(3);
+        let (subject, content) = (subject(), content());
+
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(429))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let outcomes = email_client
+            .send_email_batch(&messages(&recipients, &subject, &content))
+            .await;
+
+        // Assert
+        assert!(
+            outcomes
+                .iter()
+                .all(|outcome| matches!(outcome, Err(EmailClientError::RateLimited { .. })))
+        );
+    }
+
     #[tokio::test]
     async fn send_email_times_out_if_the_server_takes_too_long() {
         // Arrange
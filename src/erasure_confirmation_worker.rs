@@ -0,0 +1,148 @@
+use crate::configuration::WorkerSettings;
+use crate::domain::SubscriberEmail;
+use crate::email_client::EmailClient;
+use crate::issue_delivery_worker::ExecutionOutcome;
+use crate::outbox::{Outbox, outbox_loop};
+use crate::rate_limiter::RateLimiter;
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use tokio::sync::watch;
+use tracing::{Span, field::display};
+use uuid::Uuid;
+
+pub async fn erasure_confirmation_loop(
+    pool: PgPool,
+    email_client: Arc<EmailClient>,
+    rate_limiter: Arc<RateLimiter>,
+    settings: Arc<WorkerSettings>,
+    shutdown: watch::Receiver<bool>,
+) -> Result<(), anyhow::Error> {
+    outbox_loop(
+        |shutdown| {
+            try_send_erasure_confirmation(&pool, &email_client, &rate_limiter, &settings, shutdown)
+        },
+        shutdown,
+    )
+    .await
+}
+
+/// Send the next due confirmation that a reader's personal data has been erased.
+/// Every send takes a token from the global rate limit, and failed sends are retried with the
+/// same backoff as issue deliveries; confirmations to addresses that bounced or complained are
+/// dropped.
+#[tracing::instrument(skip_all, fields(outbox_id=tracing::field::Empty), err)]
+pub async fn try_send_erasure_confirmation(
+    pool: &PgPool,
+    email_client: &EmailClient,
+    rate_limiter: &RateLimiter,
+    settings: &WorkerSettings,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<ExecutionOutcome, anyhow::Error> {
+    let mut transaction = pool.begin().await?;
+    let Some(task) = dequeue_task(&mut transaction).await? else {
+        return Ok(ExecutionOutcome::EmptyQueue);
+    };
+    Span::current().record("outbox_id", display(task.outbox_id));
+    if task.suppressed {
+        tracing::info!("Dropping an erasure confirmation to a suppressed address");
+        OUTBOX.delete(&mut *transaction, task.outbox_id).await?;
+        transaction.commit().await?;
+        return Ok(ExecutionOutcome::TaskCompleted);
+    }
+    let email = match SubscriberEmail::parse(task.email.clone()) {
+        Ok(email) => email,
+        Err(e) => {
+            tracing::error!(
+                error.message = %e,
+                "Dropping an erasure confirmation. The email address is invalid",
+            );
+            OUTBOX.delete(&mut *transaction, task.outbox_id).await?;
+            transaction.commit().await?;
+            return Ok(ExecutionOutcome::TaskCompleted);
+        }
+    };
+    OUTBOX.lease(&mut *transaction, task.outbox_id).await?;
+    transaction.commit().await?;
+    if !rate_limiter.acquire_global(&mut shutdown).await {
+        OUTBOX.release(pool, task.outbox_id).await?;
+        return Ok(ExecutionOutcome::TaskCompleted);
+    }
+    let outcome = email_client
+        .send_email(
+            &email,
+            "Your data has been erased",
+            "Your subscriptions and the personal data we held about you have been erased.",
+            "Your subscriptions and the personal data we held about you have been erased.",
+        )
+        .await;
+    OUTBOX
+        .record_outcome(
+            pool,
+            task.outbox_id,
+            task.n_attempts,
+            outcome,
+            rate_limiter,
+            settings,
+        )
+        .await?;
+    Ok(ExecutionOutcome::TaskCompleted)
+}
+
+/// Queue an erasure confirmation as part of the transaction that erases the reader's data,
+/// so the confirmation is sent if and only if the erasure is committed.
+#[tracing::instrument(skip(transaction, email))]
+pub async fn enqueue_erasure_confirmation(
+    transaction: &mut Transaction<'_, Postgres>,
+    email: &str,
+) -> Result<(), sqlx::Error> {
+    transaction
+        .execute(sqlx::query!(
+            r#"
+            INSERT INTO erasure_confirmation_outbox (outbox_id, email)
+            VALUES ($1, $2)
+            "#,
+            Uuid::new_v4(),
+            email
+        ))
+        .await?;
+    Ok(())
+}
+
+type PgTransaction = Transaction<'static, Postgres>;
+
+const OUTBOX: Outbox = Outbox::new("erasure_confirmation_outbox", "an erasure confirmation");
+
+struct OutboxTask {
+    outbox_id: Uuid,
+    email: String,
+    n_attempts: i32,
+    suppressed: bool,
+}
+
+#[tracing::instrument(skip_all)]
+async fn dequeue_task(
+    transaction: &mut PgTransaction,
+) -> Result<Option<OutboxTask>, anyhow::Error> {
+    let task = sqlx::query_as!(
+        OutboxTask,
+        r#"
+        SELECT
+          outbox_id,
+          email,
+          n_attempts,
+          EXISTS (
+            SELECT 1 FROM suppressed_emails
+            WHERE suppressed_emails.email = lower(erasure_confirmation_outbox.email)
+          ) AS "suppressed!"
+        FROM erasure_confirmation_outbox
+        WHERE next_attempt_at <= now()
+        ORDER BY created_at
+        FOR UPDATE
+        SKIP LOCKED
+        LIMIT 1
+        "#,
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+    Ok(task)
+}
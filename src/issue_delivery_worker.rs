@@ -1,29 +1,165 @@
-use crate::models::{NewsletterIssue, NewsletterIssueEmail};
+use crate::configuration::WorkerSettings;
+use crate::confirmation_email_worker::confirmation_email_loop;
+use crate::domain::SubscriberEmail;
+use crate::email_client::{EmailClient, EmailClientError, EmailMessage};
+use crate::erasure_confirmation_worker::erasure_confirmation_loop;
+use crate::models::{
+    DeliveryOutcome, NewsletterIssue, NewsletterIssueEmail, WorkerStatus, enqueue_delivery_tasks,
+};
+use crate::preference_link_worker::preference_link_loop;
+use crate::rate_limiter::RateLimiter;
+use crate::shutdown::sleep_unless_shutdown;
+use crate::subscriber_retention_worker::retention_loop;
 use crate::{configuration::Settings, startup::get_connection_pool};
-use crate::{domain::SubscriberEmail, email_client::EmailClient};
+use anyhow::Context;
+use chrono::Utc;
 use sqlx::{PgPool, Postgres, Transaction};
-use std::time::Duration;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
 use tracing::{Span, field::display};
 use uuid::Uuid;
 
-pub async fn run_worker_until_stopped(configuration: Settings) -> Result<(), anyhow::Error> {
-    let connection_pool = get_connection_pool(&configuration.database);
-    let email_client = configuration.email_client.client();
-    worker_loop(connection_pool, email_client).await
+pub async fn run_worker_until_stopped(
+    configuration: Settings,
+    shutdown: watch::Receiver<bool>,
+) -> Result<(), anyhow::Error> {
+    run_workers(
+        get_connection_pool(&configuration.database),
+        configuration.email_client.client(),
+        configuration.worker,
+        configuration.application.base_url,
+        shutdown,
+    )
+    .await
 }
 
-async fn worker_loop(pool: PgPool, email_client: EmailClient) -> Result<(), anyhow::Error> {
-    loop {
-        match try_execute_task(&pool, &email_client).await {
-            Ok(ExecutionOutcome::EmptyQueue) => {
-                tokio::time::sleep(Duration::from_secs(10)).await;
-            }
-            Err(_) => {
-                tokio::time::sleep(Duration::from_secs(1)).await;
-            }
-            Ok(ExecutionOutcome::TaskCompleted) => {}
+/// Run the delivery workers, the confirmation email, preference link and erasure confirmation
+/// senders, the scheduler and the retention job until `shutdown` flips to `true`.
+/// Shutdown is observed between batches and while waiting for the rate limiter: a batch
+/// that is still waiting is released back to the queue unsent, while a batch handed to the
+/// email client always has its outcomes recorded before the workers exit.
+/// Every delivery loop records a heartbeat per iteration, reported by the readiness check.
+pub async fn run_workers(
+    pool: PgPool,
+    email_client: EmailClient,
+    settings: WorkerSettings,
+    base_url: String,
+    shutdown: watch::Receiver<bool>,
+) -> Result<(), anyhow::Error> {
+    let base_url = Arc::new(base_url);
+    let email_client = Arc::new(email_client);
+    let settings = Arc::new(settings);
+    let rate_limiter = Arc::new(RateLimiter::new(&settings));
+    let worker_id = Uuid::new_v4();
+    let mut workers = JoinSet::new();
+    for _ in 0..settings.concurrency.max(1) {
+        workers.spawn(worker_loop(
+            worker_id,
+            pool.clone(),
+            email_client.clone(),
+            rate_limiter.clone(),
+            settings.clone(),
+            base_url.clone(),
+            shutdown.clone(),
+        ));
+    }
+    workers.spawn(confirmation_email_loop(
+        pool.clone(),
+        email_client.clone(),
+        rate_limiter.clone(),
+        settings.clone(),
+        base_url.clone(),
+        shutdown.clone(),
+    ));
+    workers.spawn(preference_link_loop(
+        pool.clone(),
+        email_client.clone(),
+        rate_limiter.clone(),
+        settings.clone(),
+        base_url,
+        shutdown.clone(),
+    ));
+    workers.spawn(erasure_confirmation_loop(
+        pool.clone(),
+        email_client,
+        rate_limiter,
+        settings.clone(),
+        shutdown.clone(),
+    ));
+    workers.spawn(scheduler_loop(
+        pool.clone(),
+        settings.clone(),
+        shutdown.clone(),
+    ));
+    workers.spawn(retention_loop(pool.clone(), settings, shutdown));
+    while let Some(outcome) = workers.join_next().await {
+        outcome.context("A delivery worker panicked.")??;
+    }
+    WorkerStatus::clear_heartbeat(worker_id, &pool)
+        .await
+        .context("Failed to clear the worker heartbeat.")?;
+    tracing::info!("Delivery workers have drained and stopped");
+    Ok(())
+}
+
+async fn worker_loop(
+    worker_id: Uuid,
+    pool: PgPool,
+    email_client: Arc<EmailClient>,
+    rate_limiter: Arc<RateLimiter>,
+    settings: Arc<WorkerSettings>,
+    base_url: Arc<String>,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<(), anyhow::Error> {
+    while !*shutdown.borrow() {
+        if let Err(e) = WorkerStatus::record_heartbeat(worker_id, &pool).await {
+            tracing::warn!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "Failed to record the worker heartbeat",
+            );
+        }
+        let wait = match try_execute_task(
+            &pool,
+            &email_client,
+            &rate_limiter,
+            &settings,
+            &base_url,
+            shutdown.clone(),
+        )
+        .await
+        {
+            Ok(ExecutionOutcome::EmptyQueue) => Duration::from_secs(10),
+            Err(_) => Duration::from_secs(1),
+            Ok(ExecutionOutcome::TaskCompleted) => continue,
+        };
+        if sleep_unless_shutdown(wait, &mut shutdown).await {
+            break;
+        }
+    }
+    Ok(())
+}
+
+async fn scheduler_loop(
+    pool: PgPool,
+    settings: Arc<WorkerSettings>,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<(), anyhow::Error> {
+    while !*shutdown.borrow() {
+        let wait = match try_publish_scheduled_issue(&pool).await {
+            Ok(ExecutionOutcome::EmptyQueue) => settings.scheduler_interval(),
+            Err(_) => Duration::from_secs(1),
+            Ok(ExecutionOutcome::TaskCompleted) => continue,
+        };
+        if sleep_unless_shutdown(wait, &mut shutdown).await {
+            break;
         }
     }
+    Ok(())
 }
 
 pub enum ExecutionOutcome {
@@ -31,94 +167,508 @@ pub enum ExecutionOutcome {
     EmptyQueue,
 }
 
-#[tracing::instrument(
-    skip_all,
-    fields(
-        newsletter_issue_id=tracing::field::Empty,
-        subscriber_email=tracing::field::Empty
-    ),
-    err
-)]
+/// Dequeue a batch of due deliveries and attempt each of them.
+/// The batch is claimed with `SKIP LOCKED` so that concurrent workers never pick up the
+/// same rows, every issue is rendered at most once per batch and the emails are handed
+/// to the email client in a single `send_email_batch` call once the rate limiter allows it.
+/// The claim is committed as a lease of `DELIVERY_LEASE` before waiting for the rate limiter,
+/// and each outcome is then committed on its own, so no row lock is held while waiting or
+/// across the network call and a failure to record one outcome can't roll back the others.
+/// If `shutdown` is requested while waiting, the lease is released without sending.
+/// Every email links to `base_url` for one-click unsubscription; deliveries to subscribers
+/// who are no longer confirmed are dropped.
+#[tracing::instrument(skip_all, fields(batch_size=tracing::field::Empty), err)]
 pub async fn try_execute_task(
     pool: &PgPool,
     email_client: &EmailClient,
+    rate_limiter: &RateLimiter,
+    settings: &WorkerSettings,
+    base_url: &str,
+    mut shutdown: watch::Receiver<bool>,
 ) -> Result<ExecutionOutcome, anyhow::Error> {
-    let task = dequeue_task(pool).await?;
-    if task.is_none() {
+    let (mut transaction, tasks) = dequeue_batch(pool, settings.batch_size).await?;
+    if tasks.is_empty() {
         return Ok(ExecutionOutcome::EmptyQueue);
     }
-    let (transaction, issue_id, email) = task.unwrap();
-    Span::current()
-        .record("newsletter_issue_id", display(issue_id))
-        .record("subscriber_email", display(&email));
-    match SubscriberEmail::parse(email.clone()) {
-        Ok(email) => {
-            let issue = NewsletterIssue::find_by_newsletter_issue_id(issue_id, pool).await?;
-            let issue: NewsletterIssueEmail = issue.into();
-            if let Err(e) = email_client
-                .send_email(
-                    &email,
-                    &issue.title,
-                    &issue.html_content,
-                    &issue.text_content,
+    Span::current().record("batch_size", tasks.len());
+    let mut issues: HashMap<Uuid, NewsletterIssueEmail> = HashMap::new();
+    for task in &tasks {
+        if let Entry::Vacant(entry) = issues.entry(task.newsletter_issue_id) {
+            let issue =
+                NewsletterIssue::find_by_newsletter_issue_id(task.newsletter_issue_id, pool)
+                    .await?;
+            entry.insert(issue.into());
+        }
+    }
+    let mut deliverable: Vec<(&DeliveryTask, SubscriberEmail, PersonalisedContent)> = vec![];
+    for task in &tasks {
+        match (
+            SubscriberEmail::parse(task.subscriber_email.clone()),
+            &task.unsubscribe_token,
+        ) {
+            (Ok(email), Some(unsubscribe_token)) => {
+                let content = PersonalisedContent::new(
+                    &issues[&task.newsletter_issue_id],
+                    base_url,
+                    unsubscribe_token,
+                );
+                deliverable.push((task, email, content));
+            }
+            (Ok(_), None) => skip_unsubscribed_task(&mut transaction, task).await?,
+            (Err(e), _) => skip_invalid_task(&mut transaction, task, &e).await?,
+        }
+    }
+    let messages: Vec<EmailMessage> = deliverable
+        .iter()
+        .map(|(task, email, content)| EmailMessage {
+            recipient: email,
+            subject: &issues[&task.newsletter_issue_id].title,
+            html_content: &content.html_content,
+            text_content: &content.text_content,
+            unsubscribe_url: Some(&content.unsubscribe_url),
+        })
+        .collect();
+    let leased: Vec<&DeliveryTask> = deliverable.iter().map(|(task, _, _)| *task).collect();
+    lease_tasks(&mut transaction, &leased).await?;
+    transaction.commit().await?;
+    for task in &leased {
+        let user_id = issues[&task.newsletter_issue_id].user_id;
+        if !rate_limiter.acquire(user_id, &mut shutdown).await {
+            tracing::info!("Releasing the batch unsent to shut down");
+            release_tasks(pool, &leased).await?;
+            return Ok(ExecutionOutcome::TaskCompleted);
+        }
+    }
+    let outcomes = email_client.send_email_batch(&messages).await;
+    let mut recorded = Ok(());
+    for (task, outcome) in leased.into_iter().zip(outcomes) {
+        if let Err(e) = commit_task_outcome(pool, task, outcome, rate_limiter, settings).await {
+            tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "Failed to record a delivery outcome. \
+                    The delivery will be attempted again once its lease expires.",
+            );
+            recorded = Err(e);
+        }
+    }
+    recorded?;
+    Ok(ExecutionOutcome::TaskCompleted)
+}
+
+/// The body of an issue email addressed to a single subscriber, ending with their
+/// unsubscribe link.
+struct PersonalisedContent {
+    html_content: String,
+    text_content: String,
+    unsubscribe_url: String,
+}
+
+impl PersonalisedContent {
+    fn new(issue: &NewsletterIssueEmail, base_url: &str, unsubscribe_token: &str) -> Self {
+        let unsubscribe_url = format!(
+            "{}/subscriptions/unsubscribe?unsubscribe_token={}",
+            base_url, unsubscribe_token
+        );
+        Self {
+            html_content: format!(
+                "{}<p><a href=\"{}\">Unsubscribe</a></p>",
+                issue.html_content, unsubscribe_url
+            ),
+            text_content: format!("{}\n\nUnsubscribe: {}", issue.text_content, unsubscribe_url),
+            unsubscribe_url,
+        }
+    }
+}
+
+#[tracing::instrument(
+    skip_all,
+    fields(
+        newsletter_issue_id=%task.newsletter_issue_id,
+        subscriber_email=%task.subscriber_email
+    )
+)]
+async fn skip_unsubscribed_task(
+    transaction: &mut PgTransaction,
+    task: &DeliveryTask,
+) -> Result<(), anyhow::Error> {
+    tracing::info!("Dropping a delivery to a subscriber who is no longer confirmed");
+    log_delivery(transaction, task, DeliveryOutcome::Unsubscribed, None).await?;
+    delete_task(transaction, task).await
+}
+
+#[tracing::instrument(
+    skip_all,
+    fields(
+        newsletter_issue_id=%task.newsletter_issue_id,
+        subscriber_email=%task.subscriber_email
+    )
+)]
+async fn skip_invalid_task(
+    transaction: &mut PgTransaction,
+    task: &DeliveryTask,
+    e: &str,
+) -> Result<(), anyhow::Error> {
+    tracing::error!(
+        error.cause_chain = ?e,
+        error.message = %e,
+        "Skipping a confirmed subscriber. \
+            Their stored contact details are invalid",
+    );
+    log_delivery(transaction, task, DeliveryOutcome::Failed, None).await?;
+    move_task_to_failed_deliveries(transaction, task, task.n_attempts, e).await
+}
+
+/// Record the outcome of a single delivery in a transaction of its own.
+async fn commit_task_outcome(
+    pool: &PgPool,
+    task: &DeliveryTask,
+    outcome: Result<String, EmailClientError>,
+    rate_limiter: &RateLimiter,
+    settings: &WorkerSettings,
+) -> Result<(), anyhow::Error> {
+    let mut transaction = pool.begin().await?;
+    record_task_outcome(&mut transaction, task, outcome, rate_limiter, settings).await?;
+    transaction.commit().await?;
+    Ok(())
+}
+
+#[tracing::instrument(
+    skip_all,
+    fields(
+        newsletter_issue_id=%task.newsletter_issue_id,
+        subscriber_email=%task.subscriber_email,
+        n_attempts=task.n_attempts
+    )
+)]
+async fn record_task_outcome(
+    transaction: &mut PgTransaction,
+    task: &DeliveryTask,
+    outcome: Result<String, EmailClientError>,
+    rate_limiter: &RateLimiter,
+    settings: &WorkerSettings,
+) -> Result<(), anyhow::Error> {
+    match outcome {
+        Ok(provider_response) => {
+            log_delivery(
+                transaction,
+                task,
+                DeliveryOutcome::Sent,
+                Some(&provider_response),
+            )
+            .await?;
+            delete_task(transaction, task).await
+        }
+        Err(e @ EmailClientError::RateLimited { retry_after }) => {
+            tracing::warn!(
+                error.message = %e,
+                "The email provider is throttling deliveries. \
+                    Pausing without consuming an attempt.",
+            );
+            rate_limiter.pause(retry_after, Instant::now());
+            log_delivery(
+                transaction,
+                task,
+                DeliveryOutcome::Throttled,
+                Some(&e.to_string()),
+            )
+            .await?;
+            reschedule_task(
+                transaction,
+                task,
+                task.n_attempts,
+                &e.to_string(),
+                retry_after,
+            )
+            .await
+        }
+        Err(e) => {
+            let n_attempts = task.n_attempts + 1;
+            if n_attempts < settings.max_delivery_attempts {
+                tracing::warn!(
+                    error.cause_chain = ?e,
+                    error.message = %e,
+                    "Failed to deliver issue to a confirmed subscriber. \
+                        Retrying later.",
+                );
+                log_delivery(
+                    transaction,
+                    task,
+                    DeliveryOutcome::Retrying,
+                    Some(&e.to_string()),
                 )
-                .await
-            {
+                .await?;
+                let retry_delay = settings.retry_delay(n_attempts);
+                reschedule_task(transaction, task, n_attempts, &e.to_string(), retry_delay).await
+            } else {
                 tracing::error!(
                     error.cause_chain = ?e,
                     error.message = %e,
                     "Failed to deliver issue to a confirmed subscriber. \
-                        Skipping.",
+                        Giving up after {} attempts.",
+                    n_attempts
                 );
+                log_delivery(
+                    transaction,
+                    task,
+                    DeliveryOutcome::Failed,
+                    Some(&e.to_string()),
+                )
+                .await?;
+                move_task_to_failed_deliveries(transaction, task, n_attempts, &e.to_string()).await
             }
         }
+    }
+}
+
+/// Publish the next issue whose scheduled time has passed and enqueue its deliveries.
+/// Issues that no longer pass publish validation (e.g. edited after being scheduled)
+/// are unscheduled instead.
+#[tracing::instrument(skip_all, fields(newsletter_issue_id=tracing::field::Empty), err)]
+pub async fn try_publish_scheduled_issue(pool: &PgPool) -> Result<ExecutionOutcome, anyhow::Error> {
+    let mut transaction = pool.begin().await?;
+    let Some(issue) = NewsletterIssue::find_next_due_for_publish_txn(&mut transaction).await?
+    else {
+        return Ok(ExecutionOutcome::EmptyQueue);
+    };
+    let newsletter_issue_id = issue.newsletter_issue_id;
+    let user_id = issue.user_id;
+    Span::current().record("newsletter_issue_id", display(newsletter_issue_id));
+    match issue.validate_for_publish() {
+        Ok(issue) => {
+            let segment = issue.scheduled_segment_txn(&mut transaction).await?;
+            issue.publish_newsletter(&mut transaction).await?;
+            enqueue_delivery_tasks(
+                &mut transaction,
+                newsletter_issue_id,
+                &user_id,
+                segment.as_ref(),
+            )
+            .await?;
+        }
         Err(e) => {
             tracing::error!(
-                error.cause_chain = ?e,
                 error.message = %e,
-                "Skipping a confirmed subscriber. \
-                    Their stored contact details are invalid",
+                "Unscheduling a newsletter issue that can no longer be published."
             );
+            sqlx::query!(
+                r#"
+                UPDATE newsletter_issues
+                SET
+                  scheduled_for = NULL,
+                  scheduled_segment_tags = NULL,
+                  scheduled_segment_operator = NULL
+                WHERE newsletter_issue_id = $1
+                "#,
+                newsletter_issue_id
+            )
+            .execute(&mut *transaction)
+            .await?;
         }
     }
-    delete_task(transaction, issue_id, &email).await?;
+    transaction.commit().await?;
     Ok(ExecutionOutcome::TaskCompleted)
 }
 
 type PgTransaction = Transaction<'static, Postgres>;
 
+/// How long a dequeued delivery stays claimed while its email is in flight. Deliveries
+/// whose outcome could not be recorded are attempted again once it expires.
+const DELIVERY_LEASE: Duration = Duration::from_secs(10 * 60);
+
+struct DeliveryTask {
+    newsletter_issue_id: Uuid,
+    subscriber_email: String,
+    n_attempts: i32,
+    unsubscribe_token: Option<String>,
+}
+
 #[tracing::instrument(skip_all)]
-async fn dequeue_task(
+async fn dequeue_batch(
     pool: &PgPool,
-) -> Result<Option<(PgTransaction, Uuid, String)>, anyhow::Error> {
+    batch_size: i64,
+) -> Result<(PgTransaction, Vec<DeliveryTask>), anyhow::Error> {
     let mut transaction = pool.begin().await?;
-    let r = sqlx::query!(
+    let tasks = sqlx::query_as!(
+        DeliveryTask,
         r#"
-        SELECT newsletter_issue_id, subscriber_email
+        SELECT
+          issue_delivery_queue.newsletter_issue_id,
+          issue_delivery_queue.subscriber_email,
+          issue_delivery_queue.n_attempts,
+          (
+            SELECT subscriptions.unsubscribe_token
+            FROM subscriptions
+            JOIN newsletter_issues
+              ON subscriptions.user_id = newsletter_issues.user_id
+            WHERE newsletter_issues.newsletter_issue_id = issue_delivery_queue.newsletter_issue_id
+              AND subscriptions.email = issue_delivery_queue.subscriber_email
+              AND subscriptions.status = 'confirmed'
+          ) AS unsubscribe_token
         FROM issue_delivery_queue
+        WHERE next_attempt_at <= now()
+        ORDER BY newsletter_issue_id
         FOR UPDATE
         SKIP LOCKED
-        LIMIT 1
+        LIMIT $1
         "#,
+        batch_size
     )
-    .fetch_optional(&mut *transaction)
+    .fetch_all(&mut *transaction)
     .await?;
-    if let Some(r) = r {
-        Ok(Some((
-            transaction,
-            r.newsletter_issue_id,
-            r.subscriber_email,
-        )))
-    } else {
-        Ok(None)
-    }
+    Ok((transaction, tasks))
+}
+
+/// Push back the next attempt of the deliveries about to be sent, so that other workers
+/// leave them alone once the dequeue transaction releases its row locks.
+#[tracing::instrument(skip_all)]
+async fn lease_tasks(
+    transaction: &mut PgTransaction,
+    tasks: &[&DeliveryTask],
+) -> Result<(), anyhow::Error> {
+    let newsletter_issue_ids: Vec<Uuid> = tasks.iter().map(|t| t.newsletter_issue_id).collect();
+    let subscriber_emails: Vec<String> = tasks.iter().map(|t| t.subscriber_email.clone()).collect();
+    let leased_until = Utc::now() + DELIVERY_LEASE;
+    sqlx::query!(
+        r#"
+        UPDATE issue_delivery_queue
+        SET next_attempt_at = $3
+        FROM UNNEST($1::uuid[], $2::text[]) AS leased(newsletter_issue_id, subscriber_email)
+        WHERE
+            issue_delivery_queue.newsletter_issue_id = leased.newsletter_issue_id AND
+            issue_delivery_queue.subscriber_email = leased.subscriber_email
+        "#,
+        &newsletter_issue_ids,
+        &subscriber_emails,
+        leased_until
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
+
+/// Make leased deliveries due again, e.g. when shutting down before they could be sent.
+#[tracing::instrument(skip_all)]
+async fn release_tasks(pool: &PgPool, tasks: &[&DeliveryTask]) -> Result<(), anyhow::Error> {
+    let newsletter_issue_ids: Vec<Uuid> = tasks.iter().map(|t| t.newsletter_issue_id).collect();
+    let subscriber_emails: Vec<String> = tasks.iter().map(|t| t.subscriber_email.clone()).collect();
+    sqlx::query!(
+        r#"
+        UPDATE issue_delivery_queue
+        SET next_attempt_at = now()
+        FROM UNNEST($1::uuid[], $2::text[]) AS leased(newsletter_issue_id, subscriber_email)
+        WHERE
+            issue_delivery_queue.newsletter_issue_id = leased.newsletter_issue_id AND
+            issue_delivery_queue.subscriber_email = leased.subscriber_email
+        "#,
+        &newsletter_issue_ids,
+        &subscriber_emails
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+async fn log_delivery(
+    transaction: &mut PgTransaction,
+    task: &DeliveryTask,
+    outcome: DeliveryOutcome,
+    provider_response: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO delivery_log (
+            delivery_log_id,
+            newsletter_issue_id,
+            subscriber_email,
+            outcome,
+            provider_response,
+            logged_at
+        )
+        VALUES ($1, $2, $3, $4, $5, now())
+        "#,
+        Uuid::new_v4(),
+        task.newsletter_issue_id,
+        task.subscriber_email,
+        outcome.as_str(),
+        provider_response
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+async fn reschedule_task(
+    transaction: &mut PgTransaction,
+    task: &DeliveryTask,
+    n_attempts: i32,
+    last_error: &str,
+    retry_delay: Duration,
+) -> Result<(), anyhow::Error> {
+    let next_attempt_at = Utc::now() + retry_delay;
+    sqlx::query!(
+        r#"
+        UPDATE issue_delivery_queue
+        SET
+            n_attempts = $3,
+            last_error = $4,
+            next_attempt_at = $5
+        WHERE
+            newsletter_issue_id = $1 AND
+            subscriber_email = $2
+        "#,
+        task.newsletter_issue_id,
+        task.subscriber_email,
+        n_attempts,
+        last_error,
+        next_attempt_at
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+async fn move_task_to_failed_deliveries(
+    transaction: &mut PgTransaction,
+    task: &DeliveryTask,
+    n_attempts: i32,
+    failure_reason: &str,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO failed_issue_deliveries (
+            failed_delivery_id,
+            newsletter_issue_id,
+            subscriber_email,
+            n_attempts,
+            failure_reason,
+            failed_at
+        )
+        VALUES ($1, $2, $3, $4, $5, now())
+        ON CONFLICT (newsletter_issue_id, subscriber_email) DO UPDATE
+        SET
+            n_attempts = EXCLUDED.n_attempts,
+            failure_reason = EXCLUDED.failure_reason,
+            failed_at = EXCLUDED.failed_at
+        "#,
+        Uuid::new_v4(),
+        task.newsletter_issue_id,
+        task.subscriber_email,
+        n_attempts,
+        failure_reason
+    )
+    .execute(&mut **transaction)
+    .await?;
+    delete_task(transaction, task).await
 }
 
 #[tracing::instrument(skip_all)]
 async fn delete_task(
-    mut transaction: PgTransaction,
-    issue_id: Uuid,
-    email: &str,
+    transaction: &mut PgTransaction,
+    task: &DeliveryTask,
 ) -> Result<(), anyhow::Error> {
     sqlx::query!(
         r#"
@@ -127,11 +677,10 @@ async fn delete_task(
             newsletter_issue_id = $1 AND
             subscriber_email = $2 
         "#,
-        issue_id,
-        email
+        task.newsletter_issue_id,
+        task.subscriber_email
     )
-    .execute(&mut *transaction)
+    .execute(&mut **transaction)
     .await?;
-    transaction.commit().await?;
     Ok(())
 }
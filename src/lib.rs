@@ -2,13 +2,20 @@ pub mod authentication;
 pub mod challenge;
 pub mod clients;
 pub mod configuration;
+pub mod confirmation_email_worker;
 pub mod domain;
 pub mod email_client;
+pub mod erasure_confirmation_worker;
 pub mod idempotency;
 pub mod issue_delivery_worker;
 pub mod models;
+pub mod outbox;
+pub mod preference_link_worker;
+pub mod rate_limiter;
 pub mod routes;
 pub mod session_state;
+pub mod shutdown;
 pub mod startup;
+pub mod subscriber_retention_worker;
 pub mod telemetry;
 pub mod utils;
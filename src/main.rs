@@ -1,5 +1,6 @@
 use newsletter_api::configuration::get_configuration;
 use newsletter_api::issue_delivery_worker::run_worker_until_stopped;
+use newsletter_api::shutdown::{shutdown_channel, shutdown_signal};
 use newsletter_api::startup::Application;
 use newsletter_api::telemetry::{get_subscriber, init_subscriber};
 use std::fmt::{Debug, Display};
@@ -11,13 +12,30 @@ async fn main() -> anyhow::Result<()> {
     init_subscriber(subscriber);
 
     let configuration = get_configuration().expect("Failed to read configuration.");
+    let (shutdown_sender, shutdown) = shutdown_channel();
     let application = Application::build(configuration.clone()).await?;
-    let application_task = tokio::spawn(application.run_until_stopped());
-    let worker_task = tokio::spawn(run_worker_until_stopped(configuration));
+    let server_handle = application.handle();
+    let mut application_task = tokio::spawn(application.run_until_stopped());
+    let mut worker_task = tokio::spawn(run_worker_until_stopped(configuration, shutdown));
 
     tokio::select! {
-        o = application_task => report_exit("API", o),
-        o = worker_task =>  report_exit("Background worker", o),
+        o = &mut application_task => report_exit("API", o),
+        o = &mut worker_task => report_exit("Background worker", o),
+        _ = shutdown_signal() => {
+            tracing::info!("Shutdown signal received, draining in-flight work");
+            // Stop accepting connections and let in-flight requests and delivery batches finish.
+            let _ = shutdown_sender.send(true);
+            let (api, worker) = tokio::join!(
+                async {
+                    server_handle.stop(true).await;
+                    application_task.await
+                },
+                worker_task
+            );
+            report_exit("API", api);
+            report_exit("Background worker", worker);
+            tracing::info!("Shutdown complete");
+        }
     };
 
     Ok(())
@@ -0,0 +1,229 @@
+use crate::domain::confirmation_email::{
+    Body, CONFIRMATION_LINK_PLACEHOLDER, SUBSCRIBER_NAME_PLACEHOLDER, Subject,
+    WRITER_NAME_PLACEHOLDER,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::sync::LazyLock;
+use uuid::Uuid;
+use voca_rs::{escape, strip};
+
+/// Matches the links `markdown::to_html` renders, capturing the URL and the link text.
+static HTML_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"<a href="([^"]*)">(.*?)</a>"#).unwrap());
+
+/// The email a writer's new subscribers receive, as markdown with placeholders.
+/// Writers who haven't customised it get the default template.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ConfirmationEmailTemplate {
+    pub body: String,
+    pub subject: String,
+}
+
+impl Default for ConfirmationEmailTemplate {
+    fn default() -> Self {
+        Self {
+            body: format!(
+                "Welcome to {}'s newsletter!\n\n[Click here to confirm your subscription.]({})",
+                WRITER_NAME_PLACEHOLDER, CONFIRMATION_LINK_PLACEHOLDER
+            ),
+            subject: String::from("Welcome!"),
+        }
+    }
+}
+
+impl ConfirmationEmailTemplate {
+    pub fn validate(self) -> Result<Self, String> {
+        let subject = Subject::parse(self.subject)?.as_ref().to_string();
+        let body = Body::parse(self.body)?.as_ref().to_string();
+
+        Ok(Self { body, subject })
+    }
+
+    pub async fn find_by_user_id(user_id: Uuid, pool: &PgPool) -> Result<Self, sqlx::Error> {
+        let template = sqlx::query_as!(
+            ConfirmationEmailTemplate,
+            "SELECT body, subject FROM confirmation_email_templates WHERE user_id = $1",
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(template.unwrap_or_default())
+    }
+
+    pub async fn find_by_user_id_txn(
+        user_id: Uuid,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Self, sqlx::Error> {
+        let template = sqlx::query_as!(
+            ConfirmationEmailTemplate,
+            "SELECT body, subject FROM confirmation_email_templates WHERE user_id = $1",
+            user_id
+        )
+        .fetch_optional(&mut **transaction)
+        .await?;
+
+        Ok(template.unwrap_or_default())
+    }
+
+    pub async fn update(&self, user_id: Uuid, pool: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+              INSERT INTO confirmation_email_templates (user_id, subject, body)
+              VALUES ($1, $2, $3)
+              ON CONFLICT (user_id) DO UPDATE
+              SET subject = EXCLUDED.subject, body = EXCLUDED.body, updated_at = now()
+            "#,
+            user_id,
+            self.subject,
+            self.body
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Go back to the default template.
+    pub async fn delete(user_id: Uuid, pool: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "DELETE FROM confirmation_email_templates WHERE user_id = $1",
+            user_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub fn render(
+        &self,
+        subscriber_name: &str,
+        writer_name: &str,
+        confirmation_link: &str,
+    ) -> ConfirmationEmailContent {
+        let fill = |s: &str, subscriber_name: &str, writer_name: &str| {
+            s.replace(SUBSCRIBER_NAME_PLACEHOLDER, subscriber_name)
+                .replace(WRITER_NAME_PLACEHOLDER, writer_name)
+                .replace(CONFIRMATION_LINK_PLACEHOLDER, confirmation_link)
+        };
+        // Names are chosen by readers and writers, so they must render as text, never as
+        // markdown or HTML.
+        let html_content = markdown::to_html(&fill(
+            &self.body,
+            &escape_markdown(subscriber_name),
+            &escape_markdown(writer_name),
+        ));
+        // Stripping the tags would drop the confirmation link, so keep link targets as text.
+        let text_content = HTML_LINK.replace_all(&html_content, |captures: &regex::Captures| {
+            if captures[1] == captures[2] {
+                captures[1].to_string()
+            } else {
+                format!("{} ({})", &captures[2], &captures[1])
+            }
+        });
+        let text_content = escape::unescape_html(strip::strip_tags(&text_content).trim());
+
+        ConfirmationEmailContent {
+            html_content,
+            subject: fill(&self.subject, subscriber_name, writer_name),
+            text_content,
+        }
+    }
+}
+
+/// Backslash-escape every ASCII punctuation character, which CommonMark then renders as is,
+/// with `<`, `>`, `&` and `"` as HTML entities.
+fn escape_markdown(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c.is_ascii_punctuation() {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// A confirmation email ready to be sent to one subscriber.
+#[derive(Debug)]
+pub struct ConfirmationEmailContent {
+    pub html_content: String,
+    pub subject: String,
+    pub text_content: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::ConfirmationEmailTemplate;
+
+    #[test]
+    fn placeholders_are_filled_in_both_html_and_plain_text() {
+        let template = ConfirmationEmailTemplate {
+            body: String::from(
+                "Hi **{{subscriber_name}}**,\n\n[Join {{writer_name}}]({{confirmation_link}})",
+            ),
+            subject: String::from("Confirm your subscription to {{writer_name}}"),
+        };
+
+        let email = template.render("Ursula", "Octavia", "https://example.com/confirm");
+
+        assert_eq!(email.subject, "Confirm your subscription to Octavia");
+        assert_eq!(
+            email.html_content,
+            "<p>Hi <strong>Ursula</strong>,</p>\n\
+             <p><a href=\"https://example.com/confirm\">Join Octavia</a></p>"
+        );
+        assert_eq!(
+            email.text_content,
+            "Hi Ursula,\nJoin Octavia (https://example.com/confirm)"
+        );
+    }
+
+    #[test]
+    fn names_are_rendered_as_text_rather_than_markdown_or_html() {
+        let template = ConfirmationEmailTemplate {
+            body: String::from("Hi {{subscriber_name}}, {{confirmation_link}}"),
+            subject: String::from("Welcome {{subscriber_name}}"),
+        };
+        let name = "<img src=x onerror=alert(1)> [**click**](https://evil.example)";
+
+        let email = template.render(name, "Octavia", "link");
+
+        assert_eq!(
+            email.html_content,
+            "<p>Hi &lt;img src=x onerror=alert(1)&gt; [**click**](https://evil.example), link</p>"
+        );
+        assert_eq!(email.text_content, format!("Hi {}, link", name));
+        assert_eq!(email.subject, format!("Welcome {}", name));
+    }
+
+    #[test]
+    fn the_default_template_contains_the_confirmation_link_once_per_format() {
+        let link = "https://example.com/confirm";
+
+        let email = ConfirmationEmailTemplate::default().render("Ursula", "Octavia", link);
+
+        assert_eq!(email.html_content.matches(link).count(), 1);
+        assert_eq!(email.text_content.matches(link).count(), 1);
+    }
+
+    #[test]
+    fn the_default_template_is_valid() {
+        assert!(ConfirmationEmailTemplate::default().validate().is_ok());
+    }
+
+    #[test]
+    fn html_entities_are_unescaped_in_plain_text() {
+        let template = ConfirmationEmailTemplate {
+            body: String::from("Tom & Jerry {{confirmation_link}}"),
+            subject: String::from("Welcome!"),
+        };
+
+        let email = template.render("Ursula", "Octavia", "link");
+
+        assert_eq!(email.text_content, "Tom & Jerry link");
+    }
+}
@@ -0,0 +1,327 @@
+use crate::domain::SubscriberTag;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// How the tags of a `Segment` are combined.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TagOperator {
+    /// Subscribers carrying every tag.
+    #[default]
+    And,
+    /// Subscribers carrying at least one of the tags.
+    Or,
+}
+
+impl TagOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TagOperator::And => "and",
+            TagOperator::Or => "or",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "and" => Ok(TagOperator::And),
+            "or" => Ok(TagOperator::Or),
+            other => Err(format!("{} is not a valid tag operator.", other)),
+        }
+    }
+}
+
+/// The subset of a writer's confirmed subscribers an issue is delivered to.
+#[derive(Debug)]
+pub struct Segment {
+    operator: TagOperator,
+    tags: Vec<SubscriberTag>,
+}
+
+impl Segment {
+    pub fn parse(tags: Vec<String>, operator: TagOperator) -> Result<Self, String> {
+        let tags = SubscriberTag::parse_all(tags)?;
+        if tags.is_empty() {
+            return Err(String::from("A segment must contain at least one tag."));
+        }
+
+        Ok(Self { operator, tags })
+    }
+
+    /// Rebuild the segment saved alongside a scheduled issue, if there is one.
+    pub fn parse_saved(
+        tags: Option<Vec<String>>,
+        operator: Option<String>,
+    ) -> Result<Option<Self>, String> {
+        match (tags, operator) {
+            (Some(tags), Some(operator)) => {
+                Self::parse(tags, TagOperator::parse(&operator)?).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn operator(&self) -> TagOperator {
+        self.operator
+    }
+
+    pub fn tag_names(&self) -> Vec<String> {
+        self.tags.iter().map(|t| t.as_ref().to_owned()).collect()
+    }
+}
+
+/// Queue a delivery of a newly published issue to every confirmed subscriber of its writer,
+/// or only to those in `segment` when one is given. Suppressed addresses are skipped.
+#[tracing::instrument(skip_all)]
+pub async fn enqueue_delivery_tasks(
+    transaction: &mut Transaction<'_, Postgres>,
+    newsletter_issue_id: Uuid,
+    user_id: &Uuid,
+    segment: Option<&Segment>,
+) -> Result<(), sqlx::Error> {
+    let tags: Option<Vec<String>> = segment.map(Segment::tag_names);
+    let match_all = segment.is_some_and(|segment| segment.operator == TagOperator::And);
+    transaction
+        .execute(sqlx::query!(
+            r#"
+              INSERT INTO issue_delivery_queue (
+                newsletter_issue_id,
+                subscriber_email
+              )
+              SELECT $1, email
+              FROM subscriptions
+              WHERE status = 'confirmed'
+              AND user_id = $2
+              AND NOT EXISTS (
+                SELECT 1 FROM suppressed_emails
+                WHERE suppressed_emails.email = lower(subscriptions.email)
+              )
+              AND (
+                $3::text[] IS NULL
+                OR (
+                  SELECT COUNT(*)
+                  FROM subscriber_tags
+                  WHERE subscriber_tags.subscriber_id = subscriptions.id
+                    AND subscriber_tags.tag = ANY($3)
+                ) >= CASE WHEN $4 THEN cardinality($3) ELSE 1 END
+              )
+            "#,
+            newsletter_issue_id,
+            user_id,
+            tags.as_deref(),
+            match_all
+        ))
+        .await?;
+
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FailedIssueDelivery {
+    pub failed_at: DateTime<Utc>,
+    pub failed_delivery_id: Uuid,
+    pub failure_reason: String,
+    pub n_attempts: i32,
+    pub newsletter_issue_id: Uuid,
+    pub subscriber_email: String,
+}
+
+impl FailedIssueDelivery {
+    pub async fn get_by_newsletter_issue_id(
+        user_id: Uuid,
+        newsletter_issue_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            FailedIssueDelivery,
+            r#"
+              SELECT
+                failed_issue_deliveries.failed_at,
+                failed_issue_deliveries.failed_delivery_id,
+                failed_issue_deliveries.failure_reason,
+                failed_issue_deliveries.n_attempts,
+                failed_issue_deliveries.newsletter_issue_id,
+                failed_issue_deliveries.subscriber_email
+              FROM failed_issue_deliveries
+              JOIN newsletter_issues
+                ON failed_issue_deliveries.newsletter_issue_id = newsletter_issues.newsletter_issue_id
+              WHERE newsletter_issues.user_id = $1
+                AND failed_issue_deliveries.newsletter_issue_id = $2
+              ORDER BY failed_issue_deliveries.failed_at DESC
+            "#,
+            user_id,
+            newsletter_issue_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Move failed deliveries of an issue back onto `issue_delivery_queue`.
+    /// When `failed_delivery_id` is `None` every failed delivery of the issue is requeued.
+    /// Returns the number of requeued deliveries.
+    pub async fn requeue(
+        user_id: Uuid,
+        newsletter_issue_id: &Uuid,
+        failed_delivery_id: Option<Uuid>,
+        pool: &PgPool,
+    ) -> Result<i64, sqlx::Error> {
+        let requeued = sqlx::query!(
+            r#"
+              WITH requeued AS (
+                DELETE FROM failed_issue_deliveries
+                USING newsletter_issues
+                WHERE failed_issue_deliveries.newsletter_issue_id = newsletter_issues.newsletter_issue_id
+                  AND newsletter_issues.user_id = $1
+                  AND failed_issue_deliveries.newsletter_issue_id = $2
+                  AND ($3::uuid IS NULL OR failed_issue_deliveries.failed_delivery_id = $3)
+                RETURNING
+                  failed_issue_deliveries.newsletter_issue_id,
+                  failed_issue_deliveries.subscriber_email
+              ), enqueued AS (
+                INSERT INTO issue_delivery_queue (
+                  newsletter_issue_id,
+                  subscriber_email
+                )
+                SELECT newsletter_issue_id, subscriber_email
+                FROM requeued
+                ON CONFLICT DO NOTHING
+              )
+              SELECT COUNT(*) AS "count!"
+              FROM requeued
+            "#,
+            user_id,
+            newsletter_issue_id,
+            failed_delivery_id
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(requeued.count)
+    }
+}
+
+/// Number of delivery log entries returned per page of a delivery report.
+pub const DELIVERY_LOG_PAGE_SIZE: i64 = 50;
+
+/// The outcome of a single attempt at delivering an issue to a subscriber.
+pub enum DeliveryOutcome {
+    Sent,
+    Retrying,
+    Throttled,
+    Failed,
+    Unsubscribed,
+}
+
+impl DeliveryOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryOutcome::Sent => "sent",
+            DeliveryOutcome::Retrying => "retrying",
+            DeliveryOutcome::Throttled => "throttled",
+            DeliveryOutcome::Failed => "failed",
+            DeliveryOutcome::Unsubscribed => "unsubscribed",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeliveryLogEntry {
+    pub delivery_log_id: Uuid,
+    pub logged_at: DateTime<Utc>,
+    pub outcome: String,
+    pub provider_response: Option<String>,
+    pub subscriber_email: String,
+}
+
+impl DeliveryLogEntry {
+    pub async fn get_page_by_newsletter_issue_id(
+        newsletter_issue_id: &Uuid,
+        page: i64,
+        pool: &PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            DeliveryLogEntry,
+            r#"
+              SELECT
+                delivery_log_id,
+                logged_at,
+                outcome,
+                provider_response,
+                subscriber_email
+              FROM delivery_log
+              WHERE newsletter_issue_id = $1
+              ORDER BY logged_at DESC, delivery_log_id
+              LIMIT $2
+              OFFSET $3
+            "#,
+            newsletter_issue_id,
+            DELIVERY_LOG_PAGE_SIZE,
+            (page - 1) * DELIVERY_LOG_PAGE_SIZE
+        )
+        .fetch_all(pool)
+        .await
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeliveryReport {
+    pub failed: i64,
+    pub log: Vec<DeliveryLogEntry>,
+    pub page: i64,
+    pub pending: i64,
+    pub sent: i64,
+    pub total_log_entries: i64,
+}
+
+impl DeliveryReport {
+    /// Aggregate the delivery state of an issue alongside one page of its delivery log.
+    /// `sent` counts distinct recipients, `pending` the deliveries still queued (including
+    /// those awaiting a retry) and `failed` the deliveries sitting in the dead-letter table.
+    pub async fn get_by_newsletter_issue_id(
+        newsletter_issue_id: &Uuid,
+        page: i64,
+        pool: &PgPool,
+    ) -> Result<Self, sqlx::Error> {
+        let counts = sqlx::query!(
+            r#"
+              SELECT
+                (
+                  SELECT COUNT(DISTINCT subscriber_email)
+                  FROM delivery_log
+                  WHERE newsletter_issue_id = $1 AND outcome = 'sent'
+                ) AS "sent!",
+                (
+                  SELECT COUNT(*)
+                  FROM issue_delivery_queue
+                  WHERE newsletter_issue_id = $1
+                ) AS "pending!",
+                (
+                  SELECT COUNT(*)
+                  FROM failed_issue_deliveries
+                  WHERE newsletter_issue_id = $1
+                ) AS "failed!",
+                (
+                  SELECT COUNT(*)
+                  FROM delivery_log
+                  WHERE newsletter_issue_id = $1
+                ) AS "total_log_entries!"
+            "#,
+            newsletter_issue_id
+        )
+        .fetch_one(pool)
+        .await?;
+        let log =
+            DeliveryLogEntry::get_page_by_newsletter_issue_id(newsletter_issue_id, page, pool)
+                .await?;
+
+        Ok(Self {
+            failed: counts.failed,
+            log,
+            page,
+            pending: counts.pending,
+            sent: counts.sent,
+            total_log_entries: counts.total_log_entries,
+        })
+    }
+}
@@ -1,7 +1,21 @@
+mod confirmation_email_template;
+mod issue_delivery;
 mod newsletter;
+mod subscriber;
+mod subscriber_data;
+mod subscription_consent;
+mod suppressed_email;
 mod user;
 mod user_profile;
+mod worker_status;
 
+pub use confirmation_email_template::*;
+pub use issue_delivery::*;
 pub use newsletter::*;
+pub use subscriber::*;
+pub use subscriber_data::*;
+pub use subscription_consent::*;
+pub use suppressed_email::*;
 pub use user::*;
 pub use user_profile::*;
+pub use worker_status::*;
@@ -2,7 +2,7 @@ use crate::clients::cloudinary_client::CloudinaryClient;
 use crate::clients::s3_client::S3Client;
 use crate::domain::newsletter_issue::{Content, Description, Title};
 use crate::domain::{Base64ImageUrl, ImageUrl};
-use crate::models::AssociatedUser;
+use crate::models::{AssociatedUser, Segment};
 use crate::utils::{e500, is_empty_or_whitespace};
 use anyhow::Context;
 use chrono::{DateTime, Utc};
@@ -21,6 +21,7 @@ pub struct NewsletterIssue {
     pub description: String,
     pub newsletter_issue_id: Uuid,
     pub published_at: Option<DateTime<Utc>>,
+    pub scheduled_for: Option<DateTime<Utc>>,
     pub slug: String,
     pub title: String,
     pub user_id: Uuid,
@@ -37,6 +38,7 @@ impl TryFrom<PgRow> for NewsletterIssue {
             description: row.try_get("description")?,
             newsletter_issue_id: row.try_get("newsletter_issue_id")?,
             published_at: row.try_get("published_at")?,
+            scheduled_for: row.try_get("scheduled_for")?,
             slug: row.try_get("slug")?,
             title: row.try_get("title")?,
             user_id: row.try_get("user_id")?,
@@ -60,6 +62,7 @@ impl NewsletterIssue {
                 description,
                 newsletter_issue_id,
                 published_at,
+                scheduled_for,
                 slug,
                 title,
                 user_id
@@ -92,6 +95,7 @@ impl NewsletterIssue {
                     description,
                     newsletter_issue_id,
                     published_at,
+                    scheduled_for,
                     slug,
                     title,
                     user_id
@@ -122,6 +126,7 @@ impl NewsletterIssue {
                 description,
                 newsletter_issue_id,
                 published_at,
+                scheduled_for,
                 slug,
                 title,
                 user_id
@@ -151,6 +156,7 @@ impl NewsletterIssue {
                 description,
                 newsletter_issue_id,
                 published_at,
+                scheduled_for,
                 slug,
                 title,
                 user_id
@@ -181,6 +187,7 @@ impl NewsletterIssue {
                 description,
                 newsletter_issue_id,
                 published_at,
+                scheduled_for,
                 slug,
                 title,
                 user_id
@@ -232,7 +239,8 @@ impl NewsletterIssue {
             .execute(sqlx::query!(
                 r#"
                   UPDATE newsletter_issues
-                  SET published_at = now()
+                  SET published_at = now(),
+                      scheduled_for = NULL
                   WHERE newsletter_issue_id = $1
                     AND user_id = $2
                 "#,
@@ -244,6 +252,101 @@ impl NewsletterIssue {
         Ok(self.newsletter_issue_id)
     }
 
+    /// Set (or clear, when `scheduled_for` is `None`) the time at which the background
+    /// worker publishes an unpublished issue, and the segment it is then delivered to.
+    /// Returns `None` if the issue was published or removed in the meantime.
+    pub async fn schedule(
+        self,
+        scheduled_for: Option<DateTime<Utc>>,
+        segment: Option<&Segment>,
+        pool: &PgPool,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let tags = segment.map(Segment::tag_names);
+        let result = sqlx::query!(
+            r#"
+              UPDATE newsletter_issues
+              SET
+                scheduled_for = $1,
+                scheduled_segment_tags = $4,
+                scheduled_segment_operator = $5
+              WHERE newsletter_issue_id = $2
+                AND user_id = $3
+                AND published_at IS NULL
+            "#,
+            scheduled_for,
+            &self.newsletter_issue_id,
+            self.user_id,
+            tags.as_deref(),
+            segment.map(|segment| segment.operator().as_str()),
+        )
+        .execute(pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            scheduled_for,
+            ..self
+        }))
+    }
+
+    /// The segment the issue was scheduled to be delivered to, `None` for every confirmed
+    /// subscriber.
+    pub async fn scheduled_segment_txn(
+        &self,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Option<Segment>, anyhow::Error> {
+        let saved = sqlx::query!(
+            r#"
+              SELECT scheduled_segment_tags, scheduled_segment_operator
+              FROM newsletter_issues
+              WHERE newsletter_issue_id = $1
+            "#,
+            self.newsletter_issue_id
+        )
+        .fetch_one(&mut **transaction)
+        .await?;
+
+        Segment::parse_saved(
+            saved.scheduled_segment_tags,
+            saved.scheduled_segment_operator,
+        )
+        .map_err(anyhow::Error::msg)
+    }
+
+    /// Lock the next unpublished issue whose scheduled time has passed, skipping
+    /// issues already claimed by another worker.
+    pub async fn find_next_due_for_publish_txn(
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            NewsletterIssue,
+            r#"
+              SELECT
+                content,
+                cover_image_url,
+                created_at,
+                description,
+                newsletter_issue_id,
+                published_at,
+                scheduled_for,
+                slug,
+                title,
+                user_id
+              FROM newsletter_issues
+              WHERE published_at IS NULL
+                AND scheduled_for <= now()
+              ORDER BY scheduled_for
+              FOR UPDATE
+              SKIP LOCKED
+              LIMIT 1
+            "#
+        )
+        .fetch_optional(&mut **transaction)
+        .await
+    }
+
     // Note - For serializing nested records sqlx allows returning
     // sequence of values, which are then mapped to the key names in
     // order. This means the order of the columns in the query must
@@ -363,6 +466,7 @@ impl NewsletterIssue {
             description: description.as_ref().to_string(),
             newsletter_issue_id: self.newsletter_issue_id,
             published_at: self.published_at,
+            scheduled_for: self.scheduled_for,
             slug: self.slug,
             title: title.as_ref().to_string(),
             user_id: self.user_id,
@@ -400,6 +504,7 @@ impl NewsletterIssue {
             description: self.description,
             newsletter_issue_id: self.newsletter_issue_id,
             published_at: self.published_at,
+            scheduled_for: self.scheduled_for,
             slug: self.slug,
             title: self.title,
             user_id: self.user_id,
@@ -454,6 +559,7 @@ pub struct NewsletterIssueAPI {
     pub html_content: String,
     pub newsletter_issue_id: Uuid,
     pub published_at: Option<DateTime<Utc>>,
+    pub scheduled_for: Option<DateTime<Utc>>,
     pub slug: String,
     pub title: String,
     pub user_id: Uuid,
@@ -470,6 +576,7 @@ impl From<NewsletterIssue> for NewsletterIssueAPI {
             html_content,
             newsletter_issue_id: newsletter_issue.newsletter_issue_id,
             published_at: newsletter_issue.published_at,
+            scheduled_for: newsletter_issue.scheduled_for,
             slug: newsletter_issue.slug,
             title: newsletter_issue.title,
             user_id: newsletter_issue.user_id,
@@ -730,6 +837,7 @@ mod tests {
             description: new_newsletter_issue.description,
             newsletter_issue_id: new_newsletter_issue.newsletter_issue_id,
             published_at: Some(Utc::now()),
+            scheduled_for: None,
             slug: new_newsletter_issue.slug,
             title: new_newsletter_issue.title,
             user_id: Uuid::new_v4(),
@@ -760,6 +868,7 @@ mod tests {
             description: new_newsletter_issue.description,
             newsletter_issue_id: new_newsletter_issue.newsletter_issue_id,
             published_at: Some(Utc::now()),
+            scheduled_for: None,
             slug: new_newsletter_issue.slug,
             title: new_newsletter_issue.title,
             user_id: Uuid::new_v4(),
@@ -0,0 +1,527 @@
+use crate::domain::{SubscriberName, SubscriberTag};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Number of subscribers returned per page of a writer's subscriber list.
+pub const SUBSCRIBER_PAGE_SIZE: i64 = 50;
+
+/// The lifecycle of a subscription, as stored in `subscriptions.status`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    PendingConfirmation,
+    Confirmed,
+    /// Confirmed, but the reader asked not to receive issues for now.
+    Paused,
+    Unsubscribed,
+    /// The email provider reported a hard bounce for the address.
+    Bounced,
+    /// The reader marked an issue as spam.
+    Complained,
+}
+
+impl SubscriptionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionStatus::PendingConfirmation => "pending_confirmation",
+            SubscriptionStatus::Confirmed => "confirmed",
+            SubscriptionStatus::Paused => "paused",
+            SubscriptionStatus::Unsubscribed => "unsubscribed",
+            SubscriptionStatus::Bounced => "bounced",
+            SubscriptionStatus::Complained => "complained",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "pending_confirmation" => Ok(SubscriptionStatus::PendingConfirmation),
+            "confirmed" => Ok(SubscriptionStatus::Confirmed),
+            "paused" => Ok(SubscriptionStatus::Paused),
+            "unsubscribed" => Ok(SubscriptionStatus::Unsubscribed),
+            "bounced" => Ok(SubscriptionStatus::Bounced),
+            "complained" => Ok(SubscriptionStatus::Complained),
+            other => Err(format!("{} is not a valid subscription status.", other)),
+        }
+    }
+
+    /// Readers can pause, resume or leave a subscription, but only confirming their email
+    /// can take it out of `PendingConfirmation`.
+    pub fn reader_can_change_to(&self, to: SubscriptionStatus) -> bool {
+        use SubscriptionStatus::*;
+
+        *self == to
+            || match to {
+                Unsubscribed => true,
+                Confirmed | Paused => matches!(self, Confirmed | Paused),
+                PendingConfirmation | Bounced | Complained => false,
+            }
+    }
+
+    /// Writers can pause, resume or end a subscription, but only the reader confirming their
+    /// email can make it `Confirmed`, only the reader subscribing again can make it
+    /// `PendingConfirmation`, and bounced or complained addresses stay suppressed.
+    pub fn writer_can_change_to(&self, to: SubscriptionStatus) -> bool {
+        use SubscriptionStatus::*;
+
+        if matches!(self, Bounced | Complained) {
+            return *self == to;
+        }
+        *self == to
+            || match to {
+                Unsubscribed => true,
+                Confirmed | Paused => matches!(self, Confirmed | Paused),
+                PendingConfirmation | Bounced | Complained => false,
+            }
+    }
+}
+
+/// Narrows down a writer's subscriber list. Every criterion is optional.
+#[derive(Deserialize, Debug, Default)]
+pub struct SubscriberFilter {
+    /// Case-insensitive substring of the subscriber's email or name.
+    pub search: Option<String>,
+    pub status: Option<SubscriptionStatus>,
+    pub subscribed_after: Option<DateTime<Utc>>,
+    pub subscribed_before: Option<DateTime<Utc>>,
+}
+
+impl SubscriberFilter {
+    /// `search` as an `ILIKE` pattern, with its own wildcards escaped.
+    fn search_pattern(&self) -> Option<String> {
+        self.search.as_ref().map(|search| {
+            let escaped = search
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("%{}%", escaped)
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Subscriber {
+    pub email: String,
+    pub id: Uuid,
+    pub name: String,
+    pub status: String,
+    pub subscribed_at: DateTime<Utc>,
+    pub tags: Vec<String>,
+}
+
+impl Subscriber {
+    pub async fn find_by_user_id_and_id(
+        user_id: Uuid,
+        id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Self, sqlx::Error> {
+        sqlx::query_as!(
+            Subscriber,
+            r#"
+              SELECT
+                email,
+                id,
+                name,
+                status,
+                subscribed_at,
+                ARRAY(
+                  SELECT tag FROM subscriber_tags
+                  WHERE subscriber_tags.subscriber_id = subscriptions.id
+                  ORDER BY tag
+                ) AS "tags!"
+              FROM subscriptions
+              WHERE user_id = $1 AND id = $2
+            "#,
+            user_id,
+            id
+        )
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Up to `limit` of a writer's subscribers, oldest first, that signed up after `after`.
+    /// Pass the last subscriber's `(subscribed_at, id)` to fetch the next batch.
+    pub async fn get_batch_by_user_id(
+        user_id: Uuid,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+        pool: &PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let (after_subscribed_at, after_id) = after.unzip();
+        sqlx::query_as!(
+            Subscriber,
+            r#"
+              SELECT
+                email,
+                id,
+                name,
+                status,
+                subscribed_at,
+                ARRAY(
+                  SELECT tag FROM subscriber_tags
+                  WHERE subscriber_tags.subscriber_id = subscriptions.id
+                  ORDER BY tag
+                ) AS "tags!"
+              FROM subscriptions
+              WHERE user_id = $1
+                AND ($2::timestamptz IS NULL OR (subscribed_at, id) > ($2, $3))
+              ORDER BY subscribed_at, id
+              LIMIT $4
+            "#,
+            user_id,
+            after_subscribed_at,
+            after_id,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Returns `false` if the writer has no such subscriber.
+    pub async fn delete(user_id: Uuid, id: &Uuid, pool: &PgPool) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM subscriptions WHERE user_id = $1 AND id = $2",
+            user_id,
+            id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Tag a subscriber, keeping the tags they already carry.
+    pub async fn add_tags_txn(
+        id: &Uuid,
+        tags: &[SubscriberTag],
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        let tags: Vec<String> = tags.iter().map(|t| t.as_ref().to_owned()).collect();
+        transaction
+            .execute(sqlx::query!(
+                r#"
+                  INSERT INTO subscriber_tags (subscriber_id, tag)
+                  SELECT $1, UNNEST($2::text[])
+                  ON CONFLICT DO NOTHING
+                "#,
+                id,
+                &tags
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Replace a subscriber's tags. Returns `false` if the writer has no such subscriber.
+    pub async fn set_tags(
+        user_id: Uuid,
+        id: &Uuid,
+        tags: &[SubscriberTag],
+        pool: &PgPool,
+    ) -> Result<bool, sqlx::Error> {
+        let mut transaction = pool.begin().await?;
+        let subscriber = sqlx::query!(
+            "SELECT id FROM subscriptions WHERE user_id = $1 AND id = $2 FOR UPDATE",
+            user_id,
+            id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?;
+        if subscriber.is_none() {
+            return Ok(false);
+        }
+        transaction
+            .execute(sqlx::query!(
+                "DELETE FROM subscriber_tags WHERE subscriber_id = $1",
+                id
+            ))
+            .await?;
+        Self::add_tags_txn(id, tags, &mut transaction).await?;
+        transaction.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Returns `false` if the writer has no such subscriber or its status is no longer
+    /// `from`.
+    pub async fn update_status(
+        user_id: Uuid,
+        id: &Uuid,
+        from: SubscriptionStatus,
+        to: SubscriptionStatus,
+        pool: &PgPool,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "UPDATE subscriptions SET status = $4 WHERE user_id = $1 AND id = $2 AND status = $3",
+            user_id,
+            id,
+            from.as_str(),
+            to.as_str()
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SubscriberPage {
+    pub page: i64,
+    pub subscribers: Vec<Subscriber>,
+    pub total: i64,
+}
+
+impl SubscriberPage {
+    /// One page of a writer's subscribers matching `filter`, newest first, alongside the
+    /// total number of matching subscribers.
+    pub async fn get_by_user_id(
+        user_id: Uuid,
+        filter: &SubscriberFilter,
+        page: i64,
+        pool: &PgPool,
+    ) -> Result<Self, sqlx::Error> {
+        let search = filter.search_pattern();
+        let status = filter.status.map(|status| status.as_str());
+        let total = sqlx::query!(
+            r#"
+              SELECT COUNT(*) AS "count!"
+              FROM subscriptions
+              WHERE user_id = $1
+                AND ($2::text IS NULL OR email ILIKE $2 OR name ILIKE $2)
+                AND ($3::text IS NULL OR status = $3)
+                AND ($4::timestamptz IS NULL OR subscribed_at >= $4)
+                AND ($5::timestamptz IS NULL OR subscribed_at < $5)
+            "#,
+            user_id,
+            search,
+            status,
+            filter.subscribed_after,
+            filter.subscribed_before
+        )
+        .fetch_one(pool)
+        .await?
+        .count;
+        let subscribers = sqlx::query_as!(
+            Subscriber,
+            r#"
+              SELECT
+                email,
+                id,
+                name,
+                status,
+                subscribed_at,
+                ARRAY(
+                  SELECT tag FROM subscriber_tags
+                  WHERE subscriber_tags.subscriber_id = subscriptions.id
+                  ORDER BY tag
+                ) AS "tags!"
+              FROM subscriptions
+              WHERE user_id = $1
+                AND ($2::text IS NULL OR email ILIKE $2 OR name ILIKE $2)
+                AND ($3::text IS NULL OR status = $3)
+                AND ($4::timestamptz IS NULL OR subscribed_at >= $4)
+                AND ($5::timestamptz IS NULL OR subscribed_at < $5)
+              ORDER BY subscribed_at DESC, id
+              LIMIT $6
+              OFFSET $7
+            "#,
+            user_id,
+            search,
+            status,
+            filter.subscribed_after,
+            filter.subscribed_before,
+            SUBSCRIBER_PAGE_SIZE,
+            (page - 1) * SUBSCRIBER_PAGE_SIZE
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(Self {
+            page,
+            subscribers,
+            total,
+        })
+    }
+}
+
+/// One of the subscriptions a reader holds, as shown in their preference centre.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReaderSubscription {
+    pub name: String,
+    pub status: String,
+    pub subscribed_at: DateTime<Utc>,
+    pub subscriber_id: Uuid,
+    pub writer_display_name: String,
+    pub writer_username: String,
+}
+
+impl ReaderSubscription {
+    /// Every subscription held by `email`, across writers.
+    pub async fn get_by_email(email: &str, pool: &PgPool) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            ReaderSubscription,
+            r#"
+              SELECT
+                subscriptions.name,
+                subscriptions.status,
+                subscriptions.subscribed_at,
+                subscriptions.id AS subscriber_id,
+                COALESCE(user_profiles.display_name, '') AS "writer_display_name!",
+                users.username AS writer_username
+              FROM subscriptions
+              JOIN users ON users.user_id = subscriptions.user_id
+              LEFT JOIN user_profiles ON user_profiles.user_id = subscriptions.user_id
+              WHERE lower(subscriptions.email) = lower($1)
+              ORDER BY subscriptions.subscribed_at
+            "#,
+            email
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn find_by_email_and_id(
+        email: &str,
+        subscriber_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            ReaderSubscription,
+            r#"
+              SELECT
+                subscriptions.name,
+                subscriptions.status,
+                subscriptions.subscribed_at,
+                subscriptions.id AS subscriber_id,
+                COALESCE(user_profiles.display_name, '') AS "writer_display_name!",
+                users.username AS writer_username
+              FROM subscriptions
+              JOIN users ON users.user_id = subscriptions.user_id
+              LEFT JOIN user_profiles ON user_profiles.user_id = subscriptions.user_id
+              WHERE lower(subscriptions.email) = lower($1) AND subscriptions.id = $2
+            "#,
+            email,
+            subscriber_id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Returns `false` if the subscription's status is no longer `from`, e.g. because a
+    /// bounce was recorded in the meantime.
+    pub async fn update(
+        subscriber_id: &Uuid,
+        name: &SubscriberName,
+        from: SubscriptionStatus,
+        to: SubscriptionStatus,
+        pool: &PgPool,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "UPDATE subscriptions SET name = $2, status = $4 WHERE id = $1 AND status = $3",
+            subscriber_id,
+            name.as_ref(),
+            from.as_str(),
+            to.as_str()
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{SubscriberFilter, SubscriptionStatus};
+
+    #[test]
+    fn search_wildcards_are_escaped() {
+        let filter = SubscriberFilter {
+            search: Some(String::from("50%_off")),
+            ..Default::default()
+        };
+
+        assert_eq!(filter.search_pattern().as_deref(), Some("%50\\%\\_off%"));
+    }
+
+    #[test]
+    fn statuses_round_trip_through_their_string_form() {
+        for status in [
+            SubscriptionStatus::PendingConfirmation,
+            SubscriptionStatus::Confirmed,
+            SubscriptionStatus::Paused,
+            SubscriptionStatus::Unsubscribed,
+            SubscriptionStatus::Bounced,
+            SubscriptionStatus::Complained,
+        ] {
+            assert_eq!(SubscriptionStatus::parse(status.as_str()), Ok(status));
+        }
+        assert!(SubscriptionStatus::parse("banned").is_err());
+    }
+
+    #[test]
+    fn readers_cannot_confirm_a_pending_subscription() {
+        let pending = SubscriptionStatus::PendingConfirmation;
+
+        assert!(!pending.reader_can_change_to(SubscriptionStatus::Confirmed));
+        assert!(!pending.reader_can_change_to(SubscriptionStatus::Paused));
+        assert!(pending.reader_can_change_to(SubscriptionStatus::Unsubscribed));
+    }
+
+    #[test]
+    fn readers_can_pause_and_resume_a_confirmed_subscription() {
+        let confirmed = SubscriptionStatus::Confirmed;
+        let paused = SubscriptionStatus::Paused;
+
+        assert!(confirmed.reader_can_change_to(paused));
+        assert!(paused.reader_can_change_to(confirmed));
+        assert!(!SubscriptionStatus::Unsubscribed.reader_can_change_to(confirmed));
+    }
+
+    #[test]
+    fn readers_can_only_leave_a_suppressed_subscription() {
+        for suppressed in [SubscriptionStatus::Bounced, SubscriptionStatus::Complained] {
+            assert!(!suppressed.reader_can_change_to(SubscriptionStatus::Confirmed));
+            assert!(!SubscriptionStatus::Confirmed.reader_can_change_to(suppressed));
+            assert!(suppressed.reader_can_change_to(SubscriptionStatus::Unsubscribed));
+        }
+    }
+
+    #[test]
+    fn writers_cannot_confirm_a_subscription_the_reader_did_not_confirm() {
+        for status in [
+            SubscriptionStatus::PendingConfirmation,
+            SubscriptionStatus::Unsubscribed,
+        ] {
+            assert!(!status.writer_can_change_to(SubscriptionStatus::Confirmed));
+            assert!(!status.writer_can_change_to(SubscriptionStatus::Paused));
+        }
+        assert!(SubscriptionStatus::Paused.writer_can_change_to(SubscriptionStatus::Confirmed));
+        assert!(SubscriptionStatus::Confirmed.writer_can_change_to(SubscriptionStatus::Paused));
+        assert!(
+            SubscriptionStatus::Confirmed.writer_can_change_to(SubscriptionStatus::Unsubscribed)
+        );
+    }
+
+    #[test]
+    fn writers_cannot_move_a_subscription_in_or_out_of_suppression() {
+        for suppressed in [SubscriptionStatus::Bounced, SubscriptionStatus::Complained] {
+            for status in [
+                SubscriptionStatus::PendingConfirmation,
+                SubscriptionStatus::Confirmed,
+                SubscriptionStatus::Paused,
+                SubscriptionStatus::Unsubscribed,
+            ] {
+                assert!(!suppressed.writer_can_change_to(status));
+                assert!(!status.writer_can_change_to(suppressed));
+            }
+        }
+    }
+
+    #[test]
+    fn no_search_means_no_pattern() {
+        assert_eq!(SubscriberFilter::default().search_pattern(), None);
+    }
+}
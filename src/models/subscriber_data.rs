@@ -0,0 +1,299 @@
+use crate::models::SuppressedEmail;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Everything stored about an email address, returned to answer a data subject access request.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SubscriberDataExport {
+    pub confirmation_emails: Vec<ExportedConfirmationEmail>,
+    pub consents: Vec<ExportedConsent>,
+    pub deliveries: Vec<ExportedDelivery>,
+    pub email: String,
+    pub failed_deliveries: Vec<ExportedFailedDelivery>,
+    pub preference_tokens: Vec<ExportedPreferenceToken>,
+    pub queued_deliveries: Vec<ExportedQueuedDelivery>,
+    pub subscription_tokens: Vec<ExportedSubscriptionToken>,
+    pub subscriptions: Vec<ExportedSubscription>,
+    pub suppression: Option<SuppressedEmail>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExportedSubscription {
+    pub email: String,
+    pub name: String,
+    pub status: String,
+    pub subscribed_at: DateTime<Utc>,
+    pub subscriber_id: Uuid,
+    pub tags: Vec<String>,
+    pub unsubscribe_token: String,
+    pub writer_username: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExportedSubscriptionToken {
+    pub created_at: DateTime<Utc>,
+    pub subscriber_id: Uuid,
+    pub subscription_token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExportedConfirmationEmail {
+    pub created_at: DateTime<Utc>,
+    pub n_attempts: i32,
+    pub subscriber_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExportedConsent {
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub consent_text: String,
+    pub consented_at: DateTime<Utc>,
+    pub ip_address: Option<String>,
+    pub source: String,
+    pub subscriber_id: Uuid,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExportedQueuedDelivery {
+    pub newsletter_issue_id: Uuid,
+    pub next_attempt_at: DateTime<Utc>,
+    pub n_attempts: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExportedDelivery {
+    pub logged_at: DateTime<Utc>,
+    pub newsletter_issue_id: Uuid,
+    pub outcome: String,
+    pub provider_response: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExportedFailedDelivery {
+    pub failed_at: DateTime<Utc>,
+    pub failure_reason: String,
+    pub n_attempts: i32,
+    pub newsletter_issue_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExportedPreferenceToken {
+    pub created_at: DateTime<Utc>,
+}
+
+impl SubscriberDataExport {
+    /// Email addresses are matched case-insensitively, like in the preference centre.
+    pub async fn get_by_email(email: &str, pool: &PgPool) -> Result<Self, sqlx::Error> {
+        let subscriptions = sqlx::query_as!(
+            ExportedSubscription,
+            r#"
+              SELECT
+                subscriptions.email,
+                subscriptions.name,
+                subscriptions.status,
+                subscriptions.subscribed_at,
+                subscriptions.id AS subscriber_id,
+                ARRAY(
+                  SELECT tag FROM subscriber_tags
+                  WHERE subscriber_tags.subscriber_id = subscriptions.id
+                  ORDER BY tag
+                ) AS "tags!",
+                subscriptions.unsubscribe_token,
+                users.username AS writer_username
+              FROM subscriptions
+              JOIN users ON users.user_id = subscriptions.user_id
+              WHERE lower(subscriptions.email) = lower($1)
+              ORDER BY subscriptions.subscribed_at
+            "#,
+            email
+        )
+        .fetch_all(pool)
+        .await?;
+        let subscription_tokens = sqlx::query_as!(
+            ExportedSubscriptionToken,
+            r#"
+              SELECT
+                subscription_tokens.created_at,
+                subscription_tokens.subscriber_id,
+                subscription_tokens.subscription_token
+              FROM subscription_tokens
+              JOIN subscriptions ON subscriptions.id = subscription_tokens.subscriber_id
+              WHERE lower(subscriptions.email) = lower($1)
+              ORDER BY subscription_tokens.created_at
+            "#,
+            email
+        )
+        .fetch_all(pool)
+        .await?;
+        let confirmation_emails = sqlx::query_as!(
+            ExportedConfirmationEmail,
+            r#"
+              SELECT
+                confirmation_email_outbox.created_at,
+                confirmation_email_outbox.n_attempts,
+                confirmation_email_outbox.subscriber_id
+              FROM confirmation_email_outbox
+              JOIN subscriptions ON subscriptions.id = confirmation_email_outbox.subscriber_id
+              WHERE lower(subscriptions.email) = lower($1)
+              ORDER BY confirmation_email_outbox.created_at
+            "#,
+            email
+        )
+        .fetch_all(pool)
+        .await?;
+        let consents = sqlx::query_as!(
+            ExportedConsent,
+            r#"
+              SELECT
+                subscription_consents.confirmed_at,
+                subscription_consents.consent_text,
+                subscription_consents.consented_at,
+                subscription_consents.ip_address,
+                subscription_consents.source,
+                subscription_consents.subscriber_id,
+                subscription_consents.user_agent
+              FROM subscription_consents
+              JOIN subscriptions ON subscriptions.id = subscription_consents.subscriber_id
+              WHERE lower(subscriptions.email) = lower($1)
+              ORDER BY subscription_consents.consented_at
+            "#,
+            email
+        )
+        .fetch_all(pool)
+        .await?;
+        let queued_deliveries = sqlx::query_as!(
+            ExportedQueuedDelivery,
+            r#"
+              SELECT newsletter_issue_id, next_attempt_at, n_attempts
+              FROM issue_delivery_queue
+              WHERE lower(subscriber_email) = lower($1)
+              ORDER BY next_attempt_at
+            "#,
+            email
+        )
+        .fetch_all(pool)
+        .await?;
+        let deliveries = sqlx::query_as!(
+            ExportedDelivery,
+            r#"
+              SELECT logged_at, newsletter_issue_id, outcome, provider_response
+              FROM delivery_log
+              WHERE lower(subscriber_email) = lower($1)
+              ORDER BY logged_at
+            "#,
+            email
+        )
+        .fetch_all(pool)
+        .await?;
+        let failed_deliveries = sqlx::query_as!(
+            ExportedFailedDelivery,
+            r#"
+              SELECT failed_at, failure_reason, n_attempts, newsletter_issue_id
+              FROM failed_issue_deliveries
+              WHERE lower(subscriber_email) = lower($1)
+              ORDER BY failed_at
+            "#,
+            email
+        )
+        .fetch_all(pool)
+        .await?;
+        let preference_tokens = sqlx::query_as!(
+            ExportedPreferenceToken,
+            r#"
+              SELECT created_at
+              FROM preference_tokens
+              WHERE lower(email) = lower($1)
+              ORDER BY created_at
+            "#,
+            email
+        )
+        .fetch_all(pool)
+        .await?;
+        let suppression = sqlx::query_as!(
+            SuppressedEmail,
+            r#"
+              SELECT email, reason, suppressed_at
+              FROM suppressed_emails
+              WHERE email = lower($1)
+            "#,
+            email
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(Self {
+            confirmation_emails,
+            consents,
+            deliveries,
+            email: email.to_string(),
+            failed_deliveries,
+            preference_tokens,
+            queued_deliveries,
+            subscription_tokens,
+            subscriptions,
+            suppression,
+        })
+    }
+}
+
+/// Erase an email address across all writers. Subscriptions are deleted along with their
+/// tokens, tags, consent records and pending emails, while delivery records are kept for the
+/// writers' reports but no longer name the address. A suppressed address stays on the
+/// suppression list, so that a later import can't resume sending to it.
+#[tracing::instrument(skip_all)]
+pub async fn erase_subscriber_data(
+    transaction: &mut Transaction<'_, Postgres>,
+    email: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM subscriptions WHERE lower(email) = lower($1)",
+        email
+    )
+    .execute(&mut **transaction)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM issue_delivery_queue WHERE lower(subscriber_email) = lower($1)",
+        email
+    )
+    .execute(&mut **transaction)
+    .await?;
+    sqlx::query!(
+        r#"
+          UPDATE delivery_log
+          SET
+            subscriber_email = 'erased-' || delivery_log_id || '@invalid',
+            provider_response = NULL
+          WHERE lower(subscriber_email) = lower($1)
+        "#,
+        email
+    )
+    .execute(&mut **transaction)
+    .await?;
+    sqlx::query!(
+        r#"
+          UPDATE failed_issue_deliveries
+          SET subscriber_email = 'erased-' || failed_delivery_id || '@invalid'
+          WHERE lower(subscriber_email) = lower($1)
+        "#,
+        email
+    )
+    .execute(&mut **transaction)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM preference_tokens WHERE lower(email) = lower($1)",
+        email
+    )
+    .execute(&mut **transaction)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM preference_link_outbox WHERE lower(email) = lower($1)",
+        email
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use crate::domain::ConsentText;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// How a subscriber came to be on a writer's list.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsentSource {
+    /// The reader filled in a signup form.
+    Form,
+    /// The writer imported the reader from another platform.
+    Import,
+    /// The reader was signed up by a direct call to the subscriptions API.
+    Api,
+}
+
+impl ConsentSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsentSource::Form => "form",
+            ConsentSource::Import => "import",
+            ConsentSource::Api => "api",
+        }
+    }
+}
+
+/// What is known about the request that carried a subscriber's consent.
+pub struct ConsentEvidence {
+    pub consent_text: ConsentText,
+    pub ip_address: Option<String>,
+    pub source: ConsentSource,
+    pub user_agent: Option<String>,
+}
+
+/// One signup of a subscriber, and when they confirmed it by following the emailed link.
+/// A reader who subscribes again after leaving gets a new record.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SubscriptionConsent {
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub consent_text: String,
+    pub consented_at: DateTime<Utc>,
+    pub ip_address: Option<String>,
+    pub source: String,
+    pub user_agent: Option<String>,
+}
+
+impl SubscriptionConsent {
+    pub async fn insert_txn(
+        subscriber_id: &Uuid,
+        evidence: &ConsentEvidence,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+              INSERT INTO subscription_consents (
+                consent_id,
+                subscriber_id,
+                source,
+                ip_address,
+                user_agent,
+                consent_text
+              )
+              VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            Uuid::new_v4(),
+            subscriber_id,
+            evidence.source.as_str(),
+            evidence.ip_address,
+            evidence.user_agent,
+            evidence.consent_text.as_ref()
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record the double opt-in on every signup the subscriber hasn't confirmed yet.
+    pub async fn confirm_txn(
+        subscriber_id: &Uuid,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+              UPDATE subscription_consents
+              SET confirmed_at = now()
+              WHERE subscriber_id = $1 AND confirmed_at IS NULL
+            "#,
+            subscriber_id
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The consent records of one of a writer's subscribers, oldest first.
+    pub async fn get_by_user_id_and_subscriber_id(
+        user_id: Uuid,
+        subscriber_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            SubscriptionConsent,
+            r#"
+              SELECT
+                subscription_consents.confirmed_at,
+                subscription_consents.consent_text,
+                subscription_consents.consented_at,
+                subscription_consents.ip_address,
+                subscription_consents.source,
+                subscription_consents.user_agent
+              FROM subscription_consents
+              JOIN subscriptions ON subscriptions.id = subscription_consents.subscriber_id
+              WHERE subscriptions.user_id = $1 AND subscriptions.id = $2
+              ORDER BY subscription_consents.consented_at
+            "#,
+            user_id,
+            subscriber_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+}
@@ -0,0 +1,75 @@
+use crate::models::SubscriptionStatus;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, Transaction};
+
+/// Why an address must no longer receive issues.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SuppressionReason {
+    HardBounce,
+    SpamComplaint,
+}
+
+impl SuppressionReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SuppressionReason::HardBounce => "hard_bounce",
+            SuppressionReason::SpamComplaint => "spam_complaint",
+        }
+    }
+
+    /// The status given to every subscription of a suppressed address.
+    pub fn subscription_status(&self) -> SubscriptionStatus {
+        match self {
+            SuppressionReason::HardBounce => SubscriptionStatus::Bounced,
+            SuppressionReason::SpamComplaint => SubscriptionStatus::Complained,
+        }
+    }
+}
+
+/// An address on the suppression list, shared by every writer since a bounce or a complaint
+/// hurts the reputation of the one sending domain. Addresses are stored lowercased.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SuppressedEmail {
+    pub email: String,
+    pub reason: String,
+    pub suppressed_at: DateTime<Utc>,
+}
+
+impl SuppressedEmail {
+    /// Add `email` to the suppression list and mark its subscriptions as bounced or
+    /// complained. The first reason an address was suppressed for is kept.
+    /// Returns the number of subscriptions that were updated.
+    pub async fn suppress_txn(
+        email: &str,
+        reason: SuppressionReason,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<u64, sqlx::Error> {
+        sqlx::query!(
+            r#"
+              INSERT INTO suppressed_emails (email, reason)
+              VALUES (lower($1), $2)
+              ON CONFLICT (email) DO NOTHING
+            "#,
+            email,
+            reason.as_str()
+        )
+        .execute(&mut **transaction)
+        .await?;
+        let updated = sqlx::query!(
+            r#"
+              UPDATE subscriptions
+              SET status = $2
+              WHERE lower(email) = lower($1)
+            "#,
+            email,
+            reason.subscription_status().as_str()
+        )
+        .execute(&mut **transaction)
+        .await?
+        .rows_affected();
+
+        Ok(updated)
+    }
+}
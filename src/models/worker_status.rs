@@ -0,0 +1,54 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WorkerStatus {
+    pub last_heartbeat_at: Option<DateTime<Utc>>,
+    pub queue_depth: i64,
+}
+
+impl WorkerStatus {
+    /// The most recent heartbeat of any delivery worker and the number of queued deliveries.
+    pub async fn get(pool: &PgPool) -> Result<Self, sqlx::Error> {
+        sqlx::query_as!(
+            WorkerStatus,
+            r#"
+              SELECT
+                (SELECT MAX(last_heartbeat_at) FROM worker_heartbeats) AS last_heartbeat_at,
+                (SELECT COUNT(*) FROM issue_delivery_queue) AS "queue_depth!"
+            "#
+        )
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn record_heartbeat(worker_id: Uuid, pool: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+              INSERT INTO worker_heartbeats (worker_id, last_heartbeat_at)
+              VALUES ($1, now())
+              ON CONFLICT (worker_id) DO UPDATE
+              SET last_heartbeat_at = EXCLUDED.last_heartbeat_at
+            "#,
+            worker_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove the heartbeat of a worker that has shut down cleanly.
+    pub async fn clear_heartbeat(worker_id: Uuid, pool: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "DELETE FROM worker_heartbeats WHERE worker_id = $1",
+            worker_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
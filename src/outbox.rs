@@ -0,0 +1,184 @@
+use crate::configuration::WorkerSettings;
+use crate::email_client::EmailClientError;
+use crate::issue_delivery_worker::ExecutionOutcome;
+use crate::rate_limiter::RateLimiter;
+use crate::shutdown::sleep_unless_shutdown;
+use chrono::Utc;
+use sqlx::{PgExecutor, PgPool};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// How long a dequeued email stays claimed while waiting for the rate limiter and in flight.
+/// Emails whose outcome could not be recorded are attempted again once it expires.
+const OUTBOX_LEASE: Duration = Duration::from_secs(10 * 60);
+
+/// A table of transactional emails waiting to be sent, e.g. `confirmation_email_outbox`.
+/// Every outbox table has `outbox_id`, `n_attempts`, `last_error` and `next_attempt_at`
+/// columns, and failed sends are retried with the same backoff as issue deliveries.
+///
+/// A worker dequeues an entry with `FOR UPDATE SKIP LOCKED`, commits a `lease` on it and only
+/// then waits for the rate limiter and sends, so that no row lock is held meanwhile.
+pub struct Outbox {
+    table: &'static str,
+    email_kind: &'static str,
+}
+
+impl Outbox {
+    /// `email_kind` names what the outbox sends in log messages, e.g. "a confirmation email".
+    pub const fn new(table: &'static str, email_kind: &'static str) -> Self {
+        Self { table, email_kind }
+    }
+
+    /// Push back the next attempt of an entry about to be sent, so that other workers leave
+    /// it alone once the dequeue transaction releases its row lock.
+    #[tracing::instrument(skip_all, fields(table=self.table))]
+    pub async fn lease(
+        &self,
+        executor: impl PgExecutor<'_>,
+        outbox_id: Uuid,
+    ) -> Result<(), anyhow::Error> {
+        let leased_until = Utc::now() + OUTBOX_LEASE;
+        sqlx::query(&format!(
+            "UPDATE {} SET next_attempt_at = $2 WHERE outbox_id = $1",
+            self.table
+        ))
+        .bind(outbox_id)
+        .bind(leased_until)
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    /// Make a leased entry due again, e.g. when shutting down before it could be sent.
+    #[tracing::instrument(skip_all, fields(table=self.table))]
+    pub async fn release(&self, pool: &PgPool, outbox_id: Uuid) -> Result<(), anyhow::Error> {
+        sqlx::query(&format!(
+            "UPDATE {} SET next_attempt_at = now() WHERE outbox_id = $1",
+            self.table
+        ))
+        .bind(outbox_id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Delete the leased entry if the email was sent, or reschedule it with backoff until
+    /// `max_delivery_attempts` is reached. Throttling by the email provider pauses
+    /// `rate_limiter` without consuming an attempt.
+    #[tracing::instrument(skip_all, fields(table=self.table, n_attempts=n_attempts))]
+    pub async fn record_outcome(
+        &self,
+        pool: &PgPool,
+        outbox_id: Uuid,
+        n_attempts: i32,
+        outcome: Result<String, EmailClientError>,
+        rate_limiter: &RateLimiter,
+        settings: &WorkerSettings,
+    ) -> Result<(), anyhow::Error> {
+        match outcome {
+            Ok(_) => self.delete(pool, outbox_id).await,
+            Err(e @ EmailClientError::RateLimited { retry_after }) => {
+                tracing::warn!(
+                    error.message = %e,
+                    "The email provider is throttling deliveries. \
+                        Pausing without consuming an attempt.",
+                );
+                rate_limiter.pause(retry_after, Instant::now());
+                self.reschedule(pool, outbox_id, n_attempts, &e.to_string(), retry_after)
+                    .await
+            }
+            Err(e) => {
+                let n_attempts = n_attempts + 1;
+                if n_attempts < settings.max_delivery_attempts {
+                    tracing::warn!(
+                        error.cause_chain = ?e,
+                        error.message = %e,
+                        "Failed to send {}. Retrying later.",
+                        self.email_kind
+                    );
+                    let retry_delay = settings.retry_delay(n_attempts);
+                    self.reschedule(pool, outbox_id, n_attempts, &e.to_string(), retry_delay)
+                        .await
+                } else {
+                    tracing::error!(
+                        error.cause_chain = ?e,
+                        error.message = %e,
+                        "Failed to send {}. Giving up after {} attempts.",
+                        self.email_kind,
+                        n_attempts
+                    );
+                    self.delete(pool, outbox_id).await
+                }
+            }
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(table=self.table))]
+    async fn reschedule(
+        &self,
+        pool: &PgPool,
+        outbox_id: Uuid,
+        n_attempts: i32,
+        last_error: &str,
+        retry_delay: Duration,
+    ) -> Result<(), anyhow::Error> {
+        let next_attempt_at = Utc::now() + retry_delay;
+        sqlx::query(&format!(
+            r#"
+            UPDATE {}
+            SET
+                n_attempts = $2,
+                last_error = $3,
+                next_attempt_at = $4
+            WHERE outbox_id = $1
+            "#,
+            self.table
+        ))
+        .bind(outbox_id)
+        .bind(n_attempts)
+        .bind(last_error)
+        .bind(next_attempt_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(table=self.table))]
+    pub async fn delete(
+        &self,
+        executor: impl PgExecutor<'_>,
+        outbox_id: Uuid,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(&format!("DELETE FROM {} WHERE outbox_id = $1", self.table))
+            .bind(outbox_id)
+            .execute(executor)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Keep sending emails with `try_send_next` until `shutdown` flips to `true`, backing off
+/// while the outbox is empty or failing. `try_send_next` gets its own handle on `shutdown`
+/// to stop waiting for the rate limiter.
+pub async fn outbox_loop<F, Fut>(
+    mut try_send_next: F,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<(), anyhow::Error>
+where
+    F: FnMut(watch::Receiver<bool>) -> Fut,
+    Fut: Future<Output = Result<ExecutionOutcome, anyhow::Error>>,
+{
+    while !*shutdown.borrow() {
+        let wait = match try_send_next(shutdown.clone()).await {
+            Ok(ExecutionOutcome::EmptyQueue) => Duration::from_secs(10),
+            Err(_) => Duration::from_secs(1),
+            Ok(ExecutionOutcome::TaskCompleted) => continue,
+        };
+        if sleep_unless_shutdown(wait, &mut shutdown).await {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use crate::configuration::WorkerSettings;
+use crate::domain::SubscriberEmail;
+use crate::email_client::EmailClient;
+use crate::issue_delivery_worker::ExecutionOutcome;
+use crate::outbox::{Outbox, outbox_loop};
+use crate::rate_limiter::RateLimiter;
+use crate::routes::subscriptions::generate_subscription_token;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use tokio::sync::watch;
+use tracing::{Span, field::display};
+use uuid::Uuid;
+
+pub async fn preference_link_loop(
+    pool: PgPool,
+    email_client: Arc<EmailClient>,
+    rate_limiter: Arc<RateLimiter>,
+    settings: Arc<WorkerSettings>,
+    base_url: Arc<String>,
+    shutdown: watch::Receiver<bool>,
+) -> Result<(), anyhow::Error> {
+    outbox_loop(
+        |shutdown| {
+            try_send_preference_link(
+                &pool,
+                &email_client,
+                &rate_limiter,
+                &settings,
+                &base_url,
+                shutdown,
+            )
+        },
+        shutdown,
+    )
+    .await
+}
+
+/// Send the next due preference centre link from the outbox, along with a fresh preference
+/// token. Requests for addresses without any subscription, or whose address bounced or
+/// complained, are dropped, so that the request itself does the same work whether or not
+/// the address reads anyone.
+/// Every send takes a token from the global rate limit, and failed sends are retried with the
+/// same backoff as issue deliveries.
+#[tracing::instrument(skip_all, fields(outbox_id=tracing::field::Empty), err)]
+pub async fn try_send_preference_link(
+    pool: &PgPool,
+    email_client: &EmailClient,
+    rate_limiter: &RateLimiter,
+    settings: &WorkerSettings,
+    base_url: &str,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<ExecutionOutcome, anyhow::Error> {
+    let mut transaction = pool.begin().await?;
+    let Some(task) = dequeue_task(&mut transaction).await? else {
+        return Ok(ExecutionOutcome::EmptyQueue);
+    };
+    Span::current().record("outbox_id", display(task.outbox_id));
+    if !task.has_subscriptions {
+        tracing::info!("Dropping a preference centre link to an address without subscriptions");
+        OUTBOX.delete(&mut *transaction, task.outbox_id).await?;
+        transaction.commit().await?;
+        return Ok(ExecutionOutcome::TaskCompleted);
+    }
+    if task.suppressed {
+        tracing::info!("Dropping a preference centre link to a suppressed address");
+        OUTBOX.delete(&mut *transaction, task.outbox_id).await?;
+        transaction.commit().await?;
+        return Ok(ExecutionOutcome::TaskCompleted);
+    }
+    let email = match SubscriberEmail::parse(task.email.clone()) {
+        Ok(email) => email,
+        Err(e) => {
+            tracing::error!(
+                error.message = %e,
+                "Dropping a preference centre link. The email address is invalid",
+            );
+            OUTBOX.delete(&mut *transaction, task.outbox_id).await?;
+            transaction.commit().await?;
+            return Ok(ExecutionOutcome::TaskCompleted);
+        }
+    };
+    let preference_token = generate_subscription_token();
+    store_preference_token(&mut transaction, email.as_ref(), &preference_token).await?;
+    let preference_link = format!(
+        "{}/subscriptions/preferences?preference_token={}",
+        base_url, preference_token
+    );
+    OUTBOX.lease(&mut *transaction, task.outbox_id).await?;
+    transaction.commit().await?;
+    if !rate_limiter.acquire_global(&mut shutdown).await {
+        OUTBOX.release(pool, task.outbox_id).await?;
+        return Ok(ExecutionOutcome::TaskCompleted);
+    }
+    let outcome = email_client
+        .send_email(
+            &email,
+            "Manage your subscriptions",
+            &format!(
+                "Click <a href=\"{}\">here</a> to manage your newsletter subscriptions, \
+                    export your personal data or erase it.",
+                preference_link
+            ),
+            &format!(
+                "Visit {} to manage your newsletter subscriptions, \
+                    export your personal data or erase it.",
+                preference_link
+            ),
+        )
+        .await;
+    OUTBOX
+        .record_outcome(
+            pool,
+            task.outbox_id,
+            task.n_attempts,
+            outcome,
+            rate_limiter,
+            settings,
+        )
+        .await?;
+    Ok(ExecutionOutcome::TaskCompleted)
+}
+
+/// Queue a preference centre link for `email`, whether or not it holds any subscription.
+#[tracing::instrument(skip(pool, email))]
+pub async fn enqueue_preference_link(pool: &PgPool, email: &str) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO preference_link_outbox (outbox_id, email)
+        VALUES ($1, $2)
+        "#,
+        Uuid::new_v4(),
+        email
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+type PgTransaction = Transaction<'static, Postgres>;
+
+const OUTBOX: Outbox = Outbox::new("preference_link_outbox", "a preference centre link");
+
+struct OutboxTask {
+    outbox_id: Uuid,
+    email: String,
+    has_subscriptions: bool,
+    n_attempts: i32,
+    suppressed: bool,
+}
+
+#[tracing::instrument(skip_all)]
+async fn dequeue_task(
+    transaction: &mut PgTransaction,
+) -> Result<Option<OutboxTask>, anyhow::Error> {
+    let task = sqlx::query_as!(
+        OutboxTask,
+        r#"
+        SELECT
+          outbox_id,
+          email,
+          EXISTS (
+            SELECT 1 FROM subscriptions
+            WHERE lower(subscriptions.email) = lower(preference_link_outbox.email)
+          ) AS "has_subscriptions!",
+          n_attempts,
+          EXISTS (
+            SELECT 1 FROM suppressed_emails
+            WHERE suppressed_emails.email = lower(preference_link_outbox.email)
+          ) AS "suppressed!"
+        FROM preference_link_outbox
+        WHERE next_attempt_at <= now()
+        ORDER BY created_at
+        FOR UPDATE
+        SKIP LOCKED
+        LIMIT 1
+        "#,
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+    Ok(task)
+}
+
+#[tracing::instrument(skip_all)]
+async fn store_preference_token(
+    transaction: &mut PgTransaction,
+    email: &str,
+    preference_token: &str,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO preference_tokens (preference_token, email)
+        VALUES ($1, $2)
+        "#,
+        preference_token,
+        email
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,285 @@
+use crate::configuration::WorkerSettings;
+use crate::shutdown::sleep_unless_shutdown;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// A token bucket holding up to `capacity` tokens, refilled at `rate_per_second`.
+/// A rate of zero disables the bucket.
+pub struct TokenBucket {
+    capacity: f64,
+    rate_per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate_per_second: u32, capacity: u32, now: Instant) -> Self {
+        let capacity = f64::from(capacity.max(1));
+        Self {
+            capacity,
+            rate_per_second: f64::from(rate_per_second),
+            tokens: capacity,
+            last_refill: now,
+        }
+    }
+
+    fn is_unlimited(&self) -> bool {
+        self.rate_per_second == 0.0
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate_per_second).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// How long to wait, as of `now`, before a token is available.
+    pub fn wait_time(&mut self, now: Instant) -> Duration {
+        if self.is_unlimited() {
+            return Duration::ZERO;
+        }
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.rate_per_second)
+        }
+    }
+
+    fn take(&mut self) {
+        if !self.is_unlimited() {
+            self.tokens -= 1.0;
+        }
+    }
+}
+
+struct RateLimiterState {
+    global: TokenBucket,
+    paused_until: Option<Instant>,
+    writers: HashMap<Uuid, TokenBucket>,
+}
+
+/// Throttles outbound emails with a global token bucket and one bucket per sending writer.
+/// The limiter can also be paused, e.g. when the email provider asks us to back off.
+pub struct RateLimiter {
+    state: Mutex<RateLimiterState>,
+    writer_burst: u32,
+    writer_rate_per_second: u32,
+}
+
+impl RateLimiter {
+    pub fn new(settings: &WorkerSettings) -> Self {
+        let now = Instant::now();
+        Self {
+            state: Mutex::new(RateLimiterState {
+                global: TokenBucket::new(
+                    settings.global_send_rate_per_second,
+                    settings.global_send_burst,
+                    now,
+                ),
+                paused_until: None,
+                writers: HashMap::new(),
+            }),
+            writer_burst: settings.writer_send_burst,
+            writer_rate_per_second: settings.writer_send_rate_per_second,
+        }
+    }
+
+    /// Take a token for an email sent on behalf of `user_id`, or return how long to wait.
+    pub fn try_acquire(&self, user_id: Uuid, now: Instant) -> Result<(), Duration> {
+        self.try_acquire_for(Some(user_id), now)
+    }
+
+    /// Take a token for an email sent on behalf of no writer in particular, such as a
+    /// preference centre link, or return how long to wait.
+    pub fn try_acquire_global(&self, now: Instant) -> Result<(), Duration> {
+        self.try_acquire_for(None, now)
+    }
+
+    fn try_acquire_for(&self, user_id: Option<Uuid>, now: Instant) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap();
+        let paused_for = state
+            .paused_until
+            .map(|until| until.saturating_duration_since(now))
+            .unwrap_or_default();
+        let RateLimiterState {
+            global, writers, ..
+        } = &mut *state;
+        let mut writer = user_id.map(|user_id| {
+            writers.entry(user_id).or_insert_with(|| {
+                TokenBucket::new(self.writer_rate_per_second, self.writer_burst, now)
+            })
+        });
+        let wait = paused_for.max(global.wait_time(now)).max(
+            writer
+                .as_mut()
+                .map(|writer| writer.wait_time(now))
+                .unwrap_or_default(),
+        );
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        global.take();
+        if let Some(writer) = writer {
+            writer.take();
+        }
+        Ok(())
+    }
+
+    /// Wait until an email can be sent on behalf of `user_id`.
+    /// Returns `false`, without taking a token, if a shutdown is requested first.
+    pub async fn acquire(&self, user_id: Uuid, shutdown: &mut watch::Receiver<bool>) -> bool {
+        self.acquire_for(Some(user_id), shutdown).await
+    }
+
+    /// Wait until an email can be sent on behalf of no writer in particular.
+    /// Returns `false`, without taking a token, if a shutdown is requested first.
+    pub async fn acquire_global(&self, shutdown: &mut watch::Receiver<bool>) -> bool {
+        self.acquire_for(None, shutdown).await
+    }
+
+    async fn acquire_for(
+        &self,
+        user_id: Option<Uuid>,
+        shutdown: &mut watch::Receiver<bool>,
+    ) -> bool {
+        while let Err(wait) = self.try_acquire_for(user_id, Instant::now()) {
+            if sleep_unless_shutdown(wait, shutdown).await {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Hold back every send until `duration` from `now` has elapsed.
+    pub fn pause(&self, duration: Duration, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        let until = now + duration;
+        if state
+            .paused_until
+            .is_none_or(|paused_until| paused_until < until)
+        {
+            state.paused_until = Some(until);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::configuration::WorkerSettings;
+    use crate::rate_limiter::{RateLimiter, TokenBucket};
+    use crate::shutdown::shutdown_channel;
+    use claims::{assert_err, assert_ok};
+    use std::time::{Duration, Instant};
+    use uuid::Uuid;
+
+    fn worker_settings(global: (u32, u32), writer: (u32, u32)) -> WorkerSettings {
+        WorkerSettings {
+            batch_size: 50,
+            concurrency: 4,
+            global_send_burst: global.1,
+            global_send_rate_per_second: global.0,
+            max_delivery_attempts: 5,
+            retention_interval_milliseconds: 3600000,
+            retry_base_delay_milliseconds: 1000,
+            retry_max_delay_milliseconds: 10000,
+            scheduler_interval_milliseconds: 60000,
+            unconfirmed_reminder_days: Some(7),
+            unconfirmed_retention_days: 30,
+            writer_send_burst: writer.1,
+            writer_send_rate_per_second: writer.0,
+        }
+    }
+
+    #[test]
+    fn token_bucket_allows_a_burst_then_waits_for_a_refill() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2, 2, now);
+
+        for _ in 0..2 {
+            assert_eq!(bucket.wait_time(now), Duration::ZERO);
+            bucket.take();
+        }
+
+        assert_eq!(bucket.wait_time(now), Duration::from_millis(500));
+        assert_eq!(
+            bucket.wait_time(now + Duration::from_millis(500)),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn token_bucket_with_a_zero_rate_is_unlimited() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(0, 1, now);
+
+        for _ in 0..100 {
+            assert_eq!(bucket.wait_time(now), Duration::ZERO);
+            bucket.take();
+        }
+    }
+
+    #[test]
+    fn writers_are_limited_independently() {
+        let now = Instant::now();
+        let limiter = RateLimiter::new(&worker_settings((0, 1), (1, 1)));
+        let (first_writer, second_writer) = (Uuid::new_v4(), Uuid::new_v4());
+
+        assert_ok!(limiter.try_acquire(first_writer, now));
+        assert_err!(limiter.try_acquire(first_writer, now));
+        assert_ok!(limiter.try_acquire(second_writer, now));
+    }
+
+    #[test]
+    fn the_global_limit_applies_across_writers() {
+        let now = Instant::now();
+        let limiter = RateLimiter::new(&worker_settings((1, 1), (0, 1)));
+
+        assert_ok!(limiter.try_acquire(Uuid::new_v4(), now));
+        assert_err!(limiter.try_acquire(Uuid::new_v4(), now));
+    }
+
+    #[test]
+    fn global_tokens_count_against_the_global_limit() {
+        let now = Instant::now();
+        let limiter = RateLimiter::new(&worker_settings((1, 1), (1, 1)));
+
+        assert_ok!(limiter.try_acquire_global(now));
+        assert_err!(limiter.try_acquire_global(now));
+        assert_err!(limiter.try_acquire(Uuid::new_v4(), now));
+    }
+
+    #[test]
+    fn a_paused_limiter_holds_back_every_send() {
+        let now = Instant::now();
+        let limiter = RateLimiter::new(&worker_settings((0, 1), (0, 1)));
+        limiter.pause(Duration::from_secs(30), now);
+
+        assert_eq!(
+            limiter.try_acquire(Uuid::new_v4(), now),
+            Err(Duration::from_secs(30))
+        );
+        assert_ok!(limiter.try_acquire(Uuid::new_v4(), now + Duration::from_secs(30)));
+    }
+
+    #[tokio::test]
+    async fn waiting_for_a_token_is_cut_short_by_a_shutdown() {
+        let limiter = RateLimiter::new(&worker_settings((0, 1), (0, 1)));
+        limiter.pause(Duration::from_secs(60), Instant::now());
+        let (sender, mut receiver) = shutdown_channel();
+        sender.send(true).unwrap();
+
+        let acquired = tokio::time::timeout(
+            Duration::from_secs(1),
+            limiter.acquire(Uuid::new_v4(), &mut receiver),
+        )
+        .await
+        .expect("The wait should end as soon as a shutdown is requested.");
+        assert!(!acquired);
+    }
+}
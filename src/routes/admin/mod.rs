@@ -4,4 +4,5 @@ pub mod authenticate;
 pub mod logout;
 pub mod newsletters;
 pub mod password;
+pub mod subscribers;
 pub mod user;
@@ -0,0 +1,42 @@
+use crate::authentication::UserId;
+use crate::models::FailedIssueDelivery;
+use crate::utils::{ResponseMessage, e404, e500};
+use actix_web::http::header::ContentType;
+use actix_web::{HttpResponse, put, web};
+use anyhow::Context;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const SUCCESS_MESSAGE: &str = "The failed delivery has been requeued.";
+
+#[put("/newsletters/{newsletter_issue_id}/deliveries/failed/{failed_delivery_id}/requeue")]
+#[tracing::instrument(
+  name = "Requeue a failed delivery of a newsletter issue",
+  skip_all,
+  fields(user_id=%*user_id)
+)]
+pub async fn put(
+    path: web::Path<(Uuid, Uuid)>,
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    let (newsletter_issue_id, failed_delivery_id) = path.into_inner();
+    let requeued = FailedIssueDelivery::requeue(
+        *user_id,
+        &newsletter_issue_id,
+        Some(failed_delivery_id),
+        &pool,
+    )
+    .await
+    .context("Failed to requeue failed delivery.")
+    .map_err(e500)?;
+
+    if requeued == 0 {
+        return Err(e404("Failed delivery not found."));
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(ResponseMessage::from(SUCCESS_MESSAGE)))
+}
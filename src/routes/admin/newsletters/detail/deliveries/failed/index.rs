@@ -0,0 +1,36 @@
+use crate::authentication::UserId;
+use crate::models::{FailedIssueDelivery, NewsletterIssue};
+use crate::utils::{e404, e500};
+use actix_web::http::header::ContentType;
+use actix_web::{HttpResponse, get, web};
+use anyhow::Context;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[get("/newsletters/{newsletter_issue_id}/deliveries/failed")]
+#[tracing::instrument(
+    name = "Retrieving failed deliveries of a newsletter issue",
+    skip_all,
+    fields(user_id=%&*user_id)
+)]
+pub async fn get(
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+    path: web::Path<(Uuid,)>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    let newsletter_issue_id = path.into_inner().0;
+    NewsletterIssue::find_by_user_id_and_newsletter_issue_id(*user_id, &newsletter_issue_id, &pool)
+        .await
+        .context("Failed to find newsletter issue.")
+        .map_err(e404)?;
+    let failed_deliveries =
+        FailedIssueDelivery::get_by_newsletter_issue_id(*user_id, &newsletter_issue_id, &pool)
+            .await
+            .context("Failed to query failed deliveries.")
+            .map_err(e500)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(failed_deliveries))
+}
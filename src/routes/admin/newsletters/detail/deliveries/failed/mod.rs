@@ -0,0 +1,6 @@
+mod index;
+
+pub mod detail;
+pub mod requeue;
+
+pub use index::*;
@@ -0,0 +1,37 @@
+use crate::authentication::UserId;
+use crate::models::{FailedIssueDelivery, NewsletterIssue};
+use crate::utils::{ResponseMessage, e404, e500};
+use actix_web::http::header::ContentType;
+use actix_web::{HttpResponse, put, web};
+use anyhow::Context;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[put("/newsletters/{newsletter_issue_id}/deliveries/failed/requeue")]
+#[tracing::instrument(
+  name = "Requeue all failed deliveries of a newsletter issue",
+  skip_all,
+  fields(user_id=%*user_id)
+)]
+pub async fn put(
+    path: web::Path<(Uuid,)>,
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    let newsletter_issue_id = path.into_inner().0;
+    NewsletterIssue::find_by_user_id_and_newsletter_issue_id(*user_id, &newsletter_issue_id, &pool)
+        .await
+        .context("Failed to find newsletter issue.")
+        .map_err(e404)?;
+    let requeued = FailedIssueDelivery::requeue(*user_id, &newsletter_issue_id, None, &pool)
+        .await
+        .context("Failed to requeue failed deliveries.")
+        .map_err(e500)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(ResponseMessage::from(format!(
+            "{requeued} failed deliveries have been requeued."
+        ))))
+}
@@ -0,0 +1,48 @@
+use crate::authentication::UserId;
+use crate::models::{DELIVERY_LOG_PAGE_SIZE, DeliveryReport, NewsletterIssue};
+use crate::utils::{e400, e404, e500};
+use actix_web::http::header::ContentType;
+use actix_web::{HttpResponse, get, web};
+use anyhow::Context;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(serde::Deserialize)]
+pub struct Parameters {
+    page: Option<i64>,
+}
+
+#[get("/newsletters/{newsletter_issue_id}/deliveries")]
+#[tracing::instrument(
+    name = "Retrieving delivery report of a newsletter issue",
+    skip_all,
+    fields(user_id=%&*user_id)
+)]
+pub async fn get(
+    parameters: web::Query<Parameters>,
+    path: web::Path<(Uuid,)>,
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    let newsletter_issue_id = path.into_inner().0;
+    let page = parameters.page.unwrap_or(1);
+    if page < 1 {
+        return Err(e400("Page must be a positive number."));
+    }
+    if (page - 1).checked_mul(DELIVERY_LOG_PAGE_SIZE).is_none() {
+        return Err(e400("Page is out of range."));
+    }
+    NewsletterIssue::find_by_user_id_and_newsletter_issue_id(*user_id, &newsletter_issue_id, &pool)
+        .await
+        .context("Failed to find newsletter issue.")
+        .map_err(e404)?;
+    let report = DeliveryReport::get_by_newsletter_issue_id(&newsletter_issue_id, page, &pool)
+        .await
+        .context("Failed to query delivery report.")
+        .map_err(e500)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(report))
+}
@@ -0,0 +1,5 @@
+mod index;
+
+pub mod failed;
+
+pub use index::*;
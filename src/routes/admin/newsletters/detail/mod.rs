@@ -1,6 +1,8 @@
 mod index;
 
 pub mod cover_image;
+pub mod deliveries;
 pub mod publish;
+pub mod schedule;
 
 pub use index::*;
@@ -1,12 +1,12 @@
 use crate::authentication::UserId;
 use crate::idempotency::{IdempotencyKey, NextAction, save_response, try_processing};
-use crate::models::NewsletterIssue;
+use crate::models::{NewsletterIssue, Segment, TagOperator, enqueue_delivery_tasks};
 use crate::utils::{ResponseMessage, e400, e404, e500};
 use actix_web::http::header::ContentType;
 use actix_web::{HttpResponse, put, web};
 use anyhow::Context;
 use serde::Deserialize;
-use sqlx::{Executor, PgPool, Postgres, Transaction};
+use sqlx::PgPool;
 use uuid::Uuid;
 
 const SUCCESS_MESSAGE: &str =
@@ -15,6 +15,23 @@ const SUCCESS_MESSAGE: &str =
 #[derive(Deserialize)]
 struct PublishParams {
     idempotency_key: String,
+    /// Deliver only to subscribers with matching tags, rather than to every confirmed subscriber.
+    segment: Option<SegmentParams>,
+}
+
+#[derive(Deserialize)]
+pub struct SegmentParams {
+    #[serde(default)]
+    operator: TagOperator,
+    tags: Vec<String>,
+}
+
+impl TryFrom<SegmentParams> for Segment {
+    type Error = String;
+
+    fn try_from(params: SegmentParams) -> Result<Self, Self::Error> {
+        Segment::parse(params.tags, params.operator)
+    }
 }
 
 #[put("/newsletters/{newsletter_issue_id}/publish")]
@@ -31,8 +48,12 @@ pub async fn put(
 ) -> Result<HttpResponse, actix_web::Error> {
     let user_id = user_id.into_inner();
     let newsletter_issue_id = path.into_inner().0;
-    let PublishParams { idempotency_key } = params.0;
+    let PublishParams {
+        idempotency_key,
+        segment,
+    } = params.0;
     let idempotency_key: IdempotencyKey = idempotency_key.try_into().map_err(e400)?;
+    let segment: Option<Segment> = segment.map(TryInto::try_into).transpose().map_err(e400)?;
     let mut transaction = match try_processing(&pool, &idempotency_key, *user_id)
         .await
         .map_err(e500)?
@@ -56,10 +77,15 @@ pub async fn put(
     .await
     .context("Failed to publish newsletter issue details.")
     .map_err(e500)?;
-    enqueue_delivery_tasks(&mut transaction, newsletter_issue_id, &user_id)
-        .await
-        .context("Failed to enqueue delivery tasks.")
-        .map_err(e500)?;
+    enqueue_delivery_tasks(
+        &mut transaction,
+        newsletter_issue_id,
+        &user_id,
+        segment.as_ref(),
+    )
+    .await
+    .context("Failed to enqueue delivery tasks.")
+    .map_err(e500)?;
     let response = HttpResponse::Ok()
         .content_type(ContentType::json())
         .json(ResponseMessage::from(SUCCESS_MESSAGE));
@@ -68,29 +94,3 @@ pub async fn put(
         .map_err(e500)?;
     Ok(response)
 }
-
-#[tracing::instrument(skip_all)]
-async fn enqueue_delivery_tasks(
-    transaction: &mut Transaction<'_, Postgres>,
-    newsletter_issue_id: Uuid,
-    user_id: &Uuid,
-) -> Result<(), sqlx::Error> {
-    transaction
-        .execute(sqlx::query!(
-            r#"
-              INSERT INTO issue_delivery_queue (
-                newsletter_issue_id,
-                subscriber_email
-              )
-              SELECT $1, email
-              FROM subscriptions
-              WHERE status = 'confirmed'
-              AND user_id = $2
-            "#,
-            newsletter_issue_id,
-            user_id
-        ))
-        .await?;
-
-    Ok(())
-}
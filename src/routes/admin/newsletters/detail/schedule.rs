@@ -0,0 +1,108 @@
+use super::publish::SegmentParams;
+use crate::authentication::UserId;
+use crate::models::{NewsletterIssue, NewsletterIssueAPI, Segment};
+use crate::utils::{e400, e404, e409, e500};
+use actix_web::http::header::ContentType;
+use actix_web::{HttpResponse, delete, put, web};
+use anyhow::Context;
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const ALREADY_PUBLISHED_MESSAGE: &str = "The newsletter issue has already been published.";
+const CHANGED_MESSAGE: &str = "The newsletter issue was published or removed in the meantime.";
+
+#[derive(Deserialize)]
+pub struct ScheduleParams {
+    /// RFC 3339 timestamp, the offset lets writers schedule in their own timezone.
+    scheduled_for: DateTime<FixedOffset>,
+    /// Deliver only to subscribers with matching tags once the issue is published.
+    segment: Option<SegmentParams>,
+}
+
+#[put("/newsletters/{newsletter_issue_id}/schedule")]
+#[tracing::instrument(
+  name = "Schedule a newsletter issue",
+  skip_all,
+  fields(user_id=%*user_id)
+)]
+pub async fn put(
+    params: web::Json<ScheduleParams>,
+    path: web::Path<(Uuid,)>,
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    let newsletter_issue_id = path.into_inner().0;
+    let ScheduleParams {
+        scheduled_for,
+        segment,
+    } = params.0;
+    let scheduled_for = scheduled_for.with_timezone(&Utc);
+    if scheduled_for <= Utc::now() {
+        return Err(e400("Scheduled time must be in the future."));
+    }
+    let segment: Option<Segment> = segment.map(TryInto::try_into).transpose().map_err(e400)?;
+    let newsletter_issue = NewsletterIssue::find_by_user_id_and_newsletter_issue_id(
+        *user_id,
+        &newsletter_issue_id,
+        &pool,
+    )
+    .await
+    .context("Failed to find newsletter issue.")
+    .map_err(e404)?;
+    if newsletter_issue.published_at.is_some() {
+        return Err(e400(ALREADY_PUBLISHED_MESSAGE));
+    }
+    let newsletter_issue_api: NewsletterIssueAPI = newsletter_issue
+        .validate_for_publish()
+        .map_err(e400)?
+        .schedule(Some(scheduled_for), segment.as_ref(), &pool)
+        .await
+        .context("Failed to schedule newsletter issue.")
+        .map_err(e500)?
+        .ok_or_else(|| e409(CHANGED_MESSAGE))?
+        .into();
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(newsletter_issue_api))
+}
+
+#[delete("/newsletters/{newsletter_issue_id}/schedule")]
+#[tracing::instrument(
+  name = "Cancel a scheduled newsletter issue",
+  skip_all,
+  fields(user_id=%*user_id)
+)]
+pub async fn delete(
+    path: web::Path<(Uuid,)>,
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    let newsletter_issue_id = path.into_inner().0;
+    let newsletter_issue = NewsletterIssue::find_by_user_id_and_newsletter_issue_id(
+        *user_id,
+        &newsletter_issue_id,
+        &pool,
+    )
+    .await
+    .context("Failed to find newsletter issue.")
+    .map_err(e404)?;
+    if newsletter_issue.published_at.is_some() {
+        return Err(e400(ALREADY_PUBLISHED_MESSAGE));
+    }
+    let newsletter_issue_api: NewsletterIssueAPI = newsletter_issue
+        .schedule(None, None, &pool)
+        .await
+        .context("Failed to cancel scheduled newsletter issue.")
+        .map_err(e500)?
+        .ok_or_else(|| e409(CHANGED_MESSAGE))?
+        .into();
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(newsletter_issue_api))
+}
@@ -0,0 +1,107 @@
+use crate::authentication::UserId;
+use crate::models::{Subscriber, SubscriptionConsent};
+use crate::utils::{e404, e500};
+use actix_web::http::header::{ContentDisposition, ContentType, DispositionParam, DispositionType};
+use actix_web::{HttpResponse, get, web};
+use anyhow::Context;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// The consent records of a subscriber, proving when and how they opted in.
+#[get("/subscribers/{subscriber_id}/consents")]
+#[tracing::instrument(
+    name = "Retrieving the consent records of a subscriber",
+    skip_all,
+    fields(user_id=%&*user_id)
+)]
+pub async fn get(
+    path: web::Path<(Uuid,)>,
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    let subscriber_id = path.into_inner().0;
+    let (_, consents) = get_consents(*user_id, &subscriber_id, &pool).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(consents))
+}
+
+#[get("/subscribers/{subscriber_id}/consents/export")]
+#[tracing::instrument(
+    name = "Exporting the consent records of a subscriber",
+    skip_all,
+    fields(user_id=%&*user_id)
+)]
+pub async fn export(
+    path: web::Path<(Uuid,)>,
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    let subscriber_id = path.into_inner().0;
+    let (subscriber, consents) = get_consents(*user_id, &subscriber_id, &pool).await?;
+    let csv = to_csv(&subscriber, &consents)
+        .context("Failed to write the consent records as CSV.")
+        .map_err(e500)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(String::from("consents.csv"))],
+        })
+        .body(csv))
+}
+
+async fn get_consents(
+    user_id: Uuid,
+    subscriber_id: &Uuid,
+    pool: &PgPool,
+) -> Result<(Subscriber, Vec<SubscriptionConsent>), actix_web::Error> {
+    let subscriber = Subscriber::find_by_user_id_and_id(user_id, subscriber_id, pool)
+        .await
+        .context("Failed to find subscriber.")
+        .map_err(e404)?;
+    let consents =
+        SubscriptionConsent::get_by_user_id_and_subscriber_id(user_id, subscriber_id, pool)
+            .await
+            .context("Failed to query the subscriber's consent records.")
+            .map_err(e500)?;
+
+    Ok((subscriber, consents))
+}
+
+fn to_csv(
+    subscriber: &Subscriber,
+    consents: &[SubscriptionConsent],
+) -> Result<Vec<u8>, anyhow::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record([
+        "email",
+        "source",
+        "consent_text",
+        "consented_at",
+        "confirmed_at",
+        "ip_address",
+        "user_agent",
+    ])?;
+    for consent in consents {
+        writer.write_record([
+            subscriber.email.as_str(),
+            consent.source.as_str(),
+            consent.consent_text.as_str(),
+            consent.consented_at.to_rfc3339().as_str(),
+            consent
+                .confirmed_at
+                .map(|confirmed_at| confirmed_at.to_rfc3339())
+                .unwrap_or_default()
+                .as_str(),
+            consent.ip_address.as_deref().unwrap_or_default(),
+            consent.user_agent.as_deref().unwrap_or_default(),
+        ])?;
+    }
+
+    Ok(writer.into_inner()?)
+}
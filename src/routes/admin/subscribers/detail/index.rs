@@ -0,0 +1,58 @@
+use crate::authentication::UserId;
+use crate::models::Subscriber;
+use crate::utils::{ResponseMessage, e404, e500};
+use actix_web::http::header::ContentType;
+use actix_web::{HttpResponse, delete, get, web};
+use anyhow::Context;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[get("/subscribers/{subscriber_id}")]
+#[tracing::instrument(
+    name = "Retrieving a subscriber",
+    skip_all,
+    fields(user_id=%&*user_id)
+)]
+pub async fn get(
+    path: web::Path<(Uuid,)>,
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    let subscriber_id = path.into_inner().0;
+    let subscriber = Subscriber::find_by_user_id_and_id(*user_id, &subscriber_id, &pool)
+        .await
+        .context("Failed to find subscriber.")
+        .map_err(e404)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(subscriber))
+}
+
+#[delete("/subscribers/{subscriber_id}")]
+#[tracing::instrument(
+    name = "Removing a subscriber",
+    skip_all,
+    fields(user_id=%&*user_id)
+)]
+pub async fn delete(
+    path: web::Path<(Uuid,)>,
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    let subscriber_id = path.into_inner().0;
+    let deleted = Subscriber::delete(*user_id, &subscriber_id, &pool)
+        .await
+        .context("Failed to delete subscriber.")
+        .map_err(e500)?;
+
+    if !deleted {
+        return Err(e404("Subscriber not found."));
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(ResponseMessage::from("The subscriber has been removed.")))
+}
@@ -0,0 +1,7 @@
+mod index;
+
+pub mod consents;
+pub mod status;
+pub mod tags;
+
+pub use index::*;
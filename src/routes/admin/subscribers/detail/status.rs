@@ -0,0 +1,62 @@
+use crate::authentication::UserId;
+use crate::models::{Subscriber, SubscriptionStatus};
+use crate::utils::{ResponseMessage, e400, e404, e409, e500};
+use actix_web::http::header::ContentType;
+use actix_web::{HttpResponse, put, web};
+use anyhow::Context;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct UpdateStatusParams {
+    status: SubscriptionStatus,
+}
+
+#[put("/subscribers/{subscriber_id}/status")]
+#[tracing::instrument(
+    name = "Changing the status of a subscriber",
+    skip_all,
+    fields(user_id=%&*user_id, status=?params.status)
+)]
+pub async fn put(
+    params: web::Json<UpdateStatusParams>,
+    path: web::Path<(Uuid,)>,
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    let subscriber_id = path.into_inner().0;
+    let subscriber = Subscriber::find_by_user_id_and_id(*user_id, &subscriber_id, &pool)
+        .await
+        .context("Failed to find subscriber.")
+        .map_err(e404)?;
+    let current_status = SubscriptionStatus::parse(&subscriber.status).map_err(e500)?;
+    if !current_status.writer_can_change_to(params.status) {
+        return Err(e400(format!(
+            "A {} subscription can't be changed to {}.",
+            current_status.as_str(),
+            params.status.as_str()
+        )));
+    }
+    let updated = Subscriber::update_status(
+        *user_id,
+        &subscriber_id,
+        current_status,
+        params.status,
+        &pool,
+    )
+    .await
+    .context("Failed to update subscriber status.")
+    .map_err(e500)?;
+
+    if !updated {
+        return Err(e409("The subscriber's status changed in the meantime."));
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(ResponseMessage::from(
+            "The subscriber's status has been updated.",
+        )))
+}
@@ -0,0 +1,46 @@
+use crate::authentication::UserId;
+use crate::domain::SubscriberTag;
+use crate::models::Subscriber;
+use crate::utils::{ResponseMessage, e400, e404, e500};
+use actix_web::http::header::ContentType;
+use actix_web::{HttpResponse, put, web};
+use anyhow::Context;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct UpdateTagsParams {
+    tags: Vec<String>,
+}
+
+#[put("/subscribers/{subscriber_id}/tags")]
+#[tracing::instrument(
+    name = "Changing the tags of a subscriber",
+    skip_all,
+    fields(user_id=%&*user_id)
+)]
+pub async fn put(
+    params: web::Json<UpdateTagsParams>,
+    path: web::Path<(Uuid,)>,
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    let subscriber_id = path.into_inner().0;
+    let tags = SubscriberTag::parse_all(params.0.tags).map_err(e400)?;
+    let updated = Subscriber::set_tags(*user_id, &subscriber_id, &tags, &pool)
+        .await
+        .context("Failed to update subscriber tags.")
+        .map_err(e500)?;
+
+    if !updated {
+        return Err(e404("Subscriber not found."));
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(ResponseMessage::from(
+            "The subscriber's tags have been updated.",
+        )))
+}
@@ -0,0 +1,74 @@
+use crate::authentication::UserId;
+use crate::models::Subscriber;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::web::Bytes;
+use actix_web::{HttpResponse, get, web};
+use anyhow::Context;
+use futures_util::{StreamExt, stream};
+use sqlx::PgPool;
+
+/// Number of subscribers read from the database per chunk of the export.
+const EXPORT_BATCH_SIZE: i64 = 500;
+
+#[get("/subscribers/export")]
+#[tracing::instrument(
+    name = "Exporting user's subscribers",
+    skip_all,
+    fields(user_id=%&*user_id)
+)]
+pub async fn get(pool: web::Data<PgPool>, user_id: web::ReqData<UserId>) -> HttpResponse {
+    let user_id = *user_id.into_inner();
+    let pool = pool.into_inner();
+    let header =
+        stream::once(async { Ok(Bytes::from_static(b"email,name,status,subscribed_at\n")) });
+    // Read the list in batches rather than all at once, so large lists are streamed without
+    // holding a connection for the whole download.
+    let rows = stream::try_unfold(Some(None), move |after| {
+        let pool = pool.clone();
+        async move {
+            let Some(after) = after else {
+                return Ok::<_, anyhow::Error>(None);
+            };
+            let subscribers =
+                Subscriber::get_batch_by_user_id(user_id, after, EXPORT_BATCH_SIZE, &pool)
+                    .await
+                    .context("Failed to query subscribers.")?;
+            let Some(last) = subscribers.last() else {
+                return Ok(None);
+            };
+            // A short batch is the last one.
+            let next = (subscribers.len() as i64 == EXPORT_BATCH_SIZE)
+                .then_some(Some((last.subscribed_at, last.id)));
+            Ok(Some((to_csv(&subscribers)?, next)))
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(String::from("subscribers.csv"))],
+        })
+        .streaming(header.chain(rows))
+}
+
+fn to_csv(subscribers: &[Subscriber]) -> Result<Bytes, anyhow::Error> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(Vec::new());
+    for subscriber in subscribers {
+        writer
+            .write_record([
+                subscriber.email.as_str(),
+                subscriber.name.as_str(),
+                subscriber.status.as_str(),
+                subscriber.subscribed_at.to_rfc3339().as_str(),
+            ])
+            .context("Failed to write a subscriber as CSV.")?;
+    }
+    let bytes = writer
+        .into_inner()
+        .context("Failed to flush the CSV writer.")?;
+
+    Ok(Bytes::from(bytes))
+}
@@ -0,0 +1,219 @@
+use crate::authentication::UserId;
+use crate::confirmation_email_worker::enqueue_confirmation_email;
+use crate::domain::{ConsentText, SubscriberEmail, SubscriberName};
+use crate::models::{ConsentEvidence, ConsentSource, SubscriptionConsent, SubscriptionStatus};
+use crate::routes::subscriptions::{consent_evidence, generate_subscription_token, store_token};
+use crate::utils::{e400, e500};
+use actix_web::http::header::ContentType;
+use actix_web::{HttpRequest, HttpResponse, post, web};
+use anyhow::Context;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Recorded as the consent text of imported subscribers when the writer doesn't provide one.
+pub const IMPORT_CONSENT_TEXT: &str = "Imported by the writer from another platform.";
+
+#[derive(Deserialize)]
+pub struct ImportParams {
+    /// How the imported subscribers gave their consent on the previous platform.
+    #[serde(default)]
+    consent_text: Option<String>,
+    /// CSV with an `email` and `name` column, and optionally a `status` column.
+    csv: String,
+    /// Ask every imported subscriber to confirm their subscription again, instead of trusting
+    /// the status carried over from the previous platform.
+    #[serde(default)]
+    send_confirmation_emails: bool,
+}
+
+#[derive(Deserialize)]
+struct ImportRow {
+    email: String,
+    name: String,
+    status: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ImportRowError {
+    /// Line of the CSV the row was read from, counting the header as line 1.
+    pub line: u64,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ImportReport {
+    pub errors: Vec<ImportRowError>,
+    pub imported: u64,
+}
+
+struct ValidRow {
+    email: SubscriberEmail,
+    name: SubscriberName,
+    status: SubscriptionStatus,
+}
+
+#[post("/subscribers/import")]
+#[tracing::instrument(
+    name = "Importing subscribers",
+    skip_all,
+    fields(user_id=%&*user_id, send_confirmation_emails=params.send_confirmation_emails)
+)]
+pub async fn post(
+    params: web::Json<ImportParams>,
+    pool: web::Data<PgPool>,
+    request: HttpRequest,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    let params = params.0;
+    let consent_text = params
+        .consent_text
+        .unwrap_or_else(|| String::from(IMPORT_CONSENT_TEXT));
+    let consent_text = ConsentText::parse(consent_text).map_err(e400)?;
+    let consent = consent_evidence(&request, ConsentSource::Import, consent_text);
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(params.csv.as_bytes());
+    let headers = reader
+        .headers()
+        .context("Failed to read the CSV header.")
+        .map_err(e400)?
+        .clone();
+    if !headers.iter().any(|h| h == "email") || !headers.iter().any(|h| h == "name") {
+        return Err(e400(
+            "The CSV header must contain an email and a name column.",
+        ));
+    }
+
+    let mut report = ImportReport {
+        errors: Vec::new(),
+        imported: 0,
+    };
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")
+        .map_err(e500)?;
+    for record in reader.records() {
+        let (line, row) = match record {
+            Ok(record) => (
+                record.position().map_or(0, |position| position.line()),
+                parse_row(&record, &headers, params.send_confirmation_emails),
+            ),
+            Err(e) => (
+                e.position().map_or(0, |position| position.line()),
+                Err(String::from("The row is not valid CSV.")),
+            ),
+        };
+        let row = match row {
+            Ok(row) => row,
+            Err(error) => {
+                report.errors.push(ImportRowError { line, error });
+                continue;
+            }
+        };
+        let imported = import_subscriber(&mut transaction, *user_id, &row, &consent)
+            .await
+            .context("Failed to import a subscriber.")
+            .map_err(e500)?;
+        if imported {
+            report.imported += 1;
+        } else {
+            report.errors.push(ImportRowError {
+                line,
+                error: format!("{} is already subscribed.", row.email.as_ref()),
+            });
+        }
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to import subscribers.")
+        .map_err(e500)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(report))
+}
+
+fn parse_row(
+    record: &csv::StringRecord,
+    headers: &csv::StringRecord,
+    send_confirmation_emails: bool,
+) -> Result<ValidRow, String> {
+    let row: ImportRow = record
+        .deserialize(Some(headers))
+        .map_err(|_| String::from("The row must contain an email and a name."))?;
+    let email = SubscriberEmail::parse(row.email)?;
+    let name = SubscriberName::parse(row.name)?;
+    let status = match row.status {
+        Some(status) => SubscriptionStatus::parse(&status)?,
+        None => SubscriptionStatus::Confirmed,
+    };
+    // Re-permission everyone except those who had already unsubscribed.
+    let status = if send_confirmation_emails && status != SubscriptionStatus::Unsubscribed {
+        SubscriptionStatus::PendingConfirmation
+    } else {
+        status
+    };
+
+    Ok(ValidRow {
+        email,
+        name,
+        status,
+    })
+}
+
+/// Returns `false`, leaving the existing subscriber untouched, if the email is already on the
+/// writer's list. Subscribers imported as pending confirmation are sent a confirmation email.
+#[tracing::instrument(
+    name = "Saving an imported subscriber in the database",
+    skip(transaction, row, consent)
+)]
+async fn import_subscriber(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    row: &ValidRow,
+    consent: &ConsentEvidence,
+) -> Result<bool, anyhow::Error> {
+    let subscriber_id = sqlx::query!(
+        r#"
+          INSERT INTO subscriptions (
+            id,
+            email,
+            name,
+            subscribed_at,
+            status,
+            unsubscribe_token,
+            user_id
+          )
+          VALUES ($1, $2, $3, $4, $5, $6, $7)
+          ON CONFLICT (email, user_id) DO NOTHING
+          RETURNING id
+        "#,
+        Uuid::new_v4(),
+        row.email.as_ref(),
+        row.name.as_ref(),
+        Utc::now(),
+        row.status.as_str(),
+        generate_subscription_token(),
+        user_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    .map(|record| record.id);
+    let Some(subscriber_id) = subscriber_id else {
+        return Ok(false);
+    };
+    SubscriptionConsent::insert_txn(&subscriber_id, consent, transaction).await?;
+    if row.status == SubscriptionStatus::PendingConfirmation {
+        let subscription_token = generate_subscription_token();
+        store_token(transaction, subscriber_id, &subscription_token).await?;
+        enqueue_confirmation_email(transaction, subscriber_id, &subscription_token).await?;
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,43 @@
+use crate::authentication::UserId;
+use crate::models::{SUBSCRIBER_PAGE_SIZE, SubscriberFilter, SubscriberPage};
+use crate::utils::{e400, e500};
+use actix_web::http::header::ContentType;
+use actix_web::{HttpResponse, get, web};
+use anyhow::Context;
+use serde::Deserialize;
+use sqlx::PgPool;
+
+#[derive(Deserialize)]
+pub struct Parameters {
+    page: Option<i64>,
+}
+
+#[get("/subscribers")]
+#[tracing::instrument(
+    name = "Retrieving user's subscribers",
+    skip_all,
+    fields(user_id=%&*user_id)
+)]
+pub async fn get(
+    parameters: web::Query<Parameters>,
+    filter: web::Query<SubscriberFilter>,
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    let page = parameters.page.unwrap_or(1);
+    if page < 1 {
+        return Err(e400("Page must be a positive number."));
+    }
+    if (page - 1).checked_mul(SUBSCRIBER_PAGE_SIZE).is_none() {
+        return Err(e400("Page is out of range."));
+    }
+    let subscribers = SubscriberPage::get_by_user_id(*user_id, &filter, page, &pool)
+        .await
+        .context("Failed to query subscribers.")
+        .map_err(e500)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(subscribers))
+}
@@ -0,0 +1,7 @@
+mod index;
+
+pub mod detail;
+pub mod export;
+pub mod import;
+
+pub use index::*;
@@ -0,0 +1,79 @@
+use crate::authentication::UserId;
+use crate::models::ConfirmationEmailTemplate;
+use crate::utils::{ResponseMessage, e400, e500};
+use actix_web::http::header::ContentType;
+use actix_web::{HttpResponse, delete, get, put, web};
+use anyhow::Context;
+use sqlx::PgPool;
+
+#[get("/user/confirmation_email")]
+#[tracing::instrument(
+  name = "Get confirmation email template",
+  skip_all,
+  fields(user_id=%*user_id)
+)]
+pub async fn get(
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    let template = ConfirmationEmailTemplate::find_by_user_id(*user_id, &pool)
+        .await
+        .context("Failed to find confirmation email template.")
+        .map_err(e500)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(template))
+}
+
+#[put("/user/confirmation_email")]
+#[tracing::instrument(
+  name = "Updating confirmation email template",
+  skip_all,
+  fields(user_id=%*user_id)
+)]
+pub async fn put(
+    params: web::Json<ConfirmationEmailTemplate>,
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    params
+        .0
+        .validate()
+        .map_err(e400)?
+        .update(*user_id, &pool)
+        .await
+        .context("Failed to update confirmation email template.")
+        .map_err(e500)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(ResponseMessage::from(
+            "The confirmation email has been updated.",
+        )))
+}
+
+#[delete("/user/confirmation_email")]
+#[tracing::instrument(
+  name = "Resetting confirmation email template",
+  skip_all,
+  fields(user_id=%*user_id)
+)]
+pub async fn delete(
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner();
+    ConfirmationEmailTemplate::delete(*user_id, &pool)
+        .await
+        .context("Failed to reset confirmation email template.")
+        .map_err(e500)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(ResponseMessage::from(
+            "The confirmation email has been reset to the default.",
+        )))
+}
@@ -2,5 +2,6 @@ mod index;
 
 pub mod avatar;
 pub mod banner;
+pub mod confirmation_email;
 
 pub use index::*;
@@ -0,0 +1,5 @@
+mod index;
+
+pub mod ready;
+
+pub use index::*;
@@ -0,0 +1,102 @@
+use crate::clients::s3_client::S3Client;
+use crate::models::WorkerStatus;
+use actix_session::storage::{RedisSessionStore, SessionKey, SessionStore};
+use actix_web::http::header::ContentType;
+use actix_web::{HttpResponse, get, web};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::future::Future;
+use std::time::Duration;
+
+/// How long a single dependency may take to answer before it is reported as down.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeStatus {
+    Up,
+    Down,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReadinessReport {
+    pub postgres: ProbeStatus,
+    pub ready: bool,
+    pub redis: ProbeStatus,
+    pub s3: ProbeStatus,
+    pub worker: Option<WorkerStatus>,
+}
+
+/// Readiness probe: checks every dependency needed to serve traffic.
+/// Responds with 503 when any of them is unreachable so the instance is taken out of rotation.
+/// The worker heartbeat and queue depth are reported but do not affect readiness.
+#[get("/health_check/ready")]
+#[tracing::instrument(name = "Checking readiness", skip_all)]
+pub async fn get(
+    pool: web::Data<PgPool>,
+    redis_store: web::Data<RedisSessionStore>,
+    s3_client: web::Data<S3Client>,
+) -> HttpResponse {
+    let (postgres, redis, s3) = tokio::join!(
+        probe("postgres", async {
+            sqlx::query("SELECT 1").execute(pool.get_ref()).await?;
+            Ok(())
+        }),
+        probe("redis", async {
+            let session_key = SessionKey::try_from(String::from("readiness-probe"))?;
+            redis_store.load(&session_key).await?;
+            Ok(())
+        }),
+        probe("s3", s3_client.check_images_bucket()),
+    );
+    let worker = match postgres {
+        ProbeStatus::Up => probe_value("worker status", WorkerStatus::get(&pool)).await,
+        ProbeStatus::Down => None,
+    };
+    let ready = [&postgres, &redis, &s3]
+        .iter()
+        .all(|status| **status == ProbeStatus::Up);
+    let report = ReadinessReport {
+        postgres,
+        ready,
+        redis,
+        s3,
+        worker,
+    };
+
+    if ready {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::ServiceUnavailable()
+    }
+    .content_type(ContentType::json())
+    .json(report)
+}
+
+async fn probe(
+    dependency: &str,
+    check: impl Future<Output = Result<(), anyhow::Error>>,
+) -> ProbeStatus {
+    match probe_value(dependency, check).await {
+        Some(()) => ProbeStatus::Up,
+        None => ProbeStatus::Down,
+    }
+}
+
+async fn probe_value<T, E>(dependency: &str, check: impl Future<Output = Result<T, E>>) -> Option<T>
+where
+    E: Into<anyhow::Error>,
+{
+    let error = match tokio::time::timeout(PROBE_TIMEOUT, check).await {
+        Ok(Ok(value)) => return Some(value),
+        Ok(Err(e)) => e.into(),
+        Err(_) => anyhow::anyhow!("Timed out after {PROBE_TIMEOUT:?}"),
+    };
+    tracing::warn!(
+        error.cause_chain = ?error,
+        error.message = %error,
+        "Readiness probe for {} failed",
+        dependency
+    );
+    None
+}
@@ -6,3 +6,4 @@ pub mod login;
 pub mod newsletters;
 pub mod subscriptions;
 pub mod users;
+pub mod webhooks;
@@ -1,8 +1,11 @@
+use crate::models::SubscriptionConsent;
+use crate::startup::ConfirmationTokenTtl;
 use crate::utils::error_chain_fmt;
 use actix_web::http::StatusCode;
 use actix_web::{HttpResponse, ResponseError, put, web};
 use anyhow::Context;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
 #[derive(serde::Deserialize)]
@@ -16,6 +19,8 @@ pub enum ConfirmationError {
     UnexpectedError(#[from] anyhow::Error),
     #[error("There is no subscriber associated with the provided token.")]
     UnknownToken,
+    #[error("The confirmation token has expired. Subscribe again to receive a new one.")]
+    ExpiredToken,
 }
 
 impl std::fmt::Debug for ConfirmationError {
@@ -28,48 +33,98 @@ impl ResponseError for ConfirmationError {
     fn status_code(&self) -> StatusCode {
         match self {
             Self::UnknownToken => StatusCode::UNAUTHORIZED,
+            Self::ExpiredToken => StatusCode::GONE,
             Self::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
 
 #[put("/subscriptions/confirm")]
-#[tracing::instrument(name = "Confirm a pending subscriber", skip(parameters, pool))]
+#[tracing::instrument(
+    name = "Confirm a pending subscriber",
+    skip(parameters, pool, token_ttl)
+)]
 pub async fn put(
     parameters: web::Query<Parameters>,
     pool: web::Data<PgPool>,
+    token_ttl: web::Data<ConfirmationTokenTtl>,
 ) -> Result<HttpResponse, ConfirmationError> {
-    let subscriber_id = get_subscriber_id_from_token(&pool, &parameters.subscription_token)
+    let token = get_subscription_token(&pool, &parameters.subscription_token)
         .await
         .context("Failed to retrieve the subscriber id associated with the provided token.")?
         .ok_or(ConfirmationError::UnknownToken)?;
-    confirm_subscriber(&pool, subscriber_id)
+    if Utc::now() - token.created_at > token_ttl.0 {
+        return Err(ConfirmationError::ExpiredToken);
+    }
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool.")?;
+    let confirmed = confirm_subscriber(&mut transaction, token.subscriber_id)
         .await
         .context("Failed to update the subscriber status to `confirmed`.")?;
+    if confirmed {
+        SubscriptionConsent::confirm_txn(&token.subscriber_id, &mut transaction)
+            .await
+            .context("Failed to record the subscriber's confirmation.")?;
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the SQL transaction to confirm a subscriber.")?;
+    if !confirmed {
+        return Err(ConfirmationError::UnknownToken);
+    }
     Ok(HttpResponse::Ok().finish())
 }
 
-#[tracing::instrument(name = "Mark subscriber as confirmed", skip(subscriber_id, pool))]
-pub async fn confirm_subscriber(pool: &PgPool, subscriber_id: Uuid) -> Result<(), sqlx::Error> {
+/// Confirm a subscriber that is still pending confirmation and delete its tokens, so that
+/// a confirmation link can't be replayed. Returns `false` if the subscriber was not pending.
+#[tracing::instrument(
+    name = "Mark subscriber as confirmed",
+    skip(subscriber_id, transaction)
+)]
+pub async fn confirm_subscriber(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+          UPDATE subscriptions SET status = 'confirmed'
+          WHERE id = $1 AND status = 'pending_confirmation'
+        "#,
+        subscriber_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
     sqlx::query!(
-        r#"UPDATE subscriptions SET status = 'confirmed' WHERE id = $1"#,
+        "DELETE FROM subscription_tokens WHERE subscriber_id = $1",
         subscriber_id,
     )
-    .execute(pool)
+    .execute(&mut **transaction)
     .await?;
-    Ok(())
+    Ok(result.rows_affected() == 1)
+}
+
+pub struct SubscriptionToken {
+    pub subscriber_id: Uuid,
+    pub created_at: DateTime<Utc>,
 }
 
-#[tracing::instrument(name = "Get subscriber_id from token", skip(subscription_token, pool))]
-pub async fn get_subscriber_id_from_token(
+#[tracing::instrument(name = "Get subscription token", skip(subscription_token, pool))]
+pub async fn get_subscription_token(
     pool: &PgPool,
     subscription_token: &str,
-) -> Result<Option<Uuid>, sqlx::Error> {
-    let result = sqlx::query!(
-        r#"SELECT subscriber_id FROM subscription_tokens WHERE subscription_token = $1"#,
+) -> Result<Option<SubscriptionToken>, sqlx::Error> {
+    sqlx::query_as!(
+        SubscriptionToken,
+        r#"
+          SELECT subscriber_id, created_at
+          FROM subscription_tokens
+          WHERE subscription_token = $1
+        "#,
         subscription_token,
     )
     .fetch_optional(pool)
-    .await?;
-    Ok(result.map(|r| r.subscriber_id))
+    .await
 }
@@ -1,8 +1,10 @@
-use crate::domain::{NewSubscriber, SubscriberEmail, SubscriberName};
-use crate::email_client::EmailClient;
-use crate::startup::ApplicationBaseUrl;
+use crate::challenge::{Challenger, RedeemedChallenges};
+use crate::confirmation_email_worker::enqueue_confirmation_email;
+use crate::domain::{ConsentText, NewSubscriber, SubscriberEmail, SubscriberName, SubscriberTag};
+use crate::models::{ConsentEvidence, ConsentSource, Subscriber, SubscriptionConsent};
 use crate::utils::{e400, e500, error_chain_fmt};
-use actix_web::{HttpResponse, post, web};
+use actix_web::http::header;
+use actix_web::{HttpRequest, HttpResponse, post, web};
 use anyhow::Context;
 use chrono::Utc;
 use rand::distributions::Alphanumeric;
@@ -14,8 +16,19 @@ use uuid::Uuid;
 
 #[derive(Deserialize, Serialize)]
 pub struct SubscribeParams {
+    answer: String,
+    challenge: String,
+    /// The exact wording the reader agreed to, kept as evidence of consent.
+    #[serde(default)]
+    consent_text: Option<String>,
     email: String,
     name: String,
+    /// Whether the reader signed up through a form, the default, or a direct API call.
+    #[serde(default)]
+    source: Option<ConsentSource>,
+    /// Topics the reader is interested in, letting the writer target issues at them.
+    #[serde(default)]
+    tags: Vec<String>,
     user_id: Uuid,
 }
 
@@ -25,11 +38,13 @@ impl TryFrom<SubscribeParams> for NewSubscriber {
     fn try_from(params: SubscribeParams) -> Result<Self, Self::Error> {
         let name = SubscriberName::parse(params.name)?;
         let email = SubscriberEmail::parse(params.email)?;
+        let tags = SubscriberTag::parse_all(params.tags)?;
         let user_id = params.user_id;
 
         Ok(Self {
             email,
             name,
+            tags,
             user_id,
         })
     }
@@ -38,7 +53,7 @@ impl TryFrom<SubscribeParams> for NewSubscriber {
 #[post("/subscriptions")]
 #[tracing::instrument(
     name = "Adding a new subscriber",
-    skip(params, pool, email_client, base_url),
+    skip(params, pool, challenger, redeemed_challenges, request),
     fields(
         subscriber_email = %params.email,
         subscriber_name = %params.name
@@ -47,43 +62,113 @@ impl TryFrom<SubscribeParams> for NewSubscriber {
 pub async fn post(
     params: web::Json<SubscribeParams>,
     pool: web::Data<PgPool>,
-    email_client: web::Data<EmailClient>,
-    base_url: web::Data<ApplicationBaseUrl>,
+    challenger: web::Data<dyn Challenger>,
+    redeemed_challenges: web::Data<RedeemedChallenges>,
+    request: HttpRequest,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let new_subscriber = params.0.try_into().map_err(e400)?;
+    let mut params = params.0;
+    let challenge_id = challenger
+        .verify(&params.challenge, &params.answer, Utc::now())
+        .context("Failed to verify the captcha challenge.")
+        .map_err(e400)?;
+    let consent_text = match params.consent_text.take() {
+        Some(consent_text) => ConsentText::parse(consent_text).map_err(e400)?,
+        None => ConsentText::default(),
+    };
+    let source = params.source.unwrap_or(ConsentSource::Form);
+    if source == ConsentSource::Import {
+        return Err(e400("Only writers can import subscribers."));
+    }
+    let consent = consent_evidence(&request, source, consent_text);
+    let new_subscriber = params.try_into().map_err(e400)?;
+    let redeemed = redeemed_challenges
+        .redeem(challenge_id)
+        .await
+        .context("Failed to redeem the captcha challenge.")
+        .map_err(e500)?;
+    if !redeemed {
+        return Err(e400("The captcha challenge has already been used."));
+    }
     let mut transaction = pool
         .begin()
         .await
         .context("Failed to acquire a Postgres connection from the pool")
         .map_err(e500)?;
-    let subscriber_id = insert_subscriber(&mut transaction, &new_subscriber)
+    let existing = get_existing_subscriber(&mut transaction, &new_subscriber)
+        .await
+        .context("Failed to look up an existing subscriber in the database.")
+        .map_err(e500)?;
+    let subscriber_id = match existing {
+        // Nothing to confirm, so don't let the endpoint be used to spam confirmed subscribers.
+        Some(subscriber) if subscriber.status == "confirmed" => {
+            return Ok(HttpResponse::Ok().finish());
+        }
+        // The address bounced or complained, so never email it again, but don't reveal that.
+        Some(subscriber) if subscriber.status == "bounced" || subscriber.status == "complained" => {
+            return Ok(HttpResponse::Ok().finish());
+        }
+        // Subscribing again with a pending or unsubscribed email issues a fresh token and email.
+        Some(subscriber) => {
+            mark_subscriber_pending(&mut transaction, subscriber.id)
+                .await
+                .context("Failed to mark an existing subscriber as pending confirmation.")
+                .map_err(e500)?;
+            subscriber.id
+        }
+        None => insert_subscriber(&mut transaction, &new_subscriber)
+            .await
+            .context("Failed to insert new subscriber in the database.")
+            .map_err(e500)?,
+    };
+    SubscriptionConsent::insert_txn(&subscriber_id, &consent, &mut transaction)
+        .await
+        .context("Failed to record the consent of a new subscriber.")
+        .map_err(e500)?;
+    Subscriber::add_tags_txn(&subscriber_id, &new_subscriber.tags, &mut transaction)
         .await
-        .context("Failed to insert new subscriber in the database.")
+        .context("Failed to tag a new subscriber.")
         .map_err(e500)?;
     let subscription_token = generate_subscription_token();
     store_token(&mut transaction, subscriber_id, &subscription_token)
         .await
         .context("Failed to store the confirmation token for a new subscriber.")
         .map_err(e500)?;
+    enqueue_confirmation_email(&mut transaction, subscriber_id, &subscription_token)
+        .await
+        .context("Failed to queue the confirmation email for a new subscriber.")
+        .map_err(e500)?;
     transaction
         .commit()
         .await
         .context("Failed to commit SQL transaction to store a new subscriber.")
         .map_err(e500)?;
-    send_confirmation_email(
-        &email_client,
-        new_subscriber,
-        &base_url.0,
-        &subscription_token,
-    )
-    .await
-    .context("Failed to send a confirmation email.")
-    .map_err(e500)?;
 
     Ok(HttpResponse::Ok().finish())
 }
 
-fn generate_subscription_token() -> String {
+/// Gather consent evidence from the request that carried it. The client IP is the peer
+/// of the connection: forwarding headers are ignored since any client can set them.
+pub fn consent_evidence(
+    request: &HttpRequest,
+    source: ConsentSource,
+    consent_text: ConsentText,
+) -> ConsentEvidence {
+    let ip_address = request.peer_addr().map(|address| address.ip().to_string());
+    let user_agent = request
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|user_agent| user_agent.to_str().ok())
+        .map(String::from);
+
+    ConsentEvidence {
+        consent_text,
+        ip_address,
+        source,
+        user_agent,
+    }
+}
+
+pub fn generate_subscription_token() -> String {
     let mut rng = thread_rng();
     std::iter::repeat_with(|| rng.sample(Alphanumeric))
         .map(char::from)
@@ -91,31 +176,54 @@ fn generate_subscription_token() -> String {
         .collect()
 }
 
+struct ExistingSubscriber {
+    id: Uuid,
+    status: String,
+}
+
 #[tracing::instrument(
-    name = "Send a confirmation email to a new subscriber",
-    skip(email_client, new_subscriber, base_url, subscription_token)
+    name = "Looking up an existing subscriber",
+    skip(new_subscriber, transaction)
 )]
-async fn send_confirmation_email(
-    email_client: &EmailClient,
-    new_subscriber: NewSubscriber,
-    base_url: &str,
-    subscription_token: &str,
-) -> Result<(), reqwest::Error> {
-    let confirmation_link = format!(
-        "{}/subscriptions/confirm?subscription_token={}",
-        base_url, subscription_token
-    );
-    let plain_body = format!(
-        "Welcome to our newsletter!\nVisit {} to confirm your subscription.",
-        confirmation_link
-    );
-    let html_body = format!(
-        "Welcome to our newsletter!<br />Click <a href=\"{}\">here</a> to confirm your subscription.",
-        confirmation_link
-    );
-    email_client
-        .send_email(&new_subscriber.email, "Welcome!", &html_body, &plain_body)
-        .await
+async fn get_existing_subscriber(
+    transaction: &mut Transaction<'_, Postgres>,
+    new_subscriber: &NewSubscriber,
+) -> Result<Option<ExistingSubscriber>, sqlx::Error> {
+    sqlx::query_as!(
+        ExistingSubscriber,
+        r#"
+          SELECT id, status
+          FROM subscriptions
+          WHERE email = $1 AND user_id = $2
+          FOR UPDATE
+        "#,
+        new_subscriber.email.as_ref(),
+        new_subscriber.user_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+}
+
+#[tracing::instrument(
+    name = "Marking an existing subscriber as pending confirmation",
+    skip(transaction)
+)]
+async fn mark_subscriber_pending(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    transaction
+        .execute(sqlx::query!(
+            r#"
+              UPDATE subscriptions
+              SET status = 'pending_confirmation', confirmation_reminder_sent_at = NULL
+              WHERE id = $1
+            "#,
+            subscriber_id
+        ))
+        .await?;
+
+    Ok(())
 }
 
 #[tracing::instrument(
@@ -136,14 +244,16 @@ async fn insert_subscriber(
                 name,
                 subscribed_at,
                 status,
+                unsubscribe_token,
                 user_id
               )
-              VALUES ($1, $2, $3, $4, 'pending_confirmation', $5)
+              VALUES ($1, $2, $3, $4, 'pending_confirmation', $5, $6)
             "#,
             subscriber_id,
             new_subscriber.email.as_ref(),
             new_subscriber.name.as_ref(),
             Utc::now(),
+            generate_subscription_token(),
             new_subscriber.user_id
         ))
         .await?;
@@ -155,7 +265,7 @@ async fn insert_subscriber(
     name = "Store subscription token in the database",
     skip(subscription_token, transaction)
 )]
-async fn store_token(
+pub async fn store_token(
     transaction: &mut Transaction<'_, Postgres>,
     subscriber_id: Uuid,
     subscription_token: &str,
@@ -175,7 +285,7 @@ async fn store_token(
     Ok(())
 }
 
-struct StoreTokenError(sqlx::Error);
+pub struct StoreTokenError(sqlx::Error);
 
 impl std::error::Error for StoreTokenError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
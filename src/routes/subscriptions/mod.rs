@@ -1,5 +1,7 @@
 mod index;
 
 pub mod confirm;
+pub mod preferences;
+pub mod unsubscribe;
 
 pub use index::*;
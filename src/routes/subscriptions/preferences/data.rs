@@ -0,0 +1,65 @@
+use super::{Parameters, PreferencesError, get_preference_token_email};
+use crate::erasure_confirmation_worker::enqueue_erasure_confirmation;
+use crate::models::{SubscriberDataExport, erase_subscriber_data};
+use crate::startup::PreferenceTokenTtl;
+use actix_web::http::header::{ContentDisposition, ContentType, DispositionParam, DispositionType};
+use actix_web::{HttpResponse, delete, get, web};
+use anyhow::Context;
+use sqlx::PgPool;
+
+/// Export everything stored about the reader's email address, across all writers.
+#[get("/subscriptions/preferences/data")]
+#[tracing::instrument(
+    name = "Exporting a reader's personal data",
+    skip(parameters, pool, token_ttl)
+)]
+pub async fn get(
+    parameters: web::Query<Parameters>,
+    pool: web::Data<PgPool>,
+    token_ttl: web::Data<PreferenceTokenTtl>,
+) -> Result<HttpResponse, PreferencesError> {
+    let email = get_preference_token_email(&pool, &parameters.preference_token, &token_ttl).await?;
+    let export = SubscriberDataExport::get_by_email(&email, &pool)
+        .await
+        .context("Failed to export the reader's personal data.")?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename("personal_data.json".to_string())],
+        })
+        .json(export))
+}
+
+/// Erase the reader's email address across all writers, and queue a confirmation email in
+/// the same transaction. The erasure also revokes the preference token, so the outbox worker
+/// retries the confirmation on the reader's behalf.
+#[delete("/subscriptions/preferences/data")]
+#[tracing::instrument(
+    name = "Erasing a reader's personal data",
+    skip(parameters, pool, token_ttl)
+)]
+pub async fn delete(
+    parameters: web::Query<Parameters>,
+    pool: web::Data<PgPool>,
+    token_ttl: web::Data<PreferenceTokenTtl>,
+) -> Result<HttpResponse, PreferencesError> {
+    let email = get_preference_token_email(&pool, &parameters.preference_token, &token_ttl).await?;
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool.")?;
+    erase_subscriber_data(&mut transaction, &email)
+        .await
+        .context("Failed to erase the reader's personal data.")?;
+    enqueue_erasure_confirmation(&mut transaction, &email)
+        .await
+        .context("Failed to queue the erasure confirmation email.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the SQL transaction to erase the reader's personal data.")?;
+
+    Ok(HttpResponse::Ok().finish())
+}
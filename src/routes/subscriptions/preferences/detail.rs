@@ -0,0 +1,63 @@
+use super::{Parameters, PreferencesError, get_preference_token_email};
+use crate::domain::SubscriberName;
+use crate::models::{ReaderSubscription, SubscriptionStatus};
+use crate::startup::PreferenceTokenTtl;
+use actix_web::http::header::ContentType;
+use actix_web::{HttpResponse, put, web};
+use anyhow::Context;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct UpdateSubscriptionParams {
+    name: String,
+    status: SubscriptionStatus,
+}
+
+#[put("/subscriptions/preferences/{subscriber_id}")]
+#[tracing::instrument(
+    name = "Updating a reader's subscription",
+    skip(params, parameters, pool, token_ttl),
+    fields(status=?params.status)
+)]
+pub async fn put(
+    params: web::Json<UpdateSubscriptionParams>,
+    parameters: web::Query<Parameters>,
+    path: web::Path<(Uuid,)>,
+    pool: web::Data<PgPool>,
+    token_ttl: web::Data<PreferenceTokenTtl>,
+) -> Result<HttpResponse, PreferencesError> {
+    let subscriber_id = path.into_inner().0;
+    let email = get_preference_token_email(&pool, &parameters.preference_token, &token_ttl).await?;
+    let params = params.0;
+    let name = SubscriberName::parse(params.name).map_err(PreferencesError::ValidationError)?;
+    let subscription = ReaderSubscription::find_by_email_and_id(&email, &subscriber_id, &pool)
+        .await
+        .context("Failed to find the reader's subscription.")?
+        .ok_or(PreferencesError::SubscriptionNotFound)?;
+    let current_status =
+        SubscriptionStatus::parse(&subscription.status).map_err(anyhow::Error::msg)?;
+    if !current_status.reader_can_change_to(params.status) {
+        return Err(PreferencesError::ValidationError(format!(
+            "A {} subscription can't be changed to {}.",
+            current_status.as_str(),
+            params.status.as_str()
+        )));
+    }
+    let updated =
+        ReaderSubscription::update(&subscriber_id, &name, current_status, params.status, &pool)
+            .await
+            .context("Failed to update the reader's subscription.")?;
+    if !updated {
+        return Err(PreferencesError::Conflict);
+    }
+    let subscription = ReaderSubscription::find_by_email_and_id(&email, &subscriber_id, &pool)
+        .await
+        .context("Failed to find the reader's subscription.")?
+        .ok_or(PreferencesError::SubscriptionNotFound)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(subscription))
+}
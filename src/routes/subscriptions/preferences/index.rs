@@ -0,0 +1,157 @@
+use crate::challenge::{Challenger, RedeemedChallenges};
+use crate::domain::SubscriberEmail;
+use crate::models::ReaderSubscription;
+use crate::preference_link_worker::enqueue_preference_link;
+use crate::startup::PreferenceTokenTtl;
+use crate::utils::{e400, e500, error_chain_fmt};
+use actix_web::http::StatusCode;
+use actix_web::http::header::ContentType;
+use actix_web::{HttpResponse, ResponseError, get, post, web};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::PgPool;
+
+#[derive(Deserialize)]
+pub struct PreferenceLinkParams {
+    answer: String,
+    challenge: String,
+    email: String,
+}
+
+#[derive(Deserialize)]
+pub struct Parameters {
+    pub preference_token: String,
+}
+
+#[derive(thiserror::Error)]
+pub enum PreferencesError {
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+    #[error("There is no reader associated with the provided token.")]
+    UnknownToken,
+    #[error("The link has expired. Request a new one to manage your subscriptions.")]
+    ExpiredToken,
+    #[error("Subscription not found.")]
+    SubscriptionNotFound,
+    #[error("The subscription changed in the meantime.")]
+    Conflict,
+    #[error("{0}")]
+    ValidationError(String),
+}
+
+impl std::fmt::Debug for PreferencesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for PreferencesError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::UnknownToken => StatusCode::UNAUTHORIZED,
+            Self::ExpiredToken => StatusCode::GONE,
+            Self::SubscriptionNotFound => StatusCode::NOT_FOUND,
+            Self::Conflict => StatusCode::CONFLICT,
+            Self::ValidationError(_) => StatusCode::BAD_REQUEST,
+            Self::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Queue a magic link to the preference centre. The link is only sent to addresses holding
+/// a subscription, but that is decided by the outbox worker: the request does the same work
+/// either way, so neither its response nor its latency tells who reads whom.
+#[post("/subscriptions/preferences")]
+#[tracing::instrument(
+    name = "Sending a preference centre link",
+    skip_all,
+    fields(subscriber_email = %params.email)
+)]
+pub async fn post(
+    params: web::Json<PreferenceLinkParams>,
+    challenger: web::Data<dyn Challenger>,
+    pool: web::Data<PgPool>,
+    redeemed_challenges: web::Data<RedeemedChallenges>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let params = params.0;
+    let challenge_id = challenger
+        .verify(&params.challenge, &params.answer, Utc::now())
+        .context("Failed to verify the captcha challenge.")
+        .map_err(e400)?;
+    let email = SubscriberEmail::parse(params.email).map_err(e400)?;
+    let redeemed = redeemed_challenges
+        .redeem(challenge_id)
+        .await
+        .context("Failed to redeem the captcha challenge.")
+        .map_err(e500)?;
+    if !redeemed {
+        return Err(e400("The captcha challenge has already been used."));
+    }
+    enqueue_preference_link(&pool, email.as_ref())
+        .await
+        .context("Failed to queue the preference centre link.")
+        .map_err(e500)?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[get("/subscriptions/preferences")]
+#[tracing::instrument(
+    name = "Listing a reader's subscriptions",
+    skip(parameters, pool, token_ttl)
+)]
+pub async fn get(
+    parameters: web::Query<Parameters>,
+    pool: web::Data<PgPool>,
+    token_ttl: web::Data<PreferenceTokenTtl>,
+) -> Result<HttpResponse, PreferencesError> {
+    let email = get_preference_token_email(&pool, &parameters.preference_token, &token_ttl).await?;
+    let subscriptions = ReaderSubscription::get_by_email(&email, &pool)
+        .await
+        .context("Failed to query the reader's subscriptions.")?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(subscriptions))
+}
+
+/// The email address a preference token was issued to, as long as the token hasn't expired.
+pub async fn get_preference_token_email(
+    pool: &PgPool,
+    preference_token: &str,
+    token_ttl: &PreferenceTokenTtl,
+) -> Result<String, PreferencesError> {
+    let token = get_preference_token(pool, preference_token)
+        .await
+        .context("Failed to retrieve the preference token.")?
+        .ok_or(PreferencesError::UnknownToken)?;
+    if Utc::now() - token.created_at > token_ttl.0 {
+        return Err(PreferencesError::ExpiredToken);
+    }
+
+    Ok(token.email)
+}
+
+struct PreferenceToken {
+    created_at: DateTime<Utc>,
+    email: String,
+}
+
+#[tracing::instrument(name = "Get preference token", skip_all)]
+async fn get_preference_token(
+    pool: &PgPool,
+    preference_token: &str,
+) -> Result<Option<PreferenceToken>, sqlx::Error> {
+    sqlx::query_as!(
+        PreferenceToken,
+        r#"
+          SELECT created_at, email
+          FROM preference_tokens
+          WHERE preference_token = $1
+        "#,
+        preference_token,
+    )
+    .fetch_optional(pool)
+    .await
+}
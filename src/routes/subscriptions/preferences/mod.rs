@@ -0,0 +1,6 @@
+mod index;
+
+pub mod data;
+pub mod detail;
+
+pub use index::*;
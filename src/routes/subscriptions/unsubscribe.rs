@@ -0,0 +1,128 @@
+use crate::utils::error_chain_fmt;
+use actix_web::http::StatusCode;
+use actix_web::http::header::ContentType;
+use actix_web::{HttpResponse, ResponseError, get, post, web};
+use anyhow::Context;
+use sqlx::PgPool;
+
+#[derive(serde::Deserialize)]
+pub struct Parameters {
+    unsubscribe_token: String,
+}
+
+#[derive(thiserror::Error)]
+pub enum UnsubscribeError {
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+    #[error("There is no subscriber associated with the provided token.")]
+    UnknownToken,
+}
+
+impl std::fmt::Debug for UnsubscribeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for UnsubscribeError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::UnknownToken => StatusCode::UNAUTHORIZED,
+            Self::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Target of the unsubscribe link in the body of issue emails. Following the link only asks
+/// the reader to confirm, so that link scanners can't unsubscribe them; the form submits
+/// to the one-click `post` handler.
+#[get("/subscriptions/unsubscribe")]
+#[tracing::instrument(
+    name = "Ask a subscriber to confirm unsubscribing",
+    skip(parameters, pool)
+)]
+pub async fn get(
+    parameters: web::Query<Parameters>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, UnsubscribeError> {
+    let known = is_known_unsubscribe_token(&pool, &parameters.unsubscribe_token)
+        .await
+        .context("Failed to look up the unsubscribe token.")?;
+    if !known {
+        return Err(UnsubscribeError::UnknownToken);
+    }
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head><meta charset="utf-8"><title>Unsubscribe</title></head>
+<body>
+<form method="post" action="/subscriptions/unsubscribe?unsubscribe_token={}">
+<p>Do you want to stop receiving this newsletter?</p>
+<button type="submit">Unsubscribe</button>
+</form>
+</body>
+</html>"#,
+            urlencoding::encode(&parameters.unsubscribe_token)
+        )))
+}
+
+/// One-click unsubscribe target of the `List-Unsubscribe` header (RFC 8058).
+/// Mailbox providers POST `List-Unsubscribe=One-Click` as a form body, which is ignored.
+#[post("/subscriptions/unsubscribe")]
+#[tracing::instrument(name = "Unsubscribe a subscriber", skip(parameters, pool))]
+pub async fn post(
+    parameters: web::Query<Parameters>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, UnsubscribeError> {
+    let unsubscribed = unsubscribe_subscriber(&pool, &parameters.unsubscribe_token)
+        .await
+        .context("Failed to update the subscriber status to `unsubscribed`.")?;
+    if !unsubscribed {
+        // Bounced or complained subscribers already receive nothing and keep their status.
+        let known = is_known_unsubscribe_token(&pool, &parameters.unsubscribe_token)
+            .await
+            .context("Failed to look up the unsubscribe token.")?;
+        if !known {
+            return Err(UnsubscribeError::UnknownToken);
+        }
+    }
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[tracing::instrument(name = "Look up an unsubscribe token", skip(unsubscribe_token, pool))]
+pub async fn is_known_unsubscribe_token(
+    pool: &PgPool,
+    unsubscribe_token: &str,
+) -> Result<bool, sqlx::Error> {
+    let subscriber = sqlx::query!(
+        "SELECT id FROM subscriptions WHERE unsubscribe_token = $1",
+        unsubscribe_token,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(subscriber.is_some())
+}
+
+/// Returns `false` if no subscriber has the token, or if its address bounced or complained:
+/// those statuses keep the address suppressed and are never overwritten.
+#[tracing::instrument(
+    name = "Mark subscriber as unsubscribed",
+    skip(unsubscribe_token, pool)
+)]
+pub async fn unsubscribe_subscriber(
+    pool: &PgPool,
+    unsubscribe_token: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE subscriptions SET status = 'unsubscribed'
+        WHERE unsubscribe_token = $1 AND status NOT IN ('bounced', 'complained')
+        "#,
+        unsubscribe_token,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
@@ -0,0 +1,144 @@
+use crate::models::{SuppressedEmail, SuppressionReason};
+use crate::startup::WebhookSecret;
+use crate::utils::error_chain_fmt;
+use actix_web::http::{StatusCode, header};
+use actix_web::web::Bytes;
+use actix_web::{HttpRequest, HttpResponse, ResponseError, post, web};
+use anyhow::Context;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use sqlx::PgPool;
+use subtle::ConstantTimeEq;
+
+/// The fields of a Postmark bounce or spam-complaint webhook this endpoint acts on.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct PostmarkEvent {
+    pub email: String,
+    pub record_type: String,
+    /// The kind of bounce, such as `HardBounce` or `SoftBounce`.
+    #[serde(rename = "Type")]
+    pub bounce_type: Option<String>,
+}
+
+impl PostmarkEvent {
+    /// Only hard bounces and spam complaints suppress an address. Soft bounces and other
+    /// transient failures are left to the delivery retries.
+    fn suppression_reason(&self) -> Option<SuppressionReason> {
+        match (self.record_type.as_str(), self.bounce_type.as_deref()) {
+            ("Bounce", Some("HardBounce")) => Some(SuppressionReason::HardBounce),
+            ("SpamComplaint", _) => Some(SuppressionReason::SpamComplaint),
+            _ => None,
+        }
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum WebhookError {
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+    #[error("The webhook credentials are missing or invalid.")]
+    InvalidCredentials,
+    #[error("The webhook payload is not a valid Postmark event.")]
+    InvalidPayload(#[source] serde_json::Error),
+}
+
+impl std::fmt::Debug for WebhookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for WebhookError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::InvalidCredentials => StatusCode::UNAUTHORIZED,
+            Self::InvalidPayload(_) => StatusCode::BAD_REQUEST,
+            Self::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        if let Self::InvalidCredentials = self {
+            response.insert_header((header::WWW_AUTHENTICATE, r#"Basic realm="webhooks""#));
+        }
+        response.finish()
+    }
+}
+
+/// Receive Postmark bounce and spam-complaint webhooks, putting the address on the
+/// suppression list so no writer sends to it again.
+/// Events that don't call for suppression are acknowledged so Postmark doesn't retry them.
+#[post("/webhooks/postmark")]
+#[tracing::instrument(
+    name = "Receiving a Postmark webhook",
+    skip_all,
+    fields(record_type=tracing::field::Empty, subscriber_email=tracing::field::Empty)
+)]
+pub async fn post(
+    body: Bytes,
+    pool: web::Data<PgPool>,
+    request: HttpRequest,
+    secret: web::Data<WebhookSecret>,
+) -> Result<HttpResponse, WebhookError> {
+    verify_credentials(&request, &secret)?;
+    let event: PostmarkEvent =
+        serde_json::from_slice(&body).map_err(WebhookError::InvalidPayload)?;
+    let span = tracing::Span::current();
+    span.record("record_type", tracing::field::display(&event.record_type));
+    span.record("subscriber_email", tracing::field::display(&event.email));
+    let Some(reason) = event.suppression_reason() else {
+        tracing::info!("Ignoring a Postmark event that doesn't call for suppression");
+        return Ok(HttpResponse::Ok().finish());
+    };
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool.")?;
+    let updated = SuppressedEmail::suppress_txn(&event.email, reason, &mut transaction)
+        .await
+        .context("Failed to suppress the email address.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the SQL transaction to suppress an email address.")?;
+    tracing::info!(
+        "Suppressed the address after a {}, updating {} subscriptions",
+        reason.as_str(),
+        updated
+    );
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Postmark authenticates its webhooks with the basic auth credentials embedded in the
+/// webhook URL, e.g. `https://postmark:<secret>@example.com/webhooks/postmark`.
+/// Only the password is checked, in constant time.
+fn verify_credentials(request: &HttpRequest, secret: &WebhookSecret) -> Result<(), WebhookError> {
+    let password = basic_auth_password(request).ok_or(WebhookError::InvalidCredentials)?;
+    if bool::from(
+        password
+            .as_bytes()
+            .ct_eq(secret.0.expose_secret().as_bytes()),
+    ) {
+        Ok(())
+    } else {
+        Err(WebhookError::InvalidCredentials)
+    }
+}
+
+fn basic_auth_password(request: &HttpRequest) -> Option<String> {
+    let encoded = request
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(STANDARD.decode(encoded).ok()?).ok()?;
+    decoded
+        .split_once(':')
+        .map(|(_, password)| password.to_owned())
+}
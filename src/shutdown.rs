@@ -0,0 +1,74 @@
+use tokio::sync::watch;
+
+/// Create the channel used to tell long-running tasks that the process is shutting down.
+pub fn shutdown_channel() -> (watch::Sender<bool>, watch::Receiver<bool>) {
+    watch::channel(false)
+}
+
+/// Resolve once the process receives `SIGTERM` or `SIGINT` (Ctrl-C).
+/// If the signal handlers cannot be installed the error is logged and this never resolves.
+pub async fn shutdown_signal() {
+    if let Err(e) = wait_for_signal().await {
+        tracing::error!(
+            error.cause_chain = ?e,
+            error.message = %e,
+            "Failed to listen for shutdown signals"
+        );
+        std::future::pending::<()>().await;
+    }
+}
+
+async fn wait_for_signal() -> Result<(), std::io::Error> {
+    #[cfg(unix)]
+    {
+        let mut sigterm =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            outcome = tokio::signal::ctrl_c() => outcome,
+            _ = sigterm.recv() => Ok(()),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await
+    }
+}
+
+/// Sleep for `duration` unless a shutdown is requested first.
+/// Returns `true` when the caller should stop, including when the sender has gone away.
+pub async fn sleep_unless_shutdown(
+    duration: std::time::Duration,
+    shutdown: &mut watch::Receiver<bool>,
+) -> bool {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => false,
+        _ = shutdown.wait_for(|stop| *stop) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shutdown::{shutdown_channel, sleep_unless_shutdown};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn sleeping_is_cut_short_by_a_shutdown() {
+        let (sender, mut receiver) = shutdown_channel();
+        sender.send(true).unwrap();
+
+        let stop = tokio::time::timeout(
+            Duration::from_secs(1),
+            sleep_unless_shutdown(Duration::from_secs(60), &mut receiver),
+        )
+        .await
+        .expect("The sleep should end as soon as a shutdown is requested.");
+        assert!(stop);
+    }
+
+    #[tokio::test]
+    async fn sleeping_runs_to_completion_without_a_shutdown() {
+        let (_sender, mut receiver) = shutdown_channel();
+
+        assert!(!sleep_unless_shutdown(Duration::from_millis(10), &mut receiver).await);
+    }
+}
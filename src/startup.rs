@@ -1,16 +1,17 @@
 use crate::authentication::reject_anonymous_users;
+use crate::challenge::{Challenger, RedeemedChallenges};
 use crate::clients::cloudinary_client::CloudinaryClient;
 use crate::clients::s3_client::S3Client;
 use crate::configuration::{DatabaseSettings, Settings};
 use crate::email_client::EmailClient;
 use crate::routes::{
-    admin, captcha, health_check, index, login, newsletters, subscriptions, users,
+    admin, captcha, health_check, index, login, newsletters, subscriptions, users, webhooks,
 };
 use actix_cors::Cors;
 use actix_session::SessionMiddleware;
 use actix_session::storage::RedisSessionStore;
 use actix_web::cookie::Key;
-use actix_web::dev::Server;
+use actix_web::dev::{Server, ServerHandle};
 use actix_web::middleware::from_fn;
 use actix_web::web::Data;
 use actix_web::{App, HttpServer, web};
@@ -20,6 +21,7 @@ use secrecy::{ExposeSecret, Secret};
 use sqlx::PgPool;
 use sqlx::postgres::PgPoolOptions;
 use std::net::TcpListener;
+use std::sync::Arc;
 use tracing_actix_web::TracingLogger;
 
 pub struct Application {
@@ -32,7 +34,12 @@ impl Application {
         let connection_pool = get_connection_pool(&configuration.database);
         let cloudinary_client = configuration.cloudinary_client.client();
         let s3_client = configuration.s3_client.client().await?;
+        let webhook_secret = configuration.email_client.webhook_secret.clone();
         let email_client = configuration.email_client.client();
+        let challenger = configuration.application.challenger();
+        let captcha_ttl = configuration.application.captcha_ttl();
+        let confirmation_token_ttl = configuration.application.confirmation_token_ttl();
+        let preference_token_ttl = configuration.application.preference_token_ttl();
         let address = format!(
             "{}:{}",
             configuration.application.host, configuration.application.port
@@ -52,7 +59,11 @@ impl Application {
             host_origin_url,
             configuration.hosts.client,
             configuration.application.session_key,
-            configuration.application.captcha_secret,
+            challenger,
+            captcha_ttl,
+            confirmation_token_ttl,
+            preference_token_ttl,
+            webhook_secret,
         )
         .await?;
 
@@ -63,6 +74,12 @@ impl Application {
         self.port
     }
 
+    /// A handle used to stop the server. Signal handling is left to the caller so the API and
+    /// the delivery worker can be shut down together.
+    pub fn handle(&self) -> ServerHandle {
+        self.server.handle()
+    }
+
     pub async fn run_until_stopped(self) -> Result<(), std::io::Error> {
         self.server.await
     }
@@ -85,13 +102,20 @@ async fn run(
     host_origin_url: String,
     client_url: String,
     session_key: String,
-    captcha_secret: Secret<String>,
+    challenger: Arc<dyn Challenger>,
+    captcha_ttl: chrono::Duration,
+    confirmation_token_ttl: chrono::Duration,
+    preference_token_ttl: chrono::Duration,
+    webhook_secret: Secret<String>,
 ) -> Result<Server, anyhow::Error> {
     let base_url = Data::new(ApplicationBaseUrl(base_url));
+    let challenger = Data::from(challenger);
     let cloudinary_client = Data::new(cloudinary_client);
     let db_pool = Data::new(db_pool);
     let email_client = Data::new(email_client);
     let redis_store = RedisSessionStore::new(redis_uri.expose_secret()).await?;
+    let redeemed_challenges =
+        Data::new(RedeemedChallenges::new(redis_uri.expose_secret(), captcha_ttl).await?);
     let s3_client = Data::new(s3_client);
     let secret_key = Key::from(hmac_secret.expose_secret().as_bytes());
 
@@ -140,32 +164,65 @@ async fn run(
                     .service(admin::newsletters::detail::get)
                     .service(admin::newsletters::detail::put)
                     .service(admin::newsletters::detail::cover_image::put)
+                    .service(admin::newsletters::detail::deliveries::get)
+                    .service(admin::newsletters::detail::deliveries::failed::get)
+                    .service(admin::newsletters::detail::deliveries::failed::requeue::put)
+                    .service(admin::newsletters::detail::deliveries::failed::detail::requeue::put)
                     .service(admin::newsletters::detail::publish::put)
+                    .service(admin::newsletters::detail::schedule::put)
+                    .service(admin::newsletters::detail::schedule::delete)
+                    .service(admin::subscribers::get)
+                    .service(admin::subscribers::export::get)
+                    .service(admin::subscribers::import::post)
+                    .service(admin::subscribers::detail::get)
+                    .service(admin::subscribers::detail::delete)
+                    .service(admin::subscribers::detail::consents::get)
+                    .service(admin::subscribers::detail::consents::export)
+                    .service(admin::subscribers::detail::status::put)
+                    .service(admin::subscribers::detail::tags::put)
                     .service(admin::user::get)
                     .service(admin::user::put)
                     .service(admin::user::banner::put)
                     .service(admin::user::avatar::put)
+                    .service(admin::user::confirmation_email::get)
+                    .service(admin::user::confirmation_email::put)
+                    .service(admin::user::confirmation_email::delete)
                     .service(admin::password::put),
             )
             .service(captcha::get)
             .service(health_check::get)
+            .service(health_check::ready::get)
             .service(login::post)
             .service(newsletters::get)
             .service(newsletters::detail::get)
             .service(newsletters::by_user::get)
             .service(subscriptions::confirm::put)
+            .service(subscriptions::preferences::get)
+            .service(subscriptions::preferences::post)
+            .service(subscriptions::preferences::data::get)
+            .service(subscriptions::preferences::data::delete)
+            .service(subscriptions::preferences::detail::put)
+            .service(subscriptions::unsubscribe::get)
+            .service(subscriptions::unsubscribe::post)
             .service(subscriptions::post)
             .service(users::detail::get)
             .service(users::get)
+            .service(webhooks::postmark::post)
             .app_data(base_url.clone())
             .app_data(cloudinary_client.clone())
             .app_data(db_pool.clone())
             .app_data(email_client.clone())
+            .app_data(Data::new(redis_store.clone()))
             .app_data(s3_client.clone())
             .app_data(Data::new(HmacSecret(hmac_secret.clone())))
-            .app_data(Data::new(CaptchaSecret(captcha_secret.clone())))
+            .app_data(challenger.clone())
+            .app_data(Data::new(ConfirmationTokenTtl(confirmation_token_ttl)))
+            .app_data(Data::new(PreferenceTokenTtl(preference_token_ttl)))
+            .app_data(Data::new(WebhookSecret(webhook_secret.clone())))
+            .app_data(redeemed_challenges.clone())
             .app_data(web::JsonConfig::default().limit(1024 * 1024 * 50))
     })
+    .disable_signals()
     .listen(listener)?
     .run();
     Ok(server)
@@ -173,8 +230,14 @@ async fn run(
 
 pub struct ApplicationBaseUrl(pub String);
 
+/// How long a subscription confirmation token stays valid.
+pub struct ConfirmationTokenTtl(pub chrono::Duration);
+
+/// How long a preference centre magic link stays valid.
+pub struct PreferenceTokenTtl(pub chrono::Duration);
+
 #[derive(Clone)]
 pub struct HmacSecret(pub Secret<String>);
 
-#[derive(Clone)]
-pub struct CaptchaSecret(pub Secret<String>);
+/// Password the email provider authenticates its webhooks with.
+pub struct WebhookSecret(pub Secret<String>);
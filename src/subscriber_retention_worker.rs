@@ -0,0 +1,145 @@
+use crate::configuration::WorkerSettings;
+use crate::confirmation_email_worker::enqueue_confirmation_email;
+use crate::issue_delivery_worker::ExecutionOutcome;
+use crate::routes::subscriptions::{generate_subscription_token, store_token};
+use crate::shutdown::sleep_unless_shutdown;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{Span, field::display};
+use uuid::Uuid;
+
+/// Remind, then purge, subscribers who never confirmed their subscription.
+pub async fn retention_loop(
+    pool: PgPool,
+    settings: Arc<WorkerSettings>,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<(), anyhow::Error> {
+    while !*shutdown.borrow() {
+        let outcome = match try_remind_unconfirmed_subscribers(&pool, &settings).await {
+            Ok(ExecutionOutcome::EmptyQueue) => {
+                try_purge_unconfirmed_subscribers(&pool, &settings).await
+            }
+            outcome => outcome,
+        };
+        let wait = match outcome {
+            Ok(ExecutionOutcome::EmptyQueue) => settings.retention_interval(),
+            Err(_) => Duration::from_secs(1),
+            Ok(ExecutionOutcome::TaskCompleted) => continue,
+        };
+        if sleep_unless_shutdown(wait, &mut shutdown).await {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Send a fresh confirmation email to a batch of subscribers who have been pending for
+/// `unconfirmed_reminder_days`, through the confirmation email outbox. Each signup is reminded
+/// at most once, and only while it is still within the retention period.
+#[tracing::instrument(skip_all, fields(n_reminded=tracing::field::Empty), err)]
+pub async fn try_remind_unconfirmed_subscribers(
+    pool: &PgPool,
+    settings: &WorkerSettings,
+) -> Result<ExecutionOutcome, anyhow::Error> {
+    let Some(reminder_days) = settings.unconfirmed_reminder_days else {
+        return Ok(ExecutionOutcome::EmptyQueue);
+    };
+    let now = Utc::now();
+    let mut transaction = pool.begin().await?;
+    let subscriber_ids = sqlx::query!(
+        r#"
+        SELECT id
+        FROM subscriptions
+        WHERE status = 'pending_confirmation'
+          AND confirmation_reminder_sent_at IS NULL
+          AND COALESCE(
+            (
+              SELECT MAX(consented_at) FROM subscription_consents
+              WHERE subscription_consents.subscriber_id = subscriptions.id
+            ),
+            subscribed_at
+          ) BETWEEN $1 AND $2
+        ORDER BY subscribed_at
+        FOR UPDATE SKIP LOCKED
+        LIMIT $3
+        "#,
+        retention_cutoff(settings, now),
+        now - chrono::Duration::days(reminder_days),
+        settings.batch_size
+    )
+    .fetch_all(&mut *transaction)
+    .await?
+    .into_iter()
+    .map(|record| record.id)
+    .collect::<Vec<Uuid>>();
+    if subscriber_ids.is_empty() {
+        return Ok(ExecutionOutcome::EmptyQueue);
+    }
+    Span::current().record("n_reminded", subscriber_ids.len());
+    for subscriber_id in &subscriber_ids {
+        let subscription_token = generate_subscription_token();
+        store_token(&mut transaction, *subscriber_id, &subscription_token).await?;
+        enqueue_confirmation_email(&mut transaction, *subscriber_id, &subscription_token).await?;
+    }
+    sqlx::query!(
+        r#"
+        UPDATE subscriptions
+        SET confirmation_reminder_sent_at = now()
+        WHERE id = ANY($1)
+        "#,
+        &subscriber_ids
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(ExecutionOutcome::TaskCompleted)
+}
+
+/// Delete a batch of subscribers who are still pending confirmation
+/// `unconfirmed_retention_days` after signing up, along with their tokens, tags, consent
+/// records and queued confirmation emails.
+#[tracing::instrument(skip_all, fields(n_purged=tracing::field::Empty), err)]
+pub async fn try_purge_unconfirmed_subscribers(
+    pool: &PgPool,
+    settings: &WorkerSettings,
+) -> Result<ExecutionOutcome, anyhow::Error> {
+    let purged = sqlx::query!(
+        r#"
+        DELETE FROM subscriptions
+        WHERE id IN (
+          SELECT id
+          FROM subscriptions
+          WHERE status = 'pending_confirmation'
+            AND COALESCE(
+              (
+                SELECT MAX(consented_at) FROM subscription_consents
+                WHERE subscription_consents.subscriber_id = subscriptions.id
+              ),
+              subscribed_at
+            ) < $1
+          FOR UPDATE SKIP LOCKED
+          LIMIT $2
+        )
+        "#,
+        retention_cutoff(settings, Utc::now()),
+        settings.batch_size
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+    if purged == 0 {
+        return Ok(ExecutionOutcome::EmptyQueue);
+    }
+    Span::current().record("n_purged", display(purged));
+    tracing::info!("Purged {} unconfirmed subscribers", purged);
+    Ok(ExecutionOutcome::TaskCompleted)
+}
+
+/// Signups older than this are past the retention period. A reader who subscribes again
+/// starts a new period, so the latest consent record counts rather than `subscribed_at`.
+fn retention_cutoff(settings: &WorkerSettings, now: DateTime<Utc>) -> DateTime<Utc> {
+    now - chrono::Duration::days(settings.unconfirmed_retention_days)
+}
@@ -30,6 +30,15 @@ where
     ServerError::NotFoundError(e).into()
 }
 
+// Return a 409 with the user-representation of the conflict as body.
+// The error root cause is preserved for logging purposes.
+pub fn e409<T>(e: T) -> actix_web::Error
+where
+    T: std::fmt::Debug + std::fmt::Display + 'static,
+{
+    ServerError::ConflictError(e).into()
+}
+
 pub fn see_other(location: &str) -> HttpResponse {
     HttpResponse::SeeOther()
         .insert_header((LOCATION, location))
@@ -44,6 +53,8 @@ pub enum ServerError<T: std::fmt::Debug + std::fmt::Display + 'static> {
     BadRequestError(T),
     #[error("{0}")]
     NotFoundError(T),
+    #[error("{0}")]
+    ConflictError(T),
 }
 
 impl<T: std::fmt::Debug + std::fmt::Display + 'static> std::fmt::Debug for ServerError<T> {
@@ -58,6 +69,7 @@ impl<T: std::fmt::Debug + std::fmt::Display + 'static> ResponseError for ServerE
             ServerError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             ServerError::BadRequestError(_) => StatusCode::BAD_REQUEST,
             ServerError::NotFoundError(_) => StatusCode::NOT_FOUND,
+            ServerError::ConflictError(_) => StatusCode::CONFLICT,
         }
     }
 
@@ -1,2 +1,3 @@
 mod newsletters;
+mod subscribers;
 mod user;
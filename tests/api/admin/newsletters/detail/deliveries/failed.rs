@@ -0,0 +1,148 @@
+use crate::helpers::{TestUser, spawn_app};
+use newsletter_api::models::FailedIssueDelivery;
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+#[tokio::test]
+async fn failed_deliveries_are_listed_once_attempts_are_exhausted() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+
+    app.fail_all_pending_deliveries().await;
+
+    let response = app.get_admin_failed_deliveries(&newsletter_issue_id).await;
+    assert_eq!(200, response.status().as_u16());
+    let failed_deliveries: Vec<FailedIssueDelivery> = response.json().await.unwrap();
+    assert_eq!(failed_deliveries.len(), 1);
+    assert_eq!(
+        failed_deliveries[0].newsletter_issue_id,
+        newsletter_issue_id
+    );
+    assert_eq!(
+        failed_deliveries[0].n_attempts,
+        app.worker_settings.max_delivery_attempts
+    );
+}
+
+#[tokio::test]
+async fn requeued_failed_deliveries_are_delivered() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+    app.fail_all_pending_deliveries().await;
+
+    let response = app
+        .put_admin_requeue_failed_deliveries(&newsletter_issue_id)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app.get_admin_failed_deliveries(&newsletter_issue_id).await;
+    let failed_deliveries: Vec<FailedIssueDelivery> = response.json().await.unwrap();
+    assert!(failed_deliveries.is_empty());
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    app.dispatch_all_pending_emails().await;
+}
+
+#[tokio::test]
+async fn a_single_failed_delivery_can_be_requeued() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+    app.fail_all_pending_deliveries().await;
+
+    let response = app.get_admin_failed_deliveries(&newsletter_issue_id).await;
+    let failed_deliveries: Vec<FailedIssueDelivery> = response.json().await.unwrap();
+    assert_eq!(failed_deliveries.len(), 2);
+
+    let response = app
+        .put_admin_requeue_failed_delivery(
+            &newsletter_issue_id,
+            &failed_deliveries[0].failed_delivery_id,
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app.get_admin_failed_deliveries(&newsletter_issue_id).await;
+    let remaining: Vec<FailedIssueDelivery> = response.json().await.unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(
+        remaining[0].failed_delivery_id,
+        failed_deliveries[1].failed_delivery_id
+    );
+
+    let queued = sqlx::query!("SELECT subscriber_email, n_attempts FROM issue_delivery_queue")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("The requeued delivery should be queued.");
+    assert_eq!(
+        queued.subscriber_email,
+        failed_deliveries[0].subscriber_email
+    );
+    assert_eq!(queued.n_attempts, 0);
+}
+
+#[tokio::test]
+async fn requeueing_an_unknown_failed_delivery_returns_404() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+
+    let response = app
+        .put_admin_requeue_failed_delivery(&newsletter_issue_id, &Uuid::new_v4())
+        .await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn failed_deliveries_of_another_users_issue_are_not_accessible() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+    app.fail_all_pending_deliveries().await;
+    app.post_logout().await;
+
+    let second_user = TestUser::create(&app.db_pool).await.unwrap();
+    second_user.login(&app).await;
+
+    let response = app.get_admin_failed_deliveries(&newsletter_issue_id).await;
+    assert_eq!(404, response.status().as_u16());
+
+    let response = app
+        .put_admin_requeue_failed_deliveries(&newsletter_issue_id)
+        .await;
+    assert_eq!(404, response.status().as_u16());
+
+    let remaining = sqlx::query!("SELECT COUNT(*) AS \"count!\" FROM failed_issue_deliveries")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining.count, 1);
+}
+
+#[tokio::test]
+async fn failed_deliveries_endpoints_reject_anonymous_users() {
+    let app = spawn_app().await;
+    let newsletter_issue_id = Uuid::new_v4();
+
+    let response = app.get_admin_failed_deliveries(&newsletter_issue_id).await;
+    assert_eq!(401, response.status().as_u16());
+
+    let response = app
+        .put_admin_requeue_failed_deliveries(&newsletter_issue_id)
+        .await;
+    assert_eq!(401, response.status().as_u16());
+}
@@ -0,0 +1,155 @@
+use crate::helpers::{TestUser, spawn_app};
+use newsletter_api::models::{DELIVERY_LOG_PAGE_SIZE, DeliveryReport};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+#[tokio::test]
+async fn delivery_report_counts_pending_deliveries_before_dispatch() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+
+    let response = app
+        .get_admin_delivery_report(&newsletter_issue_id, None)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let report: DeliveryReport = response.json().await.unwrap();
+    assert_eq!(report.pending, 2);
+    assert_eq!(report.sent, 0);
+    assert_eq!(report.failed, 0);
+    assert!(report.log.is_empty());
+}
+
+#[tokio::test]
+async fn delivery_report_logs_each_recipient_outcome_and_provider_response() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"ID":"message-id"}"#))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    app.dispatch_all_pending_emails().await;
+
+    let response = app
+        .get_admin_delivery_report(&newsletter_issue_id, None)
+        .await;
+    let report: DeliveryReport = response.json().await.unwrap();
+    assert_eq!(report.pending, 0);
+    assert_eq!(report.sent, 1);
+    assert_eq!(report.failed, 0);
+    assert_eq!(report.total_log_entries, 1);
+    assert_eq!(report.log[0].outcome, "sent");
+    assert_eq!(
+        report.log[0].provider_response.as_deref(),
+        Some(r#"{"ID":"message-id"}"#)
+    );
+}
+
+#[tokio::test]
+async fn delivery_report_logs_retries_and_failures() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+    let max_attempts = app.worker_settings.max_delivery_attempts;
+
+    app.fail_all_pending_deliveries().await;
+
+    let response = app
+        .get_admin_delivery_report(&newsletter_issue_id, None)
+        .await;
+    let report: DeliveryReport = response.json().await.unwrap();
+    assert_eq!(report.pending, 0);
+    assert_eq!(report.sent, 0);
+    assert_eq!(report.failed, 1);
+    assert_eq!(report.total_log_entries, max_attempts as i64);
+    assert_eq!(report.log[0].outcome, "failed");
+    assert!(
+        report.log[1..]
+            .iter()
+            .all(|entry| entry.outcome == "retrying")
+    );
+}
+
+#[tokio::test]
+async fn delivery_report_log_is_paginated() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+    let total_entries = DELIVERY_LOG_PAGE_SIZE + 1;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO delivery_log (
+            delivery_log_id,
+            newsletter_issue_id,
+            subscriber_email,
+            outcome,
+            logged_at
+        )
+        SELECT gen_random_uuid(), $1, n || '@example.com', 'sent', now()
+        FROM generate_series(1, $2) AS n
+        "#,
+        newsletter_issue_id,
+        total_entries as i32
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    let response = app
+        .get_admin_delivery_report(&newsletter_issue_id, Some(1))
+        .await;
+    let first_page: DeliveryReport = response.json().await.unwrap();
+    assert_eq!(first_page.log.len() as i64, DELIVERY_LOG_PAGE_SIZE);
+    assert_eq!(first_page.total_log_entries, total_entries);
+    assert_eq!(first_page.sent, total_entries);
+
+    let response = app
+        .get_admin_delivery_report(&newsletter_issue_id, Some(2))
+        .await;
+    let second_page: DeliveryReport = response.json().await.unwrap();
+    assert_eq!(second_page.page, 2);
+    assert_eq!(second_page.log.len(), 1);
+}
+
+#[tokio::test]
+async fn delivery_report_rejects_invalid_pages() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+
+    let response = app
+        .get_admin_delivery_report(&newsletter_issue_id, Some(0))
+        .await;
+    assert_eq!(400, response.status().as_u16());
+
+    let response = app
+        .get_admin_delivery_report(&newsletter_issue_id, Some(i64::MAX))
+        .await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn delivery_report_of_another_users_issue_returns_404() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+    app.post_logout().await;
+
+    let second_user = TestUser::create(&app.db_pool).await.unwrap();
+    second_user.login(&app).await;
+
+    let response = app
+        .get_admin_delivery_report(&newsletter_issue_id, None)
+        .await;
+    assert_eq!(404, response.status().as_u16());
+}
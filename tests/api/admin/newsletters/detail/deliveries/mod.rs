@@ -0,0 +1,2 @@
+mod failed;
+mod index;
@@ -1,3 +1,5 @@
 mod cover_image;
+mod deliveries;
 mod index;
 mod publish;
+mod schedule;
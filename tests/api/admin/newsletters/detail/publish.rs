@@ -1,4 +1,4 @@
-use crate::helpers::spawn_app;
+use crate::helpers::{TestApp, spawn_app};
 use fake::Fake;
 use fake::faker::internet::en::SafeEmail;
 use newsletter_api::models::{NewUser, NewUserData, NewsletterIssueAPI};
@@ -376,3 +376,124 @@ async fn publish_newsletters_returns_422_for_empty_content() {
     let response_body: ResponseErrorMessage = response.json().await.unwrap();
     assert_eq!("Content body is required.".to_string(), response_body.error);
 }
+
+/// Create confirmed subscribers of the test user with the given tags, then publish a new
+/// issue to `segment`.
+async fn publish_to_segment(
+    app: &TestApp,
+    subscribers: &[(&str, &[&str])],
+    segment: serde_json::Value,
+) -> reqwest::Response {
+    for (email, tags) in subscribers {
+        app.create_confirmed_subscriber(None, Some(email.to_string()))
+            .await;
+        let subscriber_id = app.subscriber_id(email).await;
+        app.put_admin_subscriber_tags(&subscriber_id, &serde_json::json!({ "tags": tags }))
+            .await
+            .error_for_status()
+            .unwrap();
+    }
+    app.post_admin_create_newsletter(&serde_json::json!({
+      "title": "Newsletter title",
+      "description": "Newsletter description",
+      "content": "## Newsletter body as markdown",
+      "cover_image": "",
+    }))
+    .await;
+    let response = app.get_admin_unpublished_newsletter_issues().await;
+    let response_body: Vec<NewsletterIssueAPI> = response.json().await.unwrap();
+    let newsletter_issue_id = response_body[0].newsletter_issue_id;
+
+    app.put_admin_publish_newsletter(
+        &newsletter_issue_id,
+        &serde_json::json!({
+          "idempotency_key": uuid::Uuid::new_v4().to_string(),
+          "segment": segment,
+        }),
+    )
+    .await
+}
+
+/// Send every queued delivery and return the recipients.
+async fn delivered_to(app: &TestApp) -> Vec<String> {
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+    app.dispatch_all_pending_emails().await;
+    let mut recipients: Vec<String> = app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            body["To"][0]["Email"].as_str().unwrap().to_owned()
+        })
+        .collect();
+    recipients.sort();
+    recipients
+}
+
+#[tokio::test]
+async fn newsletters_are_delivered_to_subscribers_with_every_tag_of_the_segment() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    let response = publish_to_segment(
+        &app,
+        &[
+            ("both@example.com", &["rust", "go"]),
+            ("rust@example.com", &["rust"]),
+            ("none@example.com", &[]),
+        ],
+        serde_json::json!({ "tags": ["rust", "go"], "operator": "and" }),
+    )
+    .await;
+    assert_eq!(200, response.status().as_u16());
+    // Only the newsletter's deliveries are left to send.
+    app.email_server.reset().await;
+
+    assert_eq!(delivered_to(&app).await, vec!["both@example.com"]);
+}
+
+#[tokio::test]
+async fn newsletters_are_delivered_to_subscribers_with_any_tag_of_the_segment() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    let response = publish_to_segment(
+        &app,
+        &[
+            ("go@example.com", &["go"]),
+            ("rust@example.com", &["rust"]),
+            ("none@example.com", &[]),
+        ],
+        serde_json::json!({ "tags": ["Rust", "go"], "operator": "or" }),
+    )
+    .await;
+    assert_eq!(200, response.status().as_u16());
+    app.email_server.reset().await;
+
+    assert_eq!(
+        delivered_to(&app).await,
+        vec!["go@example.com", "rust@example.com"]
+    );
+}
+
+#[tokio::test]
+async fn publish_newsletters_returns_400_for_an_empty_segment() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    let response = publish_to_segment(&app, &[], serde_json::json!({ "tags": [] })).await;
+
+    assert_eq!(400, response.status().as_u16());
+    let published = sqlx::query!("SELECT published_at FROM newsletter_issues")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert!(published.published_at.is_none());
+}
@@ -0,0 +1,323 @@
+use crate::helpers::{TestUser, spawn_app};
+use chrono::{Duration, FixedOffset, Utc};
+use newsletter_api::models::{NewsletterIssue, NewsletterIssueAPI};
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+#[tokio::test]
+async fn issues_can_be_scheduled_in_the_writers_timezone() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_draft_newsletter_issue().await;
+    let offset = FixedOffset::east_opt(2 * 3600).unwrap();
+    let scheduled_for = (Utc::now() + Duration::days(1)).with_timezone(&offset);
+
+    let response = app
+        .put_admin_schedule_newsletter(
+            &newsletter_issue_id,
+            &serde_json::json!({ "scheduled_for": scheduled_for.to_rfc3339() }),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response_body: NewsletterIssueAPI = response.json().await.unwrap();
+    assert_eq!(
+        response_body.scheduled_for.unwrap().timestamp(),
+        scheduled_for.timestamp()
+    );
+    assert!(response_body.published_at.is_none());
+}
+
+#[tokio::test]
+async fn scheduling_an_issue_in_the_past_returns_400() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_draft_newsletter_issue().await;
+
+    let response = app
+        .put_admin_schedule_newsletter(
+            &newsletter_issue_id,
+            &serde_json::json!({ "scheduled_for": (Utc::now() - Duration::hours(1)).to_rfc3339() }),
+        )
+        .await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn scheduling_a_published_issue_returns_400() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+
+    let response = app
+        .put_admin_schedule_newsletter(
+            &newsletter_issue_id,
+            &serde_json::json!({ "scheduled_for": (Utc::now() + Duration::days(1)).to_rfc3339() }),
+        )
+        .await;
+    assert_eq!(400, response.status().as_u16());
+
+    let response = app
+        .delete_admin_schedule_newsletter(&newsletter_issue_id)
+        .await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn scheduling_an_issue_published_in_the_meantime_is_not_saved() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_draft_newsletter_issue().await;
+    let newsletter_issue = NewsletterIssue::find_by_user_id_and_newsletter_issue_id(
+        app.test_user.user_id,
+        &newsletter_issue_id,
+        &app.db_pool,
+    )
+    .await
+    .unwrap();
+    sqlx::query!(
+        "UPDATE newsletter_issues SET published_at = now() WHERE newsletter_issue_id = $1",
+        newsletter_issue_id
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    let scheduled = newsletter_issue
+        .schedule(Some(Utc::now() + Duration::days(1)), None, &app.db_pool)
+        .await
+        .unwrap();
+
+    assert!(scheduled.is_none());
+    let saved = sqlx::query!(
+        "SELECT scheduled_for FROM newsletter_issues WHERE newsletter_issue_id = $1",
+        newsletter_issue_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert!(saved.scheduled_for.is_none());
+}
+
+#[tokio::test]
+async fn scheduling_an_incomplete_draft_returns_400() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.post_admin_create_newsletter(&serde_json::json!({
+      "title": "Newsletter title",
+      "description": "",
+      "content": "## Newsletter body as markdown",
+      "cover_image": "",
+    }))
+    .await;
+    let response = app.get_admin_unpublished_newsletter_issues().await;
+    let response_body: Vec<NewsletterIssueAPI> = response.json().await.unwrap();
+    let newsletter_issue_id = response_body[0].newsletter_issue_id;
+
+    let response = app
+        .put_admin_schedule_newsletter(
+            &newsletter_issue_id,
+            &serde_json::json!({ "scheduled_for": (Utc::now() + Duration::days(1)).to_rfc3339() }),
+        )
+        .await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn scheduling_another_users_issue_returns_404() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_draft_newsletter_issue().await;
+    app.post_logout().await;
+
+    let second_user = TestUser::create(&app.db_pool).await.unwrap();
+    second_user.login(&app).await;
+
+    let response = app
+        .put_admin_schedule_newsletter(
+            &newsletter_issue_id,
+            &serde_json::json!({ "scheduled_for": (Utc::now() + Duration::days(1)).to_rfc3339() }),
+        )
+        .await;
+    assert_eq!(404, response.status().as_u16());
+
+    let response = app.delete_admin_schedule_newsletter(&Uuid::new_v4()).await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn scheduled_issues_are_published_and_delivered_once_due() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_draft_newsletter_issue().await;
+    app.put_admin_schedule_newsletter(
+        &newsletter_issue_id,
+        &serde_json::json!({ "scheduled_for": (Utc::now() + Duration::days(1)).to_rfc3339() }),
+    )
+    .await
+    .error_for_status()
+    .unwrap();
+
+    // Not due yet
+    app.publish_all_due_issues().await;
+    let response = app.get_admin_newsletter_issue(&newsletter_issue_id).await;
+    let newsletter_issue: NewsletterIssueAPI = response.json().await.unwrap();
+    assert!(newsletter_issue.published_at.is_none());
+
+    sqlx::query!(
+        "UPDATE newsletter_issues SET scheduled_for = now() WHERE newsletter_issue_id = $1",
+        newsletter_issue_id
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    app.publish_all_due_issues().await;
+    app.dispatch_all_pending_emails().await;
+
+    let response = app.get_admin_newsletter_issue(&newsletter_issue_id).await;
+    let newsletter_issue: NewsletterIssueAPI = response.json().await.unwrap();
+    assert!(newsletter_issue.published_at.is_some());
+    assert!(newsletter_issue.scheduled_for.is_none());
+}
+
+#[tokio::test]
+async fn scheduled_issues_are_delivered_to_their_segment_once_due() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    for (email, tags) in [
+        ("rust@example.com", &["rust"][..]),
+        ("none@example.com", &[]),
+    ] {
+        app.create_confirmed_subscriber(None, Some(email.to_string()))
+            .await;
+        let subscriber_id = app.subscriber_id(email).await;
+        app.put_admin_subscriber_tags(&subscriber_id, &serde_json::json!({ "tags": tags }))
+            .await
+            .error_for_status()
+            .unwrap();
+    }
+    let newsletter_issue_id = app.create_draft_newsletter_issue().await;
+    app.put_admin_schedule_newsletter(
+        &newsletter_issue_id,
+        &serde_json::json!({
+          "scheduled_for": (Utc::now() + Duration::days(1)).to_rfc3339(),
+          "segment": { "tags": ["rust"] },
+        }),
+    )
+    .await
+    .error_for_status()
+    .unwrap();
+    sqlx::query!(
+        "UPDATE newsletter_issues SET scheduled_for = now() WHERE newsletter_issue_id = $1",
+        newsletter_issue_id
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    app.publish_all_due_issues().await;
+
+    let queued = sqlx::query!(
+        "SELECT subscriber_email FROM issue_delivery_queue WHERE newsletter_issue_id = $1",
+        newsletter_issue_id
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0].subscriber_email, "rust@example.com");
+}
+
+#[tokio::test]
+async fn scheduling_an_issue_with_an_empty_segment_returns_400() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_draft_newsletter_issue().await;
+
+    let response = app
+        .put_admin_schedule_newsletter(
+            &newsletter_issue_id,
+            &serde_json::json!({
+              "scheduled_for": (Utc::now() + Duration::days(1)).to_rfc3339(),
+              "segment": { "tags": [] },
+            }),
+        )
+        .await;
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn cancelled_schedules_are_not_published() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_draft_newsletter_issue().await;
+    app.put_admin_schedule_newsletter(
+        &newsletter_issue_id,
+        &serde_json::json!({ "scheduled_for": (Utc::now() + Duration::days(1)).to_rfc3339() }),
+    )
+    .await
+    .error_for_status()
+    .unwrap();
+
+    let response = app
+        .delete_admin_schedule_newsletter(&newsletter_issue_id)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let newsletter_issue: NewsletterIssueAPI = response.json().await.unwrap();
+    assert!(newsletter_issue.scheduled_for.is_none());
+
+    app.publish_all_due_issues().await;
+    let response = app.get_admin_newsletter_issue(&newsletter_issue_id).await;
+    let newsletter_issue: NewsletterIssueAPI = response.json().await.unwrap();
+    assert!(newsletter_issue.published_at.is_none());
+}
+
+#[tokio::test]
+async fn scheduled_issues_edited_into_an_invalid_state_are_unscheduled() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_draft_newsletter_issue().await;
+    app.put_admin_schedule_newsletter(
+        &newsletter_issue_id,
+        &serde_json::json!({ "scheduled_for": (Utc::now() + Duration::days(1)).to_rfc3339() }),
+    )
+    .await
+    .error_for_status()
+    .unwrap();
+    app.put_admin_update_newsletter(
+        &newsletter_issue_id,
+        &serde_json::json!({
+          "title": "Newsletter title",
+          "description": "",
+          "content": "## Newsletter body as markdown",
+        }),
+    )
+    .await
+    .error_for_status()
+    .unwrap();
+    sqlx::query!(
+        "UPDATE newsletter_issues SET scheduled_for = now() WHERE newsletter_issue_id = $1",
+        newsletter_issue_id
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    app.publish_all_due_issues().await;
+
+    let response = app.get_admin_newsletter_issue(&newsletter_issue_id).await;
+    let newsletter_issue: NewsletterIssueAPI = response.json().await.unwrap();
+    assert!(newsletter_issue.published_at.is_none());
+    assert!(newsletter_issue.scheduled_for.is_none());
+}
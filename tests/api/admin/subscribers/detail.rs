@@ -0,0 +1,325 @@
+use crate::helpers::{TestUser, spawn_app};
+use newsletter_api::models::{Subscriber, SubscriptionConsent};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn a_subscriber_can_be_retrieved() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    app.test_user.login(&app).await;
+
+    let response = app.get_admin_subscriber(&subscriber_id).await;
+
+    assert_eq!(200, response.status().as_u16());
+    let subscriber: Subscriber = response.json().await.unwrap();
+    assert_eq!(subscriber.id, subscriber_id);
+    assert_eq!(subscriber.email, "ursula@example.com");
+}
+
+#[tokio::test]
+async fn a_subscriber_can_be_removed() {
+    let app = spawn_app().await;
+    app.create_unconfirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    app.test_user.login(&app).await;
+
+    let response = app.delete_admin_subscriber(&subscriber_id).await;
+
+    assert_eq!(200, response.status().as_u16());
+    let response = app.get_admin_subscriber(&subscriber_id).await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_subscribers_status_can_be_changed() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    app.test_user.login(&app).await;
+
+    let response = app
+        .put_admin_subscriber_status(
+            &subscriber_id,
+            &serde_json::json!({"status": "unsubscribed"}),
+        )
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let response = app.get_admin_subscriber(&subscriber_id).await;
+    let subscriber: Subscriber = response.json().await.unwrap();
+    assert_eq!(subscriber.status, "unsubscribed");
+}
+
+#[tokio::test]
+async fn an_unknown_status_is_rejected_with_a_400() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    app.test_user.login(&app).await;
+
+    let response = app
+        .put_admin_subscriber_status(&subscriber_id, &serde_json::json!({"status": "banned"}))
+        .await;
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn an_unconfirmed_subscriber_cannot_be_confirmed_by_the_writer() {
+    let app = spawn_app().await;
+    app.create_unconfirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    app.test_user.login(&app).await;
+
+    for status in ["confirmed", "paused"] {
+        let response = app
+            .put_admin_subscriber_status(&subscriber_id, &serde_json::json!({"status": status}))
+            .await;
+        assert_eq!(400, response.status().as_u16());
+    }
+
+    let response = app.get_admin_subscriber(&subscriber_id).await;
+    let subscriber: Subscriber = response.json().await.unwrap();
+    assert_eq!(subscriber.status, "pending_confirmation");
+}
+
+#[tokio::test]
+async fn a_subscriber_cannot_be_reset_to_pending_confirmation_by_the_writer() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    let retention_days = app.worker_settings.unconfirmed_retention_days as i32;
+    app.backdate_signup("ursula@example.com", retention_days + 1)
+        .await;
+    app.test_user.login(&app).await;
+
+    let response = app
+        .put_admin_subscriber_status(
+            &subscriber_id,
+            &serde_json::json!({"status": "pending_confirmation"}),
+        )
+        .await;
+    app.enforce_retention_policy(&app.worker_settings).await;
+
+    assert_eq!(400, response.status().as_u16());
+    let response = app.get_admin_subscriber(&subscriber_id).await;
+    let subscriber: Subscriber = response.json().await.unwrap();
+    assert_eq!(subscriber.status, "confirmed");
+}
+
+#[tokio::test]
+async fn a_suppressed_subscriber_cannot_be_reactivated_by_the_writer() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    app.test_user.login(&app).await;
+
+    for suppressed in ["bounced", "complained"] {
+        sqlx::query!(
+            "UPDATE subscriptions SET status = $1 WHERE id = $2",
+            suppressed,
+            subscriber_id
+        )
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+        for status in [
+            "pending_confirmation",
+            "confirmed",
+            "paused",
+            "unsubscribed",
+        ] {
+            let response = app
+                .put_admin_subscriber_status(&subscriber_id, &serde_json::json!({"status": status}))
+                .await;
+            assert_eq!(400, response.status().as_u16());
+        }
+
+        let response = app.get_admin_subscriber(&subscriber_id).await;
+        let subscriber: Subscriber = response.json().await.unwrap();
+        assert_eq!(subscriber.status, suppressed);
+    }
+}
+
+#[tokio::test]
+async fn a_subscriber_cannot_be_marked_as_bounced_by_the_writer() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    app.test_user.login(&app).await;
+
+    let response = app
+        .put_admin_subscriber_status(&subscriber_id, &serde_json::json!({"status": "bounced"}))
+        .await;
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn another_users_subscriber_is_not_accessible() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    let second_user = TestUser::create(&app.db_pool).await.unwrap();
+    second_user.login(&app).await;
+
+    let response = app.get_admin_subscriber(&subscriber_id).await;
+    assert_eq!(404, response.status().as_u16());
+
+    let response = app
+        .put_admin_subscriber_status(
+            &subscriber_id,
+            &serde_json::json!({"status": "unsubscribed"}),
+        )
+        .await;
+    assert_eq!(404, response.status().as_u16());
+
+    let response = app.delete_admin_subscriber(&subscriber_id).await;
+    assert_eq!(404, response.status().as_u16());
+
+    let response = app.get_admin_subscriber_consents(&subscriber_id).await;
+    assert_eq!(404, response.status().as_u16());
+
+    let response = app
+        .get_admin_subscriber_consents_export(&subscriber_id)
+        .await;
+    assert_eq!(404, response.status().as_u16());
+
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.status, "confirmed");
+}
+
+#[tokio::test]
+async fn subscriber_endpoints_reject_anonymous_users() {
+    let app = spawn_app().await;
+    let subscriber_id = Uuid::new_v4();
+
+    let response = app.get_admin_subscriber(&subscriber_id).await;
+    assert_eq!(401, response.status().as_u16());
+
+    let response = app.delete_admin_subscriber(&subscriber_id).await;
+    assert_eq!(401, response.status().as_u16());
+
+    let response = app.get_admin_subscriber_consents(&subscriber_id).await;
+    assert_eq!(401, response.status().as_u16());
+
+    let response = app
+        .put_admin_subscriber_status(
+            &subscriber_id,
+            &serde_json::json!({"status": "unsubscribed"}),
+        )
+        .await;
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_subscribers_tags_can_be_replaced() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    app.test_user.login(&app).await;
+
+    app.put_admin_subscriber_tags(
+        &subscriber_id,
+        &serde_json::json!({"tags": ["go", "sci-fi"]}),
+    )
+    .await;
+    let response = app
+        .put_admin_subscriber_tags(
+            &subscriber_id,
+            &serde_json::json!({"tags": ["Rust", "sci-fi", "rust"]}),
+        )
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let response = app.get_admin_subscriber(&subscriber_id).await;
+    let subscriber: Subscriber = response.json().await.unwrap();
+    assert_eq!(subscriber.tags, vec!["rust", "sci-fi"]);
+}
+
+#[tokio::test]
+async fn invalid_tags_are_rejected_with_a_400() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    app.test_user.login(&app).await;
+
+    let response = app
+        .put_admin_subscriber_tags(&subscriber_id, &serde_json::json!({"tags": ["<script>"]}))
+        .await;
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn another_users_subscriber_cannot_be_tagged() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    let second_user = TestUser::create(&app.db_pool).await.unwrap();
+    second_user.login(&app).await;
+
+    let response = app
+        .put_admin_subscriber_tags(&subscriber_id, &serde_json::json!({"tags": ["rust"]}))
+        .await;
+
+    assert_eq!(404, response.status().as_u16());
+    let tags = sqlx::query!("SELECT tag FROM subscriber_tags")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap();
+    assert!(tags.is_empty());
+}
+
+#[tokio::test]
+async fn a_subscribers_consent_records_can_be_viewed_and_exported() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    app.test_user.login(&app).await;
+
+    let response = app.get_admin_subscriber_consents(&subscriber_id).await;
+
+    assert_eq!(200, response.status().as_u16());
+    let consents: Vec<SubscriptionConsent> = response.json().await.unwrap();
+    assert_eq!(consents.len(), 1);
+    assert_eq!(consents[0].source, "form");
+    assert_eq!(consents[0].ip_address.as_deref(), Some("127.0.0.1"));
+    assert!(consents[0].confirmed_at.is_some());
+
+    let response = app
+        .get_admin_subscriber_consents_export(&subscriber_id)
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(
+        response.headers()["Content-Disposition"],
+        "attachment; filename=\"consents.csv\""
+    );
+    let csv = response.text().await.unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "email,source,consent_text,consented_at,confirmed_at,ip_address,user_agent"
+    );
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].starts_with("ursula@example.com,form,"));
+}
@@ -0,0 +1,70 @@
+use crate::helpers::{TestUser, spawn_app};
+
+#[tokio::test]
+async fn subscribers_are_exported_as_csv() {
+    let app = spawn_app().await;
+    let second_user = TestUser::create(&app.db_pool).await.unwrap();
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.create_unconfirmed_subscriber(None, Some("octavia@example.com".into()))
+        .await;
+    app.create_confirmed_subscriber(Some(second_user.user_id), Some("samuel@example.com".into()))
+        .await;
+    app.test_user.login(&app).await;
+
+    let response = app.get_admin_subscribers_export().await;
+
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(
+        response.headers()["Content-Disposition"],
+        "attachment; filename=\"subscribers.csv\""
+    );
+    let body = response.text().await.unwrap();
+    let mut lines = body.lines();
+    assert_eq!(lines.next(), Some("email,name,status,subscribed_at"));
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0][0], "ursula@example.com");
+    assert_eq!(rows[0][2], "confirmed");
+    assert_eq!(rows[1][0], "octavia@example.com");
+    assert_eq!(rows[1][2], "pending_confirmation");
+}
+
+#[tokio::test]
+async fn an_export_can_be_imported_again() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.test_user.login(&app).await;
+    let csv = app
+        .get_admin_subscribers_export()
+        .await
+        .text()
+        .await
+        .unwrap();
+    sqlx::query!("DELETE FROM subscriptions")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    let response = app
+        .post_admin_subscribers_import(&serde_json::json!({ "csv": csv }))
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let saved = sqlx::query!("SELECT email, status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.email, "ursula@example.com");
+    assert_eq!(saved.status, "confirmed");
+}
+
+#[tokio::test]
+async fn export_rejects_anonymous_users() {
+    let app = spawn_app().await;
+
+    let response = app.get_admin_subscribers_export().await;
+
+    assert_eq!(401, response.status().as_u16());
+}
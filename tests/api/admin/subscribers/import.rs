@@ -0,0 +1,166 @@
+use crate::helpers::spawn_app;
+use newsletter_api::routes::admin::subscribers::import::ImportReport;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+#[tokio::test]
+async fn valid_rows_are_imported_and_invalid_rows_are_reported() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let csv = "email,name,status\n\
+        ursula@example.com,Ursula,\n\
+        not-an-email,Octavia,\n\
+        samuel@example.com,Samuel,unsubscribed\n\
+        ted@example.com,,\n\
+        kim@example.com,Kim,banned\n";
+
+    let response = app
+        .post_admin_subscribers_import(&serde_json::json!({ "csv": csv }))
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let report: ImportReport = response.json().await.unwrap();
+    assert_eq!(report.imported, 2);
+    let failed_lines: Vec<u64> = report.errors.iter().map(|e| e.line).collect();
+    assert_eq!(failed_lines, vec![3, 5, 6]);
+
+    let saved = sqlx::query!(
+        "SELECT email, status FROM subscriptions WHERE user_id = $1 ORDER BY email",
+        app.test_user.user_id
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(saved.len(), 2);
+    assert_eq!(saved[0].email, "samuel@example.com");
+    assert_eq!(saved[0].status, "unsubscribed");
+    assert_eq!(saved[1].email, "ursula@example.com");
+    assert_eq!(saved[1].status, "confirmed");
+}
+
+#[tokio::test]
+async fn imported_rows_can_be_asked_to_confirm_their_subscription() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&app.email_server)
+        .await;
+    let csv = "email,name,status\n\
+        ursula@example.com,Ursula,confirmed\n\
+        octavia@example.com,Octavia,\n\
+        samuel@example.com,Samuel,unsubscribed\n";
+
+    let response = app
+        .post_admin_subscribers_import(
+            &serde_json::json!({ "csv": csv, "send_confirmation_emails": true }),
+        )
+        .await;
+    app.dispatch_all_pending_confirmation_emails().await;
+
+    assert_eq!(200, response.status().as_u16());
+    let saved = sqlx::query!(
+        "SELECT email, status FROM subscriptions WHERE user_id = $1 ORDER BY email",
+        app.test_user.user_id
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .unwrap();
+    let statuses: Vec<&str> = saved.iter().map(|s| s.status.as_str()).collect();
+    assert_eq!(
+        statuses,
+        vec![
+            "pending_confirmation",
+            "unsubscribed",
+            "pending_confirmation"
+        ]
+    );
+
+    // The confirmation links work like those sent to new subscribers.
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let confirmation_links = app.get_confirmation_links(email_request);
+    let response = app
+        .api_client
+        .put(confirmation_links.html)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn existing_subscribers_are_left_untouched() {
+    let app = spawn_app().await;
+    app.create_unconfirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.test_user.login(&app).await;
+    let csv = "email,name\nursula@example.com,Ursula\n";
+
+    let response = app
+        .post_admin_subscribers_import(&serde_json::json!({ "csv": csv }))
+        .await;
+
+    let report: ImportReport = response.json().await.unwrap();
+    assert_eq!(report.imported, 0);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].line, 2);
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.status, "pending_confirmation");
+}
+
+#[tokio::test]
+async fn a_csv_without_email_and_name_columns_is_rejected_with_a_400() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    let response = app
+        .post_admin_subscribers_import(
+            &serde_json::json!({ "csv": "address,full_name\nursula@example.com,Ursula\n" }),
+        )
+        .await;
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn import_rejects_anonymous_users() {
+    let app = spawn_app().await;
+
+    let response = app
+        .post_admin_subscribers_import(&serde_json::json!({ "csv": "email,name\n" }))
+        .await;
+
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn imported_subscribers_record_the_import_as_their_consent() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let csv = "email,name\nursula@example.com,Ursula\n";
+
+    let response = app
+        .post_admin_subscribers_import(&serde_json::json!({
+            "consent_text": "Signed up through the old platform's form.",
+            "csv": csv
+        }))
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let consent =
+        sqlx::query!("SELECT source, consent_text, confirmed_at FROM subscription_consents")
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(consent.source, "import");
+    assert_eq!(
+        consent.consent_text,
+        "Signed up through the old platform's form."
+    );
+    assert!(consent.confirmed_at.is_none());
+}
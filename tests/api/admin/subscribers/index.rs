@@ -0,0 +1,134 @@
+use crate::helpers::{TestUser, spawn_app};
+use newsletter_api::models::{SUBSCRIBER_PAGE_SIZE, SubscriberPage};
+
+#[tokio::test]
+async fn subscribers_are_listed_for_the_logged_in_user_only() {
+    let app = spawn_app().await;
+    let second_user = TestUser::create(&app.db_pool).await.unwrap();
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.create_confirmed_subscriber(
+        Some(second_user.user_id),
+        Some("octavia@example.com".into()),
+    )
+    .await;
+    app.test_user.login(&app).await;
+
+    let response = app.get_admin_subscribers(&[]).await;
+
+    assert_eq!(200, response.status().as_u16());
+    let page: SubscriberPage = response.json().await.unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.subscribers[0].email, "ursula@example.com");
+    assert_eq!(page.subscribers[0].status, "confirmed");
+}
+
+#[tokio::test]
+async fn subscribers_can_be_filtered_by_status() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.create_unconfirmed_subscriber(None, Some("octavia@example.com".into()))
+        .await;
+    app.test_user.login(&app).await;
+
+    let response = app
+        .get_admin_subscribers(&[("status", "pending_confirmation")])
+        .await;
+
+    let page: SubscriberPage = response.json().await.unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.subscribers[0].email, "octavia@example.com");
+}
+
+#[tokio::test]
+async fn subscribers_can_be_filtered_by_signup_date() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.create_confirmed_subscriber(None, Some("octavia@example.com".into()))
+        .await;
+    sqlx::query!(
+        "UPDATE subscriptions SET subscribed_at = '2020-01-01T00:00:00Z' WHERE email = $1",
+        "ursula@example.com"
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    app.test_user.login(&app).await;
+
+    let response = app
+        .get_admin_subscribers(&[("subscribed_before", "2021-01-01T00:00:00Z")])
+        .await;
+    let page: SubscriberPage = response.json().await.unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.subscribers[0].email, "ursula@example.com");
+
+    let response = app
+        .get_admin_subscribers(&[("subscribed_after", "2021-01-01T00:00:00Z")])
+        .await;
+    let page: SubscriberPage = response.json().await.unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.subscribers[0].email, "octavia@example.com");
+}
+
+#[tokio::test]
+async fn subscribers_can_be_searched_by_email_or_name() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.create_confirmed_subscriber(None, Some("octavia@example.com".into()))
+        .await;
+    app.test_user.login(&app).await;
+
+    let response = app.get_admin_subscribers(&[("search", "URSULA")]).await;
+
+    let page: SubscriberPage = response.json().await.unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.subscribers[0].email, "ursula@example.com");
+}
+
+#[tokio::test]
+async fn subscribers_are_paginated() {
+    let app = spawn_app().await;
+    let total_subscribers = SUBSCRIBER_PAGE_SIZE + 1;
+    sqlx::query!(
+        r#"
+        INSERT INTO subscriptions (id, email, name, subscribed_at, status, unsubscribe_token, user_id)
+        SELECT gen_random_uuid(), n || '@example.com', 'subscriber', now(), 'confirmed', gen_random_uuid()::text, $1
+        FROM generate_series(1, $2) AS n
+        "#,
+        app.test_user.user_id,
+        total_subscribers as i32
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    app.test_user.login(&app).await;
+
+    let response = app.get_admin_subscribers(&[("page", "1")]).await;
+    let first_page: SubscriberPage = response.json().await.unwrap();
+    assert_eq!(first_page.subscribers.len() as i64, SUBSCRIBER_PAGE_SIZE);
+    assert_eq!(first_page.total, total_subscribers);
+
+    let response = app.get_admin_subscribers(&[("page", "2")]).await;
+    let second_page: SubscriberPage = response.json().await.unwrap();
+    assert_eq!(second_page.subscribers.len(), 1);
+
+    let response = app.get_admin_subscribers(&[("page", "0")]).await;
+    assert_eq!(400, response.status().as_u16());
+
+    let response = app
+        .get_admin_subscribers(&[("page", &i64::MAX.to_string())])
+        .await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn subscribers_list_rejects_anonymous_users() {
+    let app = spawn_app().await;
+
+    let response = app.get_admin_subscribers(&[]).await;
+
+    assert_eq!(401, response.status().as_u16());
+}
@@ -0,0 +1,4 @@
+mod detail;
+mod export;
+mod import;
+mod index;
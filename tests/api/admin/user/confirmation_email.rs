@@ -0,0 +1,150 @@
+use crate::helpers::spawn_app;
+use newsletter_api::models::ConfirmationEmailTemplate;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+#[tokio::test]
+async fn writers_start_with_the_default_confirmation_email() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    let response = app.get_admin_confirmation_email().await;
+
+    assert_eq!(200, response.status().as_u16());
+    let template: ConfirmationEmailTemplate = response.json().await.unwrap();
+    assert_eq!(template, ConfirmationEmailTemplate::default());
+}
+
+#[tokio::test]
+async fn new_subscribers_receive_the_writers_confirmation_email() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.put_admin_update_user(&serde_json::json!({
+        "bio": "",
+        "description": "",
+        "display_name": "Octavia",
+    }))
+    .await
+    .error_for_status()
+    .unwrap();
+    let response = app
+        .put_admin_confirmation_email(&serde_json::json!({
+            "subject": "{{subscriber_name}}, one more step",
+            "body": "Hi {{subscriber_name}}, thanks for following {{writer_name}}.\n\n\
+                [Confirm your subscription]({{confirmation_link}})",
+        }))
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    app.post_subscriptions(&serde_json::json!({
+        "name": "Ursula",
+        "email": "ursula@example.com",
+        "user_id": &app.test_user.user_id
+    }))
+    .await
+    .error_for_status()
+    .unwrap();
+    app.dispatch_all_pending_confirmation_emails().await;
+
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    assert_eq!(body["Subject"], "Ursula, one more step");
+    assert!(
+        body["Html"]
+            .as_str()
+            .unwrap()
+            .contains("Hi Ursula, thanks for following Octavia.")
+    );
+    assert!(
+        body["Text"]
+            .as_str()
+            .unwrap()
+            .starts_with("Hi Ursula, thanks for following Octavia.")
+    );
+    // The link still confirms the subscription.
+    let confirmation_links = app.get_confirmation_links(email_request);
+    assert_eq!(confirmation_links.html, confirmation_links.plain_text);
+    let response = app
+        .api_client
+        .put(confirmation_links.html)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_confirmation_email_without_the_link_is_rejected_with_a_400() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    let test_cases = vec![
+        (
+            serde_json::json!({ "subject": "Welcome!", "body": "Welcome aboard." }),
+            "missing the confirmation link",
+        ),
+        (
+            serde_json::json!({ "subject": " ", "body": "{{confirmation_link}}" }),
+            "empty subject",
+        ),
+    ];
+
+    for (invalid_body, error_message) in test_cases {
+        let response = app.put_admin_confirmation_email(&invalid_body).await;
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request when the payload was {}.",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn the_confirmation_email_can_be_reset_to_the_default() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.put_admin_confirmation_email(&serde_json::json!({
+        "subject": "Hello",
+        "body": "{{confirmation_link}}",
+    }))
+    .await
+    .error_for_status()
+    .unwrap();
+
+    let response = app.delete_admin_confirmation_email().await;
+
+    assert_eq!(200, response.status().as_u16());
+    let template: ConfirmationEmailTemplate = app
+        .get_admin_confirmation_email()
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(template, ConfirmationEmailTemplate::default());
+}
+
+#[tokio::test]
+async fn confirmation_email_endpoints_reject_anonymous_users() {
+    let app = spawn_app().await;
+
+    let response = app.get_admin_confirmation_email().await;
+    assert_eq!(401, response.status().as_u16());
+
+    let response = app
+        .put_admin_confirmation_email(&serde_json::json!({
+            "subject": "Hello",
+            "body": "{{confirmation_link}}",
+        }))
+        .await;
+    assert_eq!(401, response.status().as_u16());
+
+    let response = app.delete_admin_confirmation_email().await;
+    assert_eq!(401, response.status().as_u16());
+}
@@ -1,3 +1,4 @@
 mod avatar;
 mod banner;
+mod confirmation_email;
 mod index;
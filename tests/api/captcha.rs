@@ -0,0 +1,22 @@
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn captcha_advertises_the_configured_challenge_kind() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_captcha().await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["kind"], "image");
+    assert!(body["challenge"].is_string());
+    assert!(
+        body["challenge_image"]
+            .as_str()
+            .unwrap()
+            .starts_with("data:image/png;base64,")
+    );
+}
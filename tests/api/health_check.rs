@@ -1,4 +1,8 @@
 use crate::helpers::spawn_app;
+use newsletter_api::models::WorkerStatus;
+use newsletter_api::routes::health_check::ready::{ProbeStatus, ReadinessReport};
+use sqlx::{Connection, Executor, PgConnection};
+use uuid::Uuid;
 
 #[tokio::test]
 async fn health_check_works() {
@@ -9,7 +13,7 @@ async fn health_check_works() {
     // Act
     let response = client
         // Use the returned application address
-        .get(&format!("{}/health_check", &app.address))
+        .get(format!("{}/health_check", &app.address))
         .send()
         .await
         .expect("Failed to execute request.");
@@ -18,3 +22,69 @@ async fn health_check_works() {
     assert!(response.status().is_success());
     assert_eq!(Some(0), response.content_length());
 }
+
+#[tokio::test]
+async fn readiness_reports_every_dependency_as_up() {
+    let app = spawn_app().await;
+
+    let response = app.get_readiness().await;
+
+    assert_eq!(200, response.status().as_u16());
+    let report: ReadinessReport = response.json().await.unwrap();
+    assert!(report.ready);
+    assert_eq!(report.postgres, ProbeStatus::Up);
+    assert_eq!(report.redis, ProbeStatus::Up);
+    assert_eq!(report.s3, ProbeStatus::Up);
+    let worker = report.worker.unwrap();
+    assert_eq!(worker.queue_depth, 0);
+    assert!(worker.last_heartbeat_at.is_none());
+}
+
+#[tokio::test]
+async fn readiness_reports_the_worker_heartbeat_and_queue_depth() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    app.create_published_newsletter_issue().await;
+    WorkerStatus::record_heartbeat(Uuid::new_v4(), &app.db_pool)
+        .await
+        .unwrap();
+
+    let response = app.get_readiness().await;
+
+    assert_eq!(200, response.status().as_u16());
+    let report: ReadinessReport = response.json().await.unwrap();
+    let worker = report.worker.unwrap();
+    assert_eq!(worker.queue_depth, 1);
+    assert!(worker.last_heartbeat_at.is_some());
+}
+
+#[tokio::test]
+async fn readiness_returns_503_when_the_database_is_unreachable() {
+    let app = spawn_app().await;
+    let database_name = app
+        .db_pool
+        .connect_options()
+        .get_database()
+        .unwrap()
+        .to_string();
+    let mut connection = PgConnection::connect_with(
+        &(*app.db_pool.connect_options())
+            .clone()
+            .database("postgres"),
+    )
+    .await
+    .unwrap();
+    connection
+        .execute(format!(r#"DROP DATABASE "{database_name}" WITH (FORCE);"#).as_str())
+        .await
+        .unwrap();
+
+    let response = app.get_readiness().await;
+
+    assert_eq!(503, response.status().as_u16());
+    let report: ReadinessReport = response.json().await.unwrap();
+    assert!(!report.ready);
+    assert_eq!(report.postgres, ProbeStatus::Down);
+    assert!(report.worker.is_none());
+}
@@ -1,18 +1,29 @@
 use fake::Fake;
 use fake::faker::internet::en::SafeEmail;
 use fake::faker::name::en::Name;
+use newsletter_api::challenge::Base64Challenger;
 use newsletter_api::clients::cloudinary_client::CloudinaryClient;
-use newsletter_api::configuration::{DatabaseSettings, get_configuration};
+use newsletter_api::configuration::{DatabaseSettings, WorkerSettings, get_configuration};
+use newsletter_api::confirmation_email_worker::try_send_confirmation_email;
 use newsletter_api::email_client::{EmailClient, EmailServer};
-use newsletter_api::issue_delivery_worker::{ExecutionOutcome, try_execute_task};
-use newsletter_api::models::{NewUser, NewUserData, UserProfile};
+use newsletter_api::erasure_confirmation_worker::try_send_erasure_confirmation;
+use newsletter_api::issue_delivery_worker::{
+    ExecutionOutcome, try_execute_task, try_publish_scheduled_issue,
+};
+use newsletter_api::models::{NewUser, NewUserData, NewsletterIssueAPI, UserProfile};
+use newsletter_api::preference_link_worker::try_send_preference_link;
+use newsletter_api::rate_limiter::RateLimiter;
+use newsletter_api::shutdown::shutdown_channel;
 use newsletter_api::startup::{Application, get_connection_pool};
+use newsletter_api::subscriber_retention_worker::{
+    try_purge_unconfirmed_subscribers, try_remind_unconfirmed_subscribers,
+};
 use newsletter_api::telemetry::{get_subscriber, init_subscriber};
-use secrecy::Secret;
+use secrecy::{ExposeSecret, Secret};
 use sqlx::{Connection, Executor, PgConnection, PgPool};
 use std::sync::LazyLock;
 use uuid::Uuid;
-use wiremock::matchers::{method, path};
+use wiremock::matchers::{any, method, path};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 // Ensure that the `tracing` stack is only initialised once using `once_cell`
@@ -30,6 +41,8 @@ static TRACING: LazyLock<()> = LazyLock::new(|| {
 
 pub struct TestApp {
     pub address: String,
+    pub base_url: String,
+    pub captcha_secret: Secret<String>,
     pub port: u16,
     pub db_pool: PgPool,
     pub email_server: MockServer,
@@ -38,6 +51,9 @@ pub struct TestApp {
     pub cloudinary_client: CloudinaryClient,
     pub cloudinary_server: MockServer,
     pub email_client: EmailClient,
+    pub rate_limiter: RateLimiter,
+    pub worker_settings: WorkerSettings,
+    pub webhook_secret: Secret<String>,
 }
 
 /// Confirmation links embedded in the request to the email API.
@@ -48,35 +64,284 @@ pub struct ConfirmationLinks {
 
 impl TestApp {
     pub async fn dispatch_all_pending_emails(&self) {
+        let (_shutdown_sender, shutdown) = shutdown_channel();
+        loop {
+            if let ExecutionOutcome::EmptyQueue = try_execute_task(
+                &self.db_pool,
+                &self.email_client,
+                &self.rate_limiter,
+                &self.worker_settings,
+                &self.base_url,
+                shutdown.clone(),
+            )
+            .await
+            .unwrap()
+            {
+                break;
+            }
+        }
+    }
+
+    pub async fn dispatch_all_pending_confirmation_emails(&self) {
+        let (_shutdown_sender, shutdown) = shutdown_channel();
+        loop {
+            if let ExecutionOutcome::EmptyQueue = try_send_confirmation_email(
+                &self.db_pool,
+                &self.email_client,
+                &self.rate_limiter,
+                &self.worker_settings,
+                &self.base_url,
+                shutdown.clone(),
+            )
+            .await
+            .unwrap()
+            {
+                break;
+            }
+        }
+    }
+
+    pub async fn dispatch_all_pending_preference_links(&self) {
+        let (_shutdown_sender, shutdown) = shutdown_channel();
+        loop {
+            if let ExecutionOutcome::EmptyQueue = try_send_preference_link(
+                &self.db_pool,
+                &self.email_client,
+                &self.rate_limiter,
+                &self.worker_settings,
+                &self.base_url,
+                shutdown.clone(),
+            )
+            .await
+            .unwrap()
+            {
+                break;
+            }
+        }
+    }
+
+    pub async fn dispatch_all_pending_erasure_confirmations(&self) {
+        let (_shutdown_sender, shutdown) = shutdown_channel();
+        loop {
+            if let ExecutionOutcome::EmptyQueue = try_send_erasure_confirmation(
+                &self.db_pool,
+                &self.email_client,
+                &self.rate_limiter,
+                &self.worker_settings,
+                shutdown.clone(),
+            )
+            .await
+            .unwrap()
+            {
+                break;
+            }
+        }
+    }
+
+    pub async fn publish_all_due_issues(&self) {
         loop {
             if let ExecutionOutcome::EmptyQueue =
-                try_execute_task(&self.db_pool, &self.email_client)
-                    .await
-                    .unwrap()
+                try_publish_scheduled_issue(&self.db_pool).await.unwrap()
             {
                 break;
             }
         }
     }
 
+    /// Run the retention job until there is nobody left to remind or purge.
+    pub async fn enforce_retention_policy(&self, settings: &WorkerSettings) {
+        while let ExecutionOutcome::TaskCompleted =
+            try_remind_unconfirmed_subscribers(&self.db_pool, settings)
+                .await
+                .unwrap()
+        {}
+        while let ExecutionOutcome::TaskCompleted =
+            try_purge_unconfirmed_subscribers(&self.db_pool, settings)
+                .await
+                .unwrap()
+        {}
+    }
+
+    /// Pretend a subscriber signed up `days` days ago.
+    pub async fn backdate_signup(&self, email: &str, days: i32) {
+        sqlx::query!(
+            r#"
+            UPDATE subscription_consents
+            SET consented_at = now() - make_interval(days => $2)
+            FROM subscriptions
+            WHERE subscriptions.id = subscription_consents.subscriber_id
+              AND subscriptions.email = $1
+            "#,
+            email,
+            days
+        )
+        .execute(&self.db_pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "UPDATE subscriptions SET subscribed_at = now() - make_interval(days => $2) \
+            WHERE email = $1",
+            email,
+            days
+        )
+        .execute(&self.db_pool)
+        .await
+        .unwrap();
+    }
+
+    /// Generate a captcha challenge and its answer as issued by `GET /captcha`.
+    pub fn solve_captcha(&self) -> (String, String) {
+        let challenger = Base64Challenger::new(self.captcha_secret.clone()).unwrap();
+        (
+            challenger.encrypt().unwrap(),
+            challenger.answer().to_string(),
+        )
+    }
+
+    /// Post a subscription request, solving a fresh captcha unless the body carries one.
     pub async fn post_subscriptions<Body>(&self, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
     {
+        let mut body = serde_json::to_value(body).unwrap();
+        if let Some(fields) = body.as_object_mut()
+            && !fields.contains_key("challenge")
+        {
+            let (challenge, answer) = self.solve_captcha();
+            fields.insert("challenge".into(), challenge.into());
+            fields.insert("answer".into(), answer.into());
+        }
         self.api_client
-            .post(&format!("{}/subscriptions", &self.address))
+            .post(format!("{}/subscriptions", &self.address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Ask for a preference centre link, solving a fresh captcha unless the body carries one.
+    pub async fn post_subscriptions_preferences<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let mut body = serde_json::to_value(body).unwrap();
+        if let Some(fields) = body.as_object_mut()
+            && !fields.contains_key("challenge")
+        {
+            let (challenge, answer) = self.solve_captcha();
+            fields.insert("challenge".into(), challenge.into());
+            fields.insert("answer".into(), answer.into());
+        }
+        self.api_client
+            .post(format!("{}/subscriptions/preferences", &self.address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Request a preference centre link for `email` and return the link from the email sent.
+    pub async fn get_preference_link(&self, email: &str) -> reqwest::Url {
+        let _mock_guard = Mock::given(path("/api/v1/send"))
+            .and(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .named("Send preference centre link")
+            .expect(1)
+            .mount_as_scoped(&self.email_server)
+            .await;
+        self.post_subscriptions_preferences(&serde_json::json!({ "email": email }))
+            .await
+            .error_for_status()
+            .unwrap();
+        self.dispatch_all_pending_preference_links().await;
+
+        let email_request = self
+            .email_server
+            .received_requests()
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+        self.get_confirmation_links(&email_request).html
+    }
+
+    pub async fn put_subscriptions_preference<Body>(
+        &self,
+        preference_link: &reqwest::Url,
+        subscriber_id: &Uuid,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let mut url = preference_link.clone();
+        url.set_path(&format!("/subscriptions/preferences/{}", subscriber_id));
+        self.api_client
+            .put(url)
             .json(body)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
+    pub async fn get_subscriptions_preferences_data(
+        &self,
+        preference_link: &reqwest::Url,
+    ) -> reqwest::Response {
+        let mut url = preference_link.clone();
+        url.set_path("/subscriptions/preferences/data");
+        self.api_client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_subscriptions_preferences_data(
+        &self,
+        preference_link: &reqwest::Url,
+    ) -> reqwest::Response {
+        let mut url = preference_link.clone();
+        url.set_path("/subscriptions/preferences/data");
+        self.api_client
+            .delete(url)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Send a webhook authenticated with the configured secret, as the email provider would.
+    pub async fn post_postmark_webhook(&self, body: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/webhooks/postmark", &self.address))
+            .basic_auth("postmark", Some(self.webhook_secret.expose_secret()))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_postmark_webhook_with_authorization(
+        &self,
+        body: &serde_json::Value,
+        authorization: Option<&str>,
+    ) -> reqwest::Response {
+        let mut request = self
+            .api_client
+            .post(format!("{}/webhooks/postmark", &self.address))
+            .json(body);
+        if let Some(authorization) = authorization {
+            request = request.header("Authorization", authorization);
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
     pub async fn post_login<Body>(&self, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
     {
         self.api_client
-            .post(&format!("{}/login", &self.address))
+            .post(format!("{}/login", &self.address))
             .json(body)
             .send()
             .await
@@ -85,7 +350,7 @@ impl TestApp {
 
     pub async fn post_logout(&self) -> reqwest::Response {
         self.api_client
-            .post(&format!("{}/admin/logout", &self.address))
+            .post(format!("{}/admin/logout", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -96,7 +361,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.api_client
-            .put(&format!("{}/admin/password", &self.address))
+            .put(format!("{}/admin/password", &self.address))
             .json(body)
             .send()
             .await
@@ -105,7 +370,7 @@ impl TestApp {
 
     pub async fn get_admin_newsletter_issues(&self) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/admin/newsletters", &self.address))
+            .get(format!("{}/admin/newsletters", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -113,7 +378,7 @@ impl TestApp {
 
     pub async fn get_admin_unpublished_newsletter_issues(&self) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/admin/newsletters/drafts", &self.address))
+            .get(format!("{}/admin/newsletters/drafts", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -124,7 +389,7 @@ impl TestApp {
         newsletter_issue_id: &Uuid,
     ) -> reqwest::Response {
         self.api_client
-            .get(&format!(
+            .get(format!(
                 "{}/admin/newsletters/{}",
                 &self.address, newsletter_issue_id
             ))
@@ -138,7 +403,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.api_client
-            .post(&format!("{}/admin/newsletters", &self.address))
+            .post(format!("{}/admin/newsletters", &self.address))
             .json(body)
             .send()
             .await
@@ -154,7 +419,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.api_client
-            .put(&format!(
+            .put(format!(
                 "{}/admin/newsletters/{}/publish",
                 &self.address, newsletter_issue_id
             ))
@@ -173,7 +438,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.api_client
-            .put(&format!(
+            .put(format!(
                 "{}/admin/newsletters/{}",
                 &self.address, newsletter_issue_id
             ))
@@ -192,7 +457,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.api_client
-            .put(&format!(
+            .put(format!(
                 "{}/admin/newsletter/{}/cover_image",
                 &self.address, newsletter_issue_id
             ))
@@ -202,9 +467,305 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    /// Create a draft issue ready for publishing as the logged in user and return its id.
+    pub async fn create_draft_newsletter_issue(&self) -> Uuid {
+        self.post_admin_create_newsletter(&serde_json::json!({
+          "title": Uuid::new_v4().to_string(),
+          "description": "Newsletter description",
+          "content": "## Newsletter body as markdown",
+          "cover_image": "",
+        }))
+        .await
+        .error_for_status()
+        .unwrap();
+
+        let response = self.get_admin_unpublished_newsletter_issues().await;
+        let response_body: Vec<NewsletterIssueAPI> = response.json().await.unwrap();
+        response_body[0].newsletter_issue_id
+    }
+
+    /// Create a draft issue as the logged in user, publish it and return its id.
+    pub async fn create_published_newsletter_issue(&self) -> Uuid {
+        let newsletter_issue_id = self.create_draft_newsletter_issue().await;
+
+        self.put_admin_publish_newsletter(
+            &newsletter_issue_id,
+            &serde_json::json!({
+              "idempotency_key": Uuid::new_v4().to_string()
+            }),
+        )
+        .await
+        .error_for_status()
+        .unwrap();
+
+        newsletter_issue_id
+    }
+
+    pub async fn put_admin_schedule_newsletter<Body>(
+        &self,
+        newsletter_issue_id: &Uuid,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .put(format!(
+                "{}/admin/newsletters/{}/schedule",
+                &self.address, newsletter_issue_id
+            ))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_admin_schedule_newsletter(
+        &self,
+        newsletter_issue_id: &Uuid,
+    ) -> reqwest::Response {
+        self.api_client
+            .delete(format!(
+                "{}/admin/newsletters/{}/schedule",
+                &self.address, newsletter_issue_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_delivery_report(
+        &self,
+        newsletter_issue_id: &Uuid,
+        page: Option<i64>,
+    ) -> reqwest::Response {
+        let mut request = self.api_client.get(format!(
+            "{}/admin/newsletters/{}/deliveries",
+            &self.address, newsletter_issue_id
+        ));
+        if let Some(page) = page {
+            request = request.query(&[("page", page)]);
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_subscribers(&self, query: &[(&str, &str)]) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/subscribers", &self.address))
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_subscribers_export(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/subscribers/export", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_admin_subscribers_import<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/subscribers/import", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_subscriber(&self, subscriber_id: &Uuid) -> reqwest::Response {
+        self.api_client
+            .get(format!(
+                "{}/admin/subscribers/{}",
+                &self.address, subscriber_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_subscriber_consents(&self, subscriber_id: &Uuid) -> reqwest::Response {
+        self.api_client
+            .get(format!(
+                "{}/admin/subscribers/{}/consents",
+                &self.address, subscriber_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_subscriber_consents_export(
+        &self,
+        subscriber_id: &Uuid,
+    ) -> reqwest::Response {
+        self.api_client
+            .get(format!(
+                "{}/admin/subscribers/{}/consents/export",
+                &self.address, subscriber_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_admin_subscriber(&self, subscriber_id: &Uuid) -> reqwest::Response {
+        self.api_client
+            .delete(format!(
+                "{}/admin/subscribers/{}",
+                &self.address, subscriber_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_admin_subscriber_status<Body>(
+        &self,
+        subscriber_id: &Uuid,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .put(format!(
+                "{}/admin/subscribers/{}/status",
+                &self.address, subscriber_id
+            ))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_admin_subscriber_tags<Body>(
+        &self,
+        subscriber_id: &Uuid,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .put(format!(
+                "{}/admin/subscribers/{}/tags",
+                &self.address, subscriber_id
+            ))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// The id of the test user's subscriber with the given email.
+    pub async fn subscriber_id(&self, email: &str) -> Uuid {
+        sqlx::query!(
+            "SELECT id FROM subscriptions WHERE email = $1 AND user_id = $2",
+            email,
+            self.test_user.user_id
+        )
+        .fetch_one(&self.db_pool)
+        .await
+        .expect("Failed to fetch subscriber id.")
+        .id
+    }
+
+    pub async fn get_admin_failed_deliveries(
+        &self,
+        newsletter_issue_id: &Uuid,
+    ) -> reqwest::Response {
+        self.api_client
+            .get(format!(
+                "{}/admin/newsletters/{}/deliveries/failed",
+                &self.address, newsletter_issue_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_admin_requeue_failed_deliveries(
+        &self,
+        newsletter_issue_id: &Uuid,
+    ) -> reqwest::Response {
+        self.api_client
+            .put(format!(
+                "{}/admin/newsletters/{}/deliveries/failed/requeue",
+                &self.address, newsletter_issue_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_admin_requeue_failed_delivery(
+        &self,
+        newsletter_issue_id: &Uuid,
+        failed_delivery_id: &Uuid,
+    ) -> reqwest::Response {
+        self.api_client
+            .put(format!(
+                "{}/admin/newsletters/{}/deliveries/failed/{}/requeue",
+                &self.address, newsletter_issue_id, failed_delivery_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Exhaust every delivery attempt of the queued tasks so they end up in the dead-letter table.
+    pub async fn fail_all_pending_deliveries(&self) {
+        let _guard = Mock::given(any())
+            .respond_with(ResponseTemplate::new(500))
+            .mount_as_scoped(&self.email_server)
+            .await;
+
+        for _ in 0..self.worker_settings.max_delivery_attempts {
+            sqlx::query!("UPDATE issue_delivery_queue SET next_attempt_at = now()")
+                .execute(&self.db_pool)
+                .await
+                .unwrap();
+            self.dispatch_all_pending_emails().await;
+        }
+    }
+
     pub async fn get_admin_user(&self) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/admin/user", &self.address))
+            .get(format!("{}/admin/user", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_confirmation_email(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/user/confirmation_email", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_admin_confirmation_email<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .put(format!("{}/admin/user/confirmation_email", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_admin_confirmation_email(&self) -> reqwest::Response {
+        self.api_client
+            .delete(format!("{}/admin/user/confirmation_email", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -215,7 +776,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.api_client
-            .put(&format!("{}/admin/user", &self.address))
+            .put(format!("{}/admin/user", &self.address))
             .json(body)
             .send()
             .await
@@ -227,7 +788,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.api_client
-            .put(&format!("{}/admin/user/avatar", &self.address))
+            .put(format!("{}/admin/user/avatar", &self.address))
             .json(body)
             .send()
             .await
@@ -239,7 +800,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.api_client
-            .put(&format!("{}/admin/user/banner", &self.address))
+            .put(format!("{}/admin/user/banner", &self.address))
             .json(body)
             .send()
             .await
@@ -248,7 +809,7 @@ impl TestApp {
 
     pub async fn get_public_newsletters(&self) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/newsletters", &self.address))
+            .get(format!("{}/newsletters", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -260,7 +821,7 @@ impl TestApp {
         slug: &String,
     ) -> reqwest::Response {
         self.api_client
-            .get(&format!(
+            .get(format!(
                 "{}/newsletters/by_user/{}/issue/{}",
                 &self.address, username, slug
             ))
@@ -271,7 +832,7 @@ impl TestApp {
 
     pub async fn get_public_newsletters_by_user(&self, username: &String) -> reqwest::Response {
         self.api_client
-            .get(&format!(
+            .get(format!(
                 "{}/newsletters/by_user/{}",
                 &self.address, username
             ))
@@ -282,7 +843,7 @@ impl TestApp {
 
     pub async fn get_users(&self) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/users", &self.address))
+            .get(format!("{}/users", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -290,7 +851,23 @@ impl TestApp {
 
     pub async fn get_user(&self, username: &String) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/users/{}", &self.address, username))
+            .get(format!("{}/users/{}", &self.address, username))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_captcha(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/captcha", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_readiness(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/health_check/ready", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -298,7 +875,7 @@ impl TestApp {
 
     pub async fn get_authenticate(&self) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/admin/authenticate", &self.address))
+            .get(format!("{}/admin/authenticate", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -331,6 +908,7 @@ impl TestApp {
             .await
             .error_for_status()
             .unwrap();
+        self.dispatch_all_pending_confirmation_emails().await;
 
         let email_request = self
             .email_server
@@ -352,6 +930,32 @@ impl TestApp {
             .expect("Failed to confirm subscriber.");
     }
 
+    /// Extract the one-click unsubscribe link from the `List-Unsubscribe` header of a
+    /// request to the email API.
+    pub fn get_unsubscribe_link(&self, email_request: &wiremock::Request) -> reqwest::Url {
+        let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+        let header = match self.email_client.server {
+            EmailServer::Mailpit => body["Headers"]["List-Unsubscribe"].as_str().unwrap(),
+            EmailServer::Postmark => body["Headers"][0]["Value"].as_str().unwrap(),
+            EmailServer::Smtp => unreachable!("Test emails are captured by the HTTP mock server."),
+        };
+        let mut unsubscribe_link =
+            reqwest::Url::parse(header.trim_start_matches('<').trim_end_matches('>')).unwrap();
+        assert_eq!(unsubscribe_link.host_str().unwrap(), "127.0.0.1");
+        unsubscribe_link.set_port(Some(self.port)).unwrap();
+        unsubscribe_link
+    }
+
+    pub async fn post_unsubscribe(&self, unsubscribe_link: reqwest::Url) -> reqwest::Response {
+        self.api_client
+            .post(unsubscribe_link)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body("List-Unsubscribe=One-Click")
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     /// Extract the confirmation links embedded in the request to the email API.
     pub fn get_confirmation_links(&self, email_request: &wiremock::Request) -> ConfirmationLinks {
         let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
@@ -374,10 +978,12 @@ impl TestApp {
         let html = match self.email_client.server {
             EmailServer::Mailpit => get_link(body["Html"].as_str().unwrap()),
             EmailServer::Postmark => get_link(body["HtmlBody"].as_str().unwrap()),
+            EmailServer::Smtp => unreachable!("Test emails are captured by the HTTP mock server."),
         };
         let plain_text = match self.email_client.server {
             EmailServer::Mailpit => get_link(body["Text"].as_str().unwrap()),
             EmailServer::Postmark => get_link(body["TextBody"].as_str().unwrap()),
+            EmailServer::Smtp => unreachable!("Test emails are captured by the HTTP mock server."),
         };
 
         ConfirmationLinks { html, plain_text }
@@ -416,7 +1022,7 @@ pub async fn spawn_app() -> TestApp {
         .await
         .expect("Failed to build application.");
     let application_port = application.port();
-    let _ = tokio::spawn(application.run_until_stopped());
+    tokio::spawn(application.run_until_stopped());
 
     let client = reqwest::Client::builder()
         .redirect(reqwest::redirect::Policy::none())
@@ -428,8 +1034,11 @@ pub async fn spawn_app() -> TestApp {
     let test_user = TestUser::create(&db_pool)
         .await
         .expect("Failed to create test user.");
-    let test_app = TestApp {
+
+    TestApp {
         address: format!("http://localhost:{}", application_port),
+        base_url: configuration.application.base_url.clone(),
+        captcha_secret: configuration.application.captcha_secret.clone(),
         port: application_port,
         cloudinary_client: configuration.cloudinary_client.client(),
         cloudinary_server,
@@ -437,10 +1046,11 @@ pub async fn spawn_app() -> TestApp {
         email_server,
         test_user,
         api_client: client,
+        webhook_secret: configuration.email_client.webhook_secret.clone(),
         email_client: configuration.email_client.client(),
-    };
-
-    test_app
+        rate_limiter: RateLimiter::new(&configuration.worker),
+        worker_settings: configuration.worker,
+    }
 }
 
 async fn configure_database(config: &DatabaseSettings) -> PgPool {
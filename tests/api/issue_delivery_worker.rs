@@ -0,0 +1,476 @@
+use crate::helpers::spawn_app;
+use chrono::Utc;
+use newsletter_api::domain::SubscriberEmail;
+use newsletter_api::email_client::{EmailClient, EmailServer};
+use newsletter_api::issue_delivery_worker::{ExecutionOutcome, run_workers, try_execute_task};
+use newsletter_api::models::{DeliveryReport, WorkerStatus};
+use newsletter_api::shutdown::shutdown_channel;
+use secrecy::Secret;
+use std::time::{Duration, Instant};
+use wiremock::matchers::{any, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+#[tokio::test]
+async fn failed_deliveries_are_rescheduled_with_backoff() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    app.create_published_newsletter_issue().await;
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    app.dispatch_all_pending_emails().await;
+
+    let task =
+        sqlx::query!("SELECT n_attempts, last_error, next_attempt_at FROM issue_delivery_queue")
+            .fetch_one(&app.db_pool)
+            .await
+            .expect("The failed delivery should still be queued.");
+
+    assert_eq!(task.n_attempts, 1);
+    assert!(task.last_error.is_some());
+    assert!(task.next_attempt_at > Utc::now());
+}
+
+#[tokio::test]
+async fn rescheduled_deliveries_are_retried_once_due() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    app.create_published_newsletter_issue().await;
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    app.dispatch_all_pending_emails().await;
+    sqlx::query!("UPDATE issue_delivery_queue SET next_attempt_at = now()")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    app.dispatch_all_pending_emails().await;
+
+    let remaining = sqlx::query!("SELECT COUNT(*) AS \"count!\" FROM issue_delivery_queue")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining.count, 0);
+}
+
+#[tokio::test]
+async fn deliveries_are_dead_lettered_after_the_maximum_number_of_attempts() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    app.create_published_newsletter_issue().await;
+    let max_attempts = app.worker_settings.max_delivery_attempts;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(500))
+        .expect(max_attempts as u64)
+        .mount(&app.email_server)
+        .await;
+
+    for _ in 0..max_attempts {
+        sqlx::query!("UPDATE issue_delivery_queue SET next_attempt_at = now()")
+            .execute(&app.db_pool)
+            .await
+            .unwrap();
+        app.dispatch_all_pending_emails().await;
+    }
+
+    let remaining = sqlx::query!("SELECT COUNT(*) AS \"count!\" FROM issue_delivery_queue")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining.count, 0);
+
+    let failed = sqlx::query!("SELECT n_attempts, failure_reason FROM failed_issue_deliveries")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("The failed delivery should have been dead-lettered.");
+    assert_eq!(failed.n_attempts, max_attempts);
+    assert!(!failed.failure_reason.is_empty());
+}
+
+#[tokio::test]
+async fn deliveries_to_invalid_emails_are_dead_lettered_immediately() {
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+
+    sqlx::query!(
+        "INSERT INTO issue_delivery_queue (newsletter_issue_id, subscriber_email) VALUES ($1, $2)",
+        newsletter_issue_id,
+        "not-an-email"
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    app.dispatch_all_pending_emails().await;
+
+    let failed = sqlx::query!("SELECT subscriber_email, n_attempts FROM failed_issue_deliveries")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("The invalid delivery should have been dead-lettered.");
+    assert_eq!(failed.subscriber_email, "not-an-email");
+    assert_eq!(failed.n_attempts, 0);
+}
+
+#[tokio::test]
+async fn a_batch_of_deliveries_is_sent_in_a_single_execution() {
+    let app = spawn_app().await;
+    for _ in 0..3 {
+        app.create_confirmed_subscriber(None, None).await;
+    }
+    app.test_user.login(&app).await;
+    app.create_published_newsletter_issue().await;
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(3)
+        .mount(&app.email_server)
+        .await;
+
+    let (_shutdown_sender, shutdown) = shutdown_channel();
+    let outcome = try_execute_task(
+        &app.db_pool,
+        &app.email_client,
+        &app.rate_limiter,
+        &app.worker_settings,
+        &app.base_url,
+        shutdown,
+    )
+    .await
+    .unwrap();
+    assert!(matches!(outcome, ExecutionOutcome::TaskCompleted));
+
+    let remaining = sqlx::query!("SELECT COUNT(*) AS \"count!\" FROM issue_delivery_queue")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining.count, 0);
+}
+
+#[tokio::test]
+async fn concurrent_workers_never_deliver_the_same_email_twice() {
+    let app = spawn_app().await;
+    for _ in 0..10 {
+        app.create_confirmed_subscriber(None, None).await;
+    }
+    app.test_user.login(&app).await;
+    app.create_published_newsletter_issue().await;
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(10)
+        .mount(&app.email_server)
+        .await;
+
+    let mut settings = app.worker_settings.clone();
+    settings.batch_size = 2;
+    let (_shutdown_sender, shutdown) = shutdown_channel();
+    let worker = || async {
+        while let ExecutionOutcome::TaskCompleted = try_execute_task(
+            &app.db_pool,
+            &app.email_client,
+            &app.rate_limiter,
+            &settings,
+            &app.base_url,
+            shutdown.clone(),
+        )
+        .await
+        .unwrap()
+        {}
+    };
+    tokio::join!(worker(), worker(), worker(), worker());
+
+    let remaining = sqlx::query!("SELECT COUNT(*) AS \"count!\" FROM issue_delivery_queue")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining.count, 0);
+}
+
+#[tokio::test]
+async fn deliveries_in_flight_are_leased_rather_than_locked() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    app.create_published_newsletter_issue().await;
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let requests_before = app.email_server.received_requests().await.unwrap().len();
+    let worker = app.dispatch_all_pending_emails();
+    let inspect = async {
+        while app.email_server.received_requests().await.unwrap().len() == requests_before {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        // The row can be locked by someone else while the email is in flight...
+        let mut transaction = app.db_pool.begin().await.unwrap();
+        let leased =
+            sqlx::query!("SELECT next_attempt_at FROM issue_delivery_queue FOR UPDATE NOWAIT")
+                .fetch_one(&mut *transaction)
+                .await
+                .expect("The delivery should not be locked while its email is in flight.");
+        // ...but it is not due, so no other worker picks it up.
+        assert!(leased.next_attempt_at > Utc::now());
+        transaction.rollback().await.unwrap();
+    };
+    tokio::join!(worker, inspect);
+
+    let remaining = sqlx::query!("SELECT COUNT(*) AS \"count!\" FROM issue_delivery_queue")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining.count, 0);
+}
+
+#[tokio::test]
+async fn deliveries_waiting_for_the_rate_limiter_are_released_on_shutdown() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    app.create_published_newsletter_issue().await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+    app.rate_limiter
+        .pause(Duration::from_secs(600), Instant::now());
+
+    let (shutdown_sender, shutdown) = shutdown_channel();
+    let worker = try_execute_task(
+        &app.db_pool,
+        &app.email_client,
+        &app.rate_limiter,
+        &app.worker_settings,
+        &app.base_url,
+        shutdown,
+    );
+    let inspect = async {
+        // The delivery is leased, not locked, while the worker waits for the rate limiter...
+        loop {
+            let task = sqlx::query!("SELECT next_attempt_at FROM issue_delivery_queue")
+                .fetch_one(&app.db_pool)
+                .await
+                .unwrap();
+            if task.next_attempt_at > Utc::now() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let mut transaction = app.db_pool.begin().await.unwrap();
+        sqlx::query!("SELECT next_attempt_at FROM issue_delivery_queue FOR UPDATE NOWAIT")
+            .fetch_one(&mut *transaction)
+            .await
+            .expect("The delivery should not be locked while waiting.");
+        transaction.rollback().await.unwrap();
+        // ...and a shutdown ends the wait.
+        shutdown_sender.send(true).unwrap();
+    };
+    let (outcome, _) = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::join!(worker, inspect)
+    })
+    .await
+    .expect("The worker should stop waiting once a shutdown is requested.");
+    outcome.unwrap();
+
+    let task = sqlx::query!("SELECT n_attempts, next_attempt_at FROM issue_delivery_queue")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("The unsent delivery should still be queued.");
+    assert_eq!(task.n_attempts, 0);
+    assert!(task.next_attempt_at <= Utc::now());
+}
+
+#[tokio::test]
+async fn postmark_batch_results_are_recorded_per_recipient() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    app.create_published_newsletter_issue().await;
+    let email_client = EmailClient::new(
+        app.email_server.uri(),
+        SubscriberEmail::parse(String::from("sender@example.com")).unwrap(),
+        Secret::new(String::from("my-secret-token")),
+        Duration::from_millis(200),
+        EmailServer::Postmark,
+    );
+
+    Mock::given(path("/email/batch"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+            { "ErrorCode": 0, "Message": "OK" },
+            { "ErrorCode": 406, "Message": "Address is inactive." },
+        ])))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let (_shutdown_sender, shutdown) = shutdown_channel();
+    try_execute_task(
+        &app.db_pool,
+        &email_client,
+        &app.rate_limiter,
+        &app.worker_settings,
+        &app.base_url,
+        shutdown,
+    )
+    .await
+    .unwrap();
+
+    let outcomes = sqlx::query!("SELECT outcome FROM delivery_log ORDER BY outcome")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap();
+    let outcomes: Vec<String> = outcomes.into_iter().map(|r| r.outcome).collect();
+    assert_eq!(outcomes, vec!["retrying", "sent"]);
+
+    let task = sqlx::query!("SELECT n_attempts FROM issue_delivery_queue")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("The rejected delivery should still be queued.");
+    assert_eq!(task.n_attempts, 1);
+}
+
+#[tokio::test]
+async fn throttled_deliveries_are_postponed_without_consuming_an_attempt() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "120"))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    app.dispatch_all_pending_emails().await;
+
+    let task = sqlx::query!("SELECT n_attempts, next_attempt_at FROM issue_delivery_queue")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("The throttled delivery should still be queued.");
+    assert_eq!(task.n_attempts, 0);
+    assert!(task.next_attempt_at > Utc::now() + chrono::Duration::seconds(100));
+
+    let response = app
+        .get_admin_delivery_report(&newsletter_issue_id, None)
+        .await;
+    let report: DeliveryReport = response.json().await.unwrap();
+    assert_eq!(report.log[0].outcome, "throttled");
+}
+
+#[tokio::test]
+async fn the_rate_limiter_is_paused_when_the_provider_throttles_deliveries() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    app.create_published_newsletter_issue().await;
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "120"))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    app.dispatch_all_pending_emails().await;
+
+    assert!(
+        app.rate_limiter
+            .try_acquire(app.test_user.user_id, Instant::now())
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn workers_finish_their_current_batch_before_shutting_down() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let email_client = EmailClient::new(
+        app.email_server.uri(),
+        SubscriberEmail::parse(String::from("sender@example.com")).unwrap(),
+        Secret::new(String::from("my-secret-token")),
+        Duration::from_secs(5),
+        EmailServer::Mailpit,
+    );
+    let requests_before = app.email_server.received_requests().await.unwrap().len();
+    let (shutdown_sender, shutdown) = shutdown_channel();
+    let workers = tokio::spawn(run_workers(
+        app.db_pool.clone(),
+        email_client,
+        app.worker_settings.clone(),
+        app.base_url.clone(),
+        shutdown,
+    ));
+
+    // Request a shutdown while the email is still in flight
+    while app.email_server.received_requests().await.unwrap().len() == requests_before {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    shutdown_sender.send(true).unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), workers)
+        .await
+        .expect("The workers should stop once their batch is done.")
+        .unwrap()
+        .unwrap();
+
+    let response = app
+        .get_admin_delivery_report(&newsletter_issue_id, None)
+        .await;
+    let report: DeliveryReport = response.json().await.unwrap();
+    assert_eq!(report.sent, 1);
+    assert_eq!(report.pending, 0);
+
+    // A worker that shut down cleanly no longer reports a heartbeat
+    let worker_status = WorkerStatus::get(&app.db_pool).await.unwrap();
+    assert!(worker_status.last_heartbeat_at.is_none());
+}
@@ -1,10 +1,16 @@
 mod admin;
 mod admin_dashboard;
+mod captcha;
 mod change_password;
 mod health_check;
 mod helpers;
+mod issue_delivery_worker;
 mod login;
 mod newsletters;
+mod subscriber_retention_worker;
 mod subscriptions;
 mod subscriptions_confirm;
+mod subscriptions_preferences;
+mod subscriptions_unsubscribe;
 mod users;
+mod webhooks;
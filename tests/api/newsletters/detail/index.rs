@@ -62,7 +62,7 @@ async fn public_newsletter_includes_associated_user() {
         description: "Description".to_string(),
         display_name: "Display name".to_string(),
         bio: "Bio".to_string(),
-        user_id: app.test_user.user_id.clone(),
+        user_id: app.test_user.user_id,
     }
     .update(&app.db_pool)
     .await
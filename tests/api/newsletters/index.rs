@@ -14,7 +14,7 @@ async fn unauthenticated_user_can_get_published_timeline_newsletter_issues() {
     assert_eq!(200, response.status().as_u16());
 
     let response_body: Vec<PublicNewsletterListItem> = response.json().await.unwrap();
-    assert_eq!(response_body.is_empty(), true);
+    assert!(response_body.is_empty());
 }
 
 #[tokio::test]
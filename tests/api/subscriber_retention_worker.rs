@@ -0,0 +1,124 @@
+use crate::helpers::spawn_app;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+#[tokio::test]
+async fn unconfirmed_subscribers_are_purged_after_the_retention_period() {
+    let app = spawn_app().await;
+    app.create_unconfirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.create_unconfirmed_subscriber(None, Some("octavia@example.com".into()))
+        .await;
+    app.create_confirmed_subscriber(None, Some("samuel@example.com".into()))
+        .await;
+    let retention_days = app.worker_settings.unconfirmed_retention_days as i32;
+    app.backdate_signup("ursula@example.com", retention_days + 1)
+        .await;
+    app.backdate_signup("samuel@example.com", retention_days + 1)
+        .await;
+
+    app.enforce_retention_policy(&app.worker_settings).await;
+
+    let saved = sqlx::query!("SELECT email FROM subscriptions ORDER BY email")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap();
+    let emails: Vec<&str> = saved.iter().map(|r| r.email.as_str()).collect();
+    assert_eq!(emails, vec!["octavia@example.com", "samuel@example.com"]);
+    let tokens = sqlx::query!(
+        r#"SELECT COUNT(*) AS "count!" FROM subscription_tokens
+        JOIN subscriptions ON subscriptions.id = subscription_tokens.subscriber_id
+        WHERE subscriptions.email = 'ursula@example.com'"#
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(tokens.count, 0);
+}
+
+#[tokio::test]
+async fn unconfirmed_subscribers_are_reminded_once_before_being_purged() {
+    let app = spawn_app().await;
+    app.create_unconfirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let reminder_days = app.worker_settings.unconfirmed_reminder_days.unwrap() as i32;
+    app.backdate_signup("ursula@example.com", reminder_days + 1)
+        .await;
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    app.enforce_retention_policy(&app.worker_settings).await;
+    app.dispatch_all_pending_confirmation_emails().await;
+    app.enforce_retention_policy(&app.worker_settings).await;
+    app.dispatch_all_pending_confirmation_emails().await;
+
+    // The reminder carries a fresh link, even though the original one has expired.
+    let email_request = app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let confirmation_links = app.get_confirmation_links(&email_request);
+    let response = app
+        .api_client
+        .put(confirmation_links.html)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn unconfirmed_subscribers_are_purged_without_a_reminder_when_reminders_are_disabled() {
+    let app = spawn_app().await;
+    app.create_unconfirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let mut settings = app.worker_settings.clone();
+    settings.unconfirmed_reminder_days = None;
+    app.backdate_signup(
+        "ursula@example.com",
+        settings.unconfirmed_retention_days as i32 + 1,
+    )
+    .await;
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    app.enforce_retention_policy(&settings).await;
+    app.dispatch_all_pending_confirmation_emails().await;
+
+    let saved = sqlx::query!(r#"SELECT COUNT(*) AS "count!" FROM subscriptions"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.count, 0);
+}
+
+#[tokio::test]
+async fn subscribing_again_starts_a_new_retention_period() {
+    let app = spawn_app().await;
+    app.create_unconfirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let retention_days = app.worker_settings.unconfirmed_retention_days as i32;
+    app.backdate_signup("ursula@example.com", retention_days + 1)
+        .await;
+    app.create_unconfirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+
+    app.enforce_retention_policy(&app.worker_settings).await;
+
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("The subscriber who signed up again should not be purged.");
+    assert_eq!(saved.status, "pending_confirmation");
+}
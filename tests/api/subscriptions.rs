@@ -1,7 +1,13 @@
 use crate::helpers::spawn_app;
 use claims::assert_ok;
+use newsletter_api::challenge::Base64Challenger;
+use newsletter_api::confirmation_email_worker::try_send_confirmation_email;
+use newsletter_api::domain::DEFAULT_CONSENT_TEXT;
+use newsletter_api::shutdown::shutdown_channel;
 use newsletter_api::utils::ResponseErrorMessage;
-use wiremock::matchers::{method, path};
+use secrecy::Secret;
+use std::time::{Duration, Instant};
+use wiremock::matchers::{any, method, path};
 use wiremock::{Mock, ResponseTemplate};
 
 #[tokio::test]
@@ -87,6 +93,7 @@ async fn subscribe_sends_a_confirmation_email_for_valid_data() {
         &serde_json::json!({"name": "le guin", "email": "ursula_le_guin@gmail.com", "user_id": &app.test_user.user_id}),
     )
     .await;
+    app.dispatch_all_pending_confirmation_emails().await;
 
     // Assert
     // Mock asserts on drop
@@ -108,6 +115,7 @@ async fn subscribe_sends_a_confirmation_email_with_a_link() {
         &serde_json::json!({"name": "le guin", "email": "ursula_le_guin@gmail.com", "user_id": &app.test_user.user_id}),
     )
     .await;
+    app.dispatch_all_pending_confirmation_emails().await;
 
     // Assert
     let email_request = &app.email_server.received_requests().await.unwrap()[0];
@@ -187,3 +195,362 @@ async fn subscribe_returns_a_400_with_json_message_when_fields_are_present_but_i
         assert_ok!(response_body);
     }
 }
+
+#[tokio::test]
+async fn subscribe_returns_a_400_when_the_captcha_is_not_solved() {
+    // Arrange
+    let app = spawn_app().await;
+    let (challenge, answer) = app.solve_captcha();
+    let (_, other_answer) = app.solve_captcha();
+    let foreign_challenge =
+        Base64Challenger::new(Secret::new("AnotherSecretThatIs32BytesLong!!".to_string()))
+            .unwrap()
+            .encrypt()
+            .unwrap();
+    let test_cases = vec![
+        (
+            serde_json::json!({"challenge": &challenge, "answer": "", "name": "le guin", "email": "ursula_le_guin@gmail.com", "user_id": &app.test_user.user_id}),
+            "a missing answer",
+        ),
+        (
+            serde_json::json!({"challenge": &challenge, "answer": format!("{answer}x"), "name": "le guin", "email": "ursula_le_guin@gmail.com", "user_id": &app.test_user.user_id}),
+            "an incorrect answer",
+        ),
+        (
+            serde_json::json!({"challenge": &challenge, "answer": other_answer, "name": "le guin", "email": "ursula_le_guin@gmail.com", "user_id": &app.test_user.user_id}),
+            "the answer to another challenge",
+        ),
+        (
+            serde_json::json!({"challenge": foreign_challenge, "answer": answer, "name": "le guin", "email": "ursula_le_guin@gmail.com", "user_id": &app.test_user.user_id}),
+            "a challenge issued with another secret",
+        ),
+        (
+            serde_json::json!({"challenge": "not-a-challenge", "answer": answer, "name": "le guin", "email": "ursula_le_guin@gmail.com", "user_id": &app.test_user.user_id}),
+            "a malformed challenge",
+        ),
+    ];
+
+    for (body, description) in test_cases {
+        // Act
+        let response = app.post_subscriptions(&body).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not return a 400 Bad Request when the payload had {}.",
+            description
+        );
+    }
+
+    let saved = sqlx::query!("SELECT COUNT(*) AS \"count!\" FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.count, 0);
+}
+
+#[tokio::test]
+async fn subscribe_returns_a_400_when_a_captcha_is_replayed() {
+    // Arrange
+    let app = spawn_app().await;
+    let (challenge, answer) = app.solve_captcha();
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let first_response = app
+        .post_subscriptions(
+            &serde_json::json!({"challenge": &challenge, "answer": &answer, "name": "le guin", "email": "ursula_le_guin@gmail.com", "user_id": &app.test_user.user_id}),
+        )
+        .await;
+    let replayed_response = app
+        .post_subscriptions(
+            &serde_json::json!({"challenge": &challenge, "answer": &answer, "name": "octavia butler", "email": "octavia_butler@gmail.com", "user_id": &app.test_user.user_id}),
+        )
+        .await;
+    app.dispatch_all_pending_confirmation_emails().await;
+
+    // Assert
+    assert_eq!(200, first_response.status().as_u16());
+    assert_eq!(400, replayed_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn subscribing_twice_with_a_pending_email_sends_a_fresh_confirmation_email() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = serde_json::json!({"name": "le guin", "email": "ursula_le_guin@gmail.com", "user_id": &app.test_user.user_id});
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let first_response = app.post_subscriptions(&body).await;
+    let second_response = app.post_subscriptions(&body).await;
+    app.dispatch_all_pending_confirmation_emails().await;
+
+    // Assert
+    assert_eq!(200, first_response.status().as_u16());
+    assert_eq!(200, second_response.status().as_u16());
+    let email_requests = app.email_server.received_requests().await.unwrap();
+    let first_links = app.get_confirmation_links(&email_requests[0]);
+    let second_links = app.get_confirmation_links(&email_requests[1]);
+    assert_ne!(first_links.html, second_links.html);
+    let saved = sqlx::query!("SELECT COUNT(*) AS \"count!\" FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.count, 1);
+}
+
+#[tokio::test]
+async fn subscribing_with_a_confirmed_email_does_not_send_another_email() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = serde_json::json!({"name": "le guin", "email": "ursula_le_guin@gmail.com", "user_id": &app.test_user.user_id});
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    app.post_subscriptions(&body).await;
+    app.dispatch_all_pending_confirmation_emails().await;
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let confirmation_links = app.get_confirmation_links(email_request);
+    app.api_client
+        .put(confirmation_links.html)
+        .send()
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+
+    // Act
+    let response = app.post_subscriptions(&body).await;
+    app.dispatch_all_pending_confirmation_emails().await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn subscribe_succeeds_and_retries_the_confirmation_email_when_the_email_provider_is_down() {
+    // Arrange
+    let app = spawn_app().await;
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_subscriptions(
+            &serde_json::json!({"name": "le guin", "email": "ursula_le_guin@gmail.com", "user_id": &app.test_user.user_id}),
+        )
+        .await;
+    app.dispatch_all_pending_confirmation_emails().await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let pending = sqlx::query!("SELECT n_attempts, last_error FROM confirmation_email_outbox")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch the queued confirmation email.");
+    assert_eq!(pending.n_attempts, 1);
+    assert!(pending.last_error.is_some());
+}
+
+#[tokio::test]
+async fn confirmation_emails_waiting_for_the_rate_limiter_are_released_on_shutdown() {
+    let app = spawn_app().await;
+    let body = serde_json::json!({"name": "le guin", "email": "ursula_le_guin@gmail.com", "user_id": &app.test_user.user_id});
+    app.post_subscriptions(&body)
+        .await
+        .error_for_status()
+        .unwrap();
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+    app.rate_limiter
+        .pause(Duration::from_secs(600), Instant::now());
+    let (shutdown_sender, shutdown) = shutdown_channel();
+    shutdown_sender.send(true).unwrap();
+
+    tokio::time::timeout(
+        Duration::from_secs(5),
+        try_send_confirmation_email(
+            &app.db_pool,
+            &app.email_client,
+            &app.rate_limiter,
+            &app.worker_settings,
+            &app.base_url,
+            shutdown,
+        ),
+    )
+    .await
+    .expect("The worker should not wait for the rate limiter once a shutdown is requested.")
+    .unwrap();
+
+    let pending = sqlx::query!("SELECT n_attempts, next_attempt_at FROM confirmation_email_outbox")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("The unsent confirmation email should still be queued.");
+    assert_eq!(pending.n_attempts, 0);
+    assert!(pending.next_attempt_at <= chrono::Utc::now());
+}
+
+#[tokio::test]
+async fn subscribe_persists_the_tags_chosen_at_signup() {
+    let app = spawn_app().await;
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    let response = app
+        .post_subscriptions(&serde_json::json!({
+            "name": "le guin",
+            "email": "ursula_le_guin@gmail.com",
+            "tags": ["Sci-Fi", "essays"],
+            "user_id": &app.test_user.user_id
+        }))
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let saved = sqlx::query!("SELECT tag FROM subscriber_tags ORDER BY tag")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap();
+    let tags: Vec<&str> = saved.iter().map(|r| r.tag.as_str()).collect();
+    assert_eq!(tags, vec!["essays", "sci-fi"]);
+}
+
+#[tokio::test]
+async fn subscribe_records_the_consent_given_by_the_reader() {
+    let app = spawn_app().await;
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+    let (challenge, answer) = app.solve_captcha();
+
+    let response = app
+        .api_client
+        .post(format!("{}/subscriptions", &app.address))
+        .header("User-Agent", "Mozilla/5.0 (X11; Linux x86_64)")
+        .header("X-Forwarded-For", "203.0.113.7")
+        .json(&serde_json::json!({
+            "answer": answer,
+            "challenge": challenge,
+            "consent_text": "Send me the weekly digest.",
+            "email": "ursula_le_guin@gmail.com",
+            "name": "le guin",
+            "user_id": &app.test_user.user_id
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(200, response.status().as_u16());
+    let consent = sqlx::query!(
+        "SELECT source, ip_address, user_agent, consent_text, confirmed_at \
+        FROM subscription_consents"
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(consent.source, "form");
+    // The spoofable forwarding header is ignored in favour of the connection's peer.
+    assert_eq!(consent.ip_address.as_deref(), Some("127.0.0.1"));
+    assert_eq!(
+        consent.user_agent.as_deref(),
+        Some("Mozilla/5.0 (X11; Linux x86_64)")
+    );
+    assert_eq!(consent.consent_text, "Send me the weekly digest.");
+    assert!(consent.confirmed_at.is_none());
+}
+
+#[tokio::test]
+async fn subscribe_records_the_default_consent_text_and_the_api_source() {
+    let app = spawn_app().await;
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    let response = app
+        .post_subscriptions(&serde_json::json!({
+            "name": "le guin",
+            "email": "ursula_le_guin@gmail.com",
+            "source": "api",
+            "user_id": &app.test_user.user_id
+        }))
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let consent =
+        sqlx::query!("SELECT source, ip_address, consent_text FROM subscription_consents")
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(consent.source, "api");
+    assert_eq!(consent.ip_address.as_deref(), Some("127.0.0.1"));
+    assert_eq!(consent.consent_text, DEFAULT_CONSENT_TEXT);
+}
+
+#[tokio::test]
+async fn subscribe_returns_a_400_for_invalid_consent() {
+    let app = spawn_app().await;
+    let test_cases = vec![
+        (
+            serde_json::json!({ "consent_text": "  " }),
+            "empty consent text",
+        ),
+        (
+            serde_json::json!({ "consent_text": "a".repeat(2001) }),
+            "consent text too long",
+        ),
+        (serde_json::json!({ "source": "import" }), "import source"),
+        (serde_json::json!({ "source": "email" }), "unknown source"),
+    ];
+
+    for (mut body, description) in test_cases {
+        let fields = body.as_object_mut().unwrap();
+        fields.insert("name".into(), "le guin".into());
+        fields.insert("email".into(), "ursula_le_guin@gmail.com".into());
+        fields.insert("user_id".into(), app.test_user.user_id.to_string().into());
+
+        let response = app.post_subscriptions(&body).await;
+
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request when the payload had an {}.",
+            description
+        );
+    }
+}
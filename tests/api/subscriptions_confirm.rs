@@ -10,7 +10,7 @@ async fn confirmations_without_token_are_rejected_with_a_400() {
     // Act
     let response = app
         .api_client
-        .put(&format!("{}/subscriptions/confirm", app.address))
+        .put(format!("{}/subscriptions/confirm", app.address))
         .send()
         .await
         .expect("Failed to confirm subscriber.");
@@ -34,6 +34,7 @@ async fn the_link_returned_by_subscribe_returns_a_200_if_called() {
         &serde_json::json!({"name": "le guin", "email": "ursula_le_guin@gmail.com", "user_id": &app.test_user.user_id}),
     )
     .await;
+    app.dispatch_all_pending_confirmation_emails().await;
     let email_request = &app.email_server.received_requests().await.unwrap()[0];
     let confirmation_links = app.get_confirmation_links(email_request);
 
@@ -64,6 +65,7 @@ async fn clicking_on_the_confirmation_link_confirms_a_subscriber() {
         &serde_json::json!({"name": "le guin", "email": "ursula_le_guin@gmail.com", "user_id": &app.test_user.user_id}),
     )
     .await;
+    app.dispatch_all_pending_confirmation_emails().await;
     let email_request = &app.email_server.received_requests().await.unwrap()[0];
     let confirmation_links = app.get_confirmation_links(email_request);
 
@@ -85,3 +87,110 @@ async fn clicking_on_the_confirmation_link_confirms_a_subscriber() {
     assert_eq!(saved.name, "le guin");
     assert_eq!(saved.status, "confirmed");
 }
+
+#[tokio::test]
+async fn expired_confirmation_tokens_are_rejected_with_a_410() {
+    // Arrange
+    let app = spawn_app().await;
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    app.post_subscriptions(
+        &serde_json::json!({"name": "le guin", "email": "ursula_le_guin@gmail.com", "user_id": &app.test_user.user_id}),
+    )
+    .await;
+    app.dispatch_all_pending_confirmation_emails().await;
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let confirmation_links = app.get_confirmation_links(email_request);
+    sqlx::query!("UPDATE subscription_tokens SET created_at = now() - interval '30 days'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let response = app
+        .api_client
+        .put(confirmation_links.html)
+        .send()
+        .await
+        .expect("Failed to confirm subscriber.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 410);
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved subscription.");
+    assert_eq!(saved.status, "pending_confirmation");
+}
+
+#[tokio::test]
+async fn confirming_a_subscriber_records_the_double_opt_in() {
+    // Arrange
+    let app = spawn_app().await;
+    let confirmation_links = app.create_unconfirmed_subscriber(None, None).await;
+
+    // Act
+    let response = app
+        .api_client
+        .put(confirmation_links.html)
+        .send()
+        .await
+        .expect("Failed to confirm subscriber.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let consent = sqlx::query!("SELECT consented_at, confirmed_at FROM subscription_consents")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch the consent record.");
+    let confirmed_at = consent
+        .confirmed_at
+        .expect("The confirmation was not recorded.");
+    assert!(confirmed_at >= consent.consented_at);
+}
+
+#[tokio::test]
+async fn confirmation_links_cannot_be_replayed() {
+    // Arrange
+    let app = spawn_app().await;
+    let confirmation_links = app.create_unconfirmed_subscriber(None, None).await;
+    app.api_client
+        .put(confirmation_links.html.clone())
+        .send()
+        .await
+        .expect("Failed to confirm subscriber.")
+        .error_for_status()
+        .unwrap();
+    sqlx::query!("UPDATE subscriptions SET status = 'unsubscribed'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let response = app
+        .api_client
+        .put(confirmation_links.html)
+        .send()
+        .await
+        .expect("Failed to confirm subscriber.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 401);
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved subscription.");
+    assert_eq!(saved.status, "unsubscribed");
+    let confirmations = sqlx::query!(
+        r#"SELECT COUNT(*) AS "count!" FROM subscription_consents WHERE confirmed_at IS NOT NULL"#
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(confirmations.count, 1);
+}
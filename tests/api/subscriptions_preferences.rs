@@ -0,0 +1,402 @@
+use crate::helpers::{TestUser, spawn_app};
+use newsletter_api::models::{ReaderSubscription, SubscriberDataExport};
+use uuid::Uuid;
+use wiremock::matchers::{any, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+#[tokio::test]
+async fn the_preference_link_lists_every_subscription_of_the_reader() {
+    let app = spawn_app().await;
+    let second_user = TestUser::create(&app.db_pool).await.unwrap();
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.create_unconfirmed_subscriber(Some(second_user.user_id), Some("ursula@example.com".into()))
+        .await;
+    app.create_confirmed_subscriber(None, Some("octavia@example.com".into()))
+        .await;
+
+    let preference_link = app.get_preference_link("ursula@example.com").await;
+    let response = app.api_client.get(preference_link).send().await.unwrap();
+
+    assert_eq!(200, response.status().as_u16());
+    let subscriptions: Vec<ReaderSubscription> = response.json().await.unwrap();
+    let writers: Vec<(&str, &str)> = subscriptions
+        .iter()
+        .map(|s| (s.writer_username.as_str(), s.status.as_str()))
+        .collect();
+    assert_eq!(
+        writers,
+        vec![
+            (app.test_user.username.as_str(), "confirmed"),
+            (second_user.username.as_str(), "pending_confirmation"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn no_link_is_sent_to_an_address_without_subscriptions() {
+    let app = spawn_app().await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app
+        .post_subscriptions_preferences(&serde_json::json!({ "email": "ursula@example.com" }))
+        .await;
+    app.dispatch_all_pending_preference_links().await;
+
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn link_requests_do_not_depend_on_the_email_provider() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // The link is only queued, so a subscribed address answers like any other.
+    for email in ["ursula@example.com", "someone-else@example.com"] {
+        let response = app
+            .post_subscriptions_preferences(&serde_json::json!({ "email": email }))
+            .await;
+        assert_eq!(200, response.status().as_u16());
+    }
+    let queued = sqlx::query!(r#"SELECT COUNT(*) AS "count!" FROM preference_link_outbox"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(queued.count, 2);
+}
+
+#[tokio::test]
+async fn failed_preference_links_are_retried_later() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.post_subscriptions_preferences(&serde_json::json!({ "email": "ursula@example.com" }))
+        .await
+        .error_for_status()
+        .unwrap();
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    app.dispatch_all_pending_preference_links().await;
+
+    let queued = sqlx::query!("SELECT n_attempts, next_attempt_at FROM preference_link_outbox")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(queued.n_attempts, 1);
+    assert!(queued.next_attempt_at > chrono::Utc::now());
+}
+
+#[tokio::test]
+async fn a_reader_can_pause_and_rename_a_subscription() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    let preference_link = app.get_preference_link("ursula@example.com").await;
+
+    let response = app
+        .put_subscriptions_preference(
+            &preference_link,
+            &subscriber_id,
+            &serde_json::json!({ "name": "Ursula K. Le Guin", "status": "paused" }),
+        )
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let saved = sqlx::query!("SELECT name, status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.name, "Ursula K. Le Guin");
+    assert_eq!(saved.status, "paused");
+
+    let response = app
+        .put_subscriptions_preference(
+            &preference_link,
+            &subscriber_id,
+            &serde_json::json!({ "name": "Ursula K. Le Guin", "status": "confirmed" }),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_reader_can_unsubscribe() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    let preference_link = app.get_preference_link("ursula@example.com").await;
+
+    let response = app
+        .put_subscriptions_preference(
+            &preference_link,
+            &subscriber_id,
+            &serde_json::json!({ "name": "Ursula", "status": "unsubscribed" }),
+        )
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let subscription: ReaderSubscription = response.json().await.unwrap();
+    assert_eq!(subscription.status, "unsubscribed");
+}
+
+#[tokio::test]
+async fn a_reader_cannot_confirm_a_pending_subscription() {
+    let app = spawn_app().await;
+    app.create_unconfirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("ursula@example.com").await;
+    let preference_link = app.get_preference_link("ursula@example.com").await;
+
+    let response = app
+        .put_subscriptions_preference(
+            &preference_link,
+            &subscriber_id,
+            &serde_json::json!({ "name": "Ursula", "status": "confirmed" }),
+        )
+        .await;
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_reader_cannot_change_someone_elses_subscription() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.create_confirmed_subscriber(None, Some("octavia@example.com".into()))
+        .await;
+    let subscriber_id = app.subscriber_id("octavia@example.com").await;
+    let preference_link = app.get_preference_link("ursula@example.com").await;
+
+    let response = app
+        .put_subscriptions_preference(
+            &preference_link,
+            &subscriber_id,
+            &serde_json::json!({ "name": "Octavia", "status": "unsubscribed" }),
+        )
+        .await;
+
+    assert_eq!(404, response.status().as_u16());
+    let saved =
+        sqlx::query!("SELECT status FROM subscriptions WHERE email = 'octavia@example.com'")
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(saved.status, "confirmed");
+}
+
+#[tokio::test]
+async fn an_expired_preference_link_is_rejected_with_a_410() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let preference_link = app.get_preference_link("ursula@example.com").await;
+    sqlx::query!("UPDATE preference_tokens SET created_at = now() - interval '1 day'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    let response = app.api_client.get(preference_link).send().await.unwrap();
+
+    assert_eq!(410, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn an_unknown_preference_token_is_rejected_with_a_401() {
+    let app = spawn_app().await;
+
+    let response = app
+        .api_client
+        .get(format!("{}/subscriptions/preferences", app.address))
+        .query(&[("preference_token", "unknown")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(401, response.status().as_u16());
+
+    let response = app
+        .api_client
+        .put(format!(
+            "{}/subscriptions/preferences/{}",
+            app.address,
+            Uuid::new_v4()
+        ))
+        .query(&[("preference_token", "unknown")])
+        .json(&serde_json::json!({ "name": "Ursula", "status": "paused" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_reader_can_export_their_personal_data() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.create_confirmed_subscriber(None, Some("octavia@example.com".into()))
+        .await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+    {
+        let _mock_guard = Mock::given(path("/api/v1/send"))
+            .and(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount_as_scoped(&app.email_server)
+            .await;
+        app.dispatch_all_pending_emails().await;
+    }
+    let preference_link = app.get_preference_link("ursula@example.com").await;
+
+    let response = app
+        .get_subscriptions_preferences_data(&preference_link)
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let export: SubscriberDataExport = response.json().await.unwrap();
+    assert_eq!(export.email, "ursula@example.com");
+    assert_eq!(export.subscriptions.len(), 1);
+    assert_eq!(
+        export.subscriptions[0].writer_username,
+        app.test_user.username
+    );
+    assert_eq!(export.deliveries.len(), 1);
+    assert_eq!(
+        export.deliveries[0].newsletter_issue_id,
+        newsletter_issue_id
+    );
+    assert_eq!(export.deliveries[0].outcome, "sent");
+    assert_eq!(export.preference_tokens.len(), 1);
+}
+
+#[tokio::test]
+async fn a_reader_can_erase_their_personal_data_across_all_writers() {
+    let app = spawn_app().await;
+    let second_user = TestUser::create(&app.db_pool).await.unwrap();
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.create_unconfirmed_subscriber(Some(second_user.user_id), Some("ursula@example.com".into()))
+        .await;
+    app.create_confirmed_subscriber(None, Some("octavia@example.com".into()))
+        .await;
+    app.test_user.login(&app).await;
+    app.create_published_newsletter_issue().await;
+    {
+        let _mock_guard = Mock::given(path("/api/v1/send"))
+            .and(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount_as_scoped(&app.email_server)
+            .await;
+        app.dispatch_all_pending_emails().await;
+    }
+    let preference_link = app.get_preference_link("ursula@example.com").await;
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app
+        .delete_subscriptions_preferences_data(&preference_link)
+        .await;
+    app.dispatch_all_pending_erasure_confirmations().await;
+
+    assert_eq!(200, response.status().as_u16());
+    let email_request = app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    assert_eq!(body["To"][0]["Email"], "ursula@example.com");
+
+    let emails = sqlx::query!("SELECT email FROM subscriptions")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap();
+    let emails: Vec<String> = emails.into_iter().map(|r| r.email).collect();
+    assert_eq!(emails, vec!["octavia@example.com"]);
+
+    let deliveries = sqlx::query!("SELECT subscriber_email, provider_response FROM delivery_log")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(deliveries.len(), 2);
+    assert!(
+        deliveries
+            .iter()
+            .all(|d| d.subscriber_email != "ursula@example.com")
+    );
+
+    let response = app
+        .get_subscriptions_preferences_data(&preference_link)
+        .await;
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn failed_erasure_confirmations_are_retried_later() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let preference_link = app.get_preference_link("ursula@example.com").await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app
+        .delete_subscriptions_preferences_data(&preference_link)
+        .await;
+    app.dispatch_all_pending_erasure_confirmations().await;
+
+    assert_eq!(200, response.status().as_u16());
+    let queued =
+        sqlx::query!("SELECT email, n_attempts, next_attempt_at FROM erasure_confirmation_outbox")
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(queued.email, "ursula@example.com");
+    assert_eq!(queued.n_attempts, 1);
+    assert!(queued.next_attempt_at > chrono::Utc::now());
+}
+
+#[tokio::test]
+async fn personal_data_requests_with_an_unknown_token_are_rejected_with_a_401() {
+    let app = spawn_app().await;
+    let preference_link = reqwest::Url::parse(&format!(
+        "{}/subscriptions/preferences?preference_token=unknown",
+        app.address
+    ))
+    .unwrap();
+
+    let response = app
+        .get_subscriptions_preferences_data(&preference_link)
+        .await;
+    assert_eq!(401, response.status().as_u16());
+
+    let response = app
+        .delete_subscriptions_preferences_data(&preference_link)
+        .await;
+    assert_eq!(401, response.status().as_u16());
+}
@@ -0,0 +1,202 @@
+use crate::helpers::{TestApp, spawn_app};
+use newsletter_api::models::DeliveryReport;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Deliver a newly published issue to the test user's subscribers and return the
+/// request received by the email API.
+async fn deliver_an_issue(app: &TestApp) -> wiremock::Request {
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+    app.create_published_newsletter_issue().await;
+    app.dispatch_all_pending_emails().await;
+
+    app.email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn issue_emails_carry_one_click_unsubscribe_headers() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+
+    let email_request = deliver_an_issue(&app).await;
+
+    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    assert_eq!(
+        body["Headers"]["List-Unsubscribe-Post"],
+        "List-Unsubscribe=One-Click"
+    );
+    let unsubscribe_link = app.get_unsubscribe_link(&email_request);
+    let unsubscribe_token = unsubscribe_link
+        .query_pairs()
+        .find(|(key, _)| key == "unsubscribe_token")
+        .unwrap()
+        .1
+        .to_string();
+    assert!(body["Html"].as_str().unwrap().contains(&unsubscribe_token));
+    assert!(body["Text"].as_str().unwrap().contains(&unsubscribe_token));
+}
+
+#[tokio::test]
+async fn the_unsubscribe_link_unsubscribes_the_subscriber() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    let email_request = deliver_an_issue(&app).await;
+
+    let response = app
+        .post_unsubscribe(app.get_unsubscribe_link(&email_request))
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved subscription.");
+    assert_eq!(saved.status, "unsubscribed");
+}
+
+#[tokio::test]
+async fn the_unsubscribe_link_does_not_overwrite_a_bounced_or_complained_status() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    let email_request = deliver_an_issue(&app).await;
+    let unsubscribe_link = app.get_unsubscribe_link(&email_request);
+
+    for status in ["bounced", "complained"] {
+        sqlx::query!("UPDATE subscriptions SET status = $1", status)
+            .execute(&app.db_pool)
+            .await
+            .unwrap();
+
+        let response = app.post_unsubscribe(unsubscribe_link.clone()).await;
+
+        assert_eq!(200, response.status().as_u16());
+        let saved = sqlx::query!("SELECT status FROM subscriptions")
+            .fetch_one(&app.db_pool)
+            .await
+            .expect("Failed to fetch saved subscription.");
+        assert_eq!(saved.status, status);
+    }
+}
+
+#[tokio::test]
+async fn the_link_in_the_email_body_asks_for_confirmation_before_unsubscribing() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    let email_request = deliver_an_issue(&app).await;
+    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    let links: Vec<_> = linkify::LinkFinder::new()
+        .links(body["Html"].as_str().unwrap())
+        .filter(|l| *l.kind() == linkify::LinkKind::Url)
+        .collect();
+    let mut unsubscribe_link = reqwest::Url::parse(links.last().unwrap().as_str()).unwrap();
+    unsubscribe_link.set_port(Some(app.port)).unwrap();
+
+    let response = app
+        .api_client
+        .get(unsubscribe_link.clone())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(200, response.status().as_u16());
+    let page = response.text().await.unwrap();
+    assert!(page.contains(r#"<form method="post""#));
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved subscription.");
+    assert_eq!(saved.status, "confirmed");
+
+    // Submitting the confirmation form.
+    let response = app
+        .api_client
+        .post(unsubscribe_link)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(200, response.status().as_u16());
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved subscription.");
+    assert_eq!(saved.status, "unsubscribed");
+}
+
+#[tokio::test]
+async fn queued_deliveries_to_unsubscribed_subscribers_are_dropped() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, None).await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = app.create_published_newsletter_issue().await;
+    sqlx::query!("UPDATE subscriptions SET status = 'unsubscribed'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    Mock::given(path("/api/v1/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+    app.dispatch_all_pending_emails().await;
+
+    let response = app
+        .get_admin_delivery_report(&newsletter_issue_id, None)
+        .await;
+    let report: DeliveryReport = response.json().await.unwrap();
+    assert_eq!(report.pending, 0);
+    assert_eq!(report.sent, 0);
+    assert_eq!(report.log[0].outcome, "unsubscribed");
+}
+
+#[tokio::test]
+async fn unknown_unsubscribe_tokens_are_rejected_with_a_401() {
+    let app = spawn_app().await;
+    let unsubscribe_link = reqwest::Url::parse(&format!(
+        "{}/subscriptions/unsubscribe?unsubscribe_token=unknown",
+        app.address
+    ))
+    .unwrap();
+
+    let response = app.post_unsubscribe(unsubscribe_link.clone()).await;
+    assert_eq!(401, response.status().as_u16());
+
+    let response = app
+        .api_client
+        .get(unsubscribe_link)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn unsubscribing_without_a_token_is_rejected_with_a_400() {
+    let app = spawn_app().await;
+
+    let response = app
+        .api_client
+        .post(format!("{}/subscriptions/unsubscribe", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(400, response.status().as_u16());
+}
@@ -0,0 +1,310 @@
+use crate::helpers::{TestUser, spawn_app};
+use wiremock::matchers::any;
+use wiremock::{Mock, ResponseTemplate};
+
+/// A Postmark bounce webhook, as documented at
+/// https://postmarkapp.com/developer/webhooks/bounce-webhook
+fn bounce(email: &str, bounce_type: &str) -> serde_json::Value {
+    serde_json::json!({
+        "RecordType": "Bounce",
+        "MessageStream": "outbound",
+        "ID": 4323372036854775807i64,
+        "Type": bounce_type,
+        "TypeCode": 1,
+        "Name": "Hard bounce",
+        "Tag": "",
+        "MessageID": "883953f4-6105-42a2-a16a-77a8eac79483",
+        "Metadata": {},
+        "ServerID": 23,
+        "Description": "The server was unable to deliver your message (ex: unknown user, mailbox not found).",
+        "Details": "Test bounce details",
+        "Email": email,
+        "From": "sender@example.com",
+        "BouncedAt": "2026-10-17T10:00:00Z",
+        "DumpAvailable": true,
+        "Inactive": true,
+        "CanActivate": true,
+        "Subject": "Test subject",
+        "Content": "",
+    })
+}
+
+/// A Postmark spam-complaint webhook, as documented at
+/// https://postmarkapp.com/developer/webhooks/spam-complaint-webhook
+fn spam_complaint(email: &str) -> serde_json::Value {
+    serde_json::json!({
+        "RecordType": "SpamComplaint",
+        "MessageStream": "outbound",
+        "ID": 42,
+        "Type": "SpamComplaint",
+        "TypeCode": 512,
+        "Name": "Spam complaint",
+        "Tag": "",
+        "MessageID": "00000000-0000-0000-0000-000000000000",
+        "Metadata": {},
+        "ServerID": 1234,
+        "Description": "",
+        "Details": "Test spam complaint details",
+        "Email": email,
+        "From": "sender@example.com",
+        "BouncedAt": "2026-10-17T10:00:00Z",
+        "DumpAvailable": true,
+        "Inactive": true,
+        "CanActivate": false,
+        "Subject": "Test subject",
+        "Content": "",
+    })
+}
+
+#[tokio::test]
+async fn a_hard_bounce_marks_every_subscription_of_the_address_as_bounced() {
+    let app = spawn_app().await;
+    let second_user = TestUser::create(&app.db_pool).await.unwrap();
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.create_confirmed_subscriber(Some(second_user.user_id), Some("ursula@example.com".into()))
+        .await;
+    app.create_confirmed_subscriber(None, Some("octavia@example.com".into()))
+        .await;
+
+    let response = app
+        .post_postmark_webhook(&bounce("Ursula@Example.com", "HardBounce"))
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let saved = sqlx::query!("SELECT email, status FROM subscriptions ORDER BY email, status")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap();
+    let saved: Vec<(&str, &str)> = saved
+        .iter()
+        .map(|r| (r.email.as_str(), r.status.as_str()))
+        .collect();
+    assert_eq!(
+        saved,
+        vec![
+            ("octavia@example.com", "confirmed"),
+            ("ursula@example.com", "bounced"),
+            ("ursula@example.com", "bounced"),
+        ]
+    );
+    let suppressed = sqlx::query!("SELECT email, reason FROM suppressed_emails")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(suppressed.email, "ursula@example.com");
+    assert_eq!(suppressed.reason, "hard_bounce");
+}
+
+#[tokio::test]
+async fn a_spam_complaint_marks_the_subscription_as_complained() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+
+    let response = app
+        .post_postmark_webhook(&spam_complaint("ursula@example.com"))
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.status, "complained");
+}
+
+#[tokio::test]
+async fn suppressed_addresses_are_not_sent_new_issues() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.create_confirmed_subscriber(None, Some("octavia@example.com".into()))
+        .await;
+    app.post_postmark_webhook(&spam_complaint("ursula@example.com"))
+        .await
+        .error_for_status()
+        .unwrap();
+    // Even a writer who marks the reader as confirmed again can't resume sending.
+    sqlx::query!("UPDATE subscriptions SET status = 'confirmed'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    app.test_user.login(&app).await;
+
+    app.create_published_newsletter_issue().await;
+
+    let queued = sqlx::query!("SELECT subscriber_email FROM issue_delivery_queue")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap();
+    let queued: Vec<&str> = queued.iter().map(|r| r.subscriber_email.as_str()).collect();
+    assert_eq!(queued, vec!["octavia@example.com"]);
+}
+
+#[tokio::test]
+async fn suppressed_addresses_cannot_subscribe_again() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.post_postmark_webhook(&bounce("ursula@example.com", "HardBounce"))
+        .await
+        .error_for_status()
+        .unwrap();
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app
+        .post_subscriptions(&serde_json::json!({
+            "name": "le guin",
+            "email": "ursula@example.com",
+            "user_id": app.test_user.user_id,
+        }))
+        .await;
+    app.dispatch_all_pending_confirmation_emails().await;
+
+    assert_eq!(200, response.status().as_u16());
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.status, "bounced");
+}
+
+#[tokio::test]
+async fn suppressed_addresses_are_not_sent_confirmation_emails() {
+    let app = spawn_app().await;
+    let second_user = TestUser::create(&app.db_pool).await.unwrap();
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.post_postmark_webhook(&spam_complaint("ursula@example.com"))
+        .await
+        .error_for_status()
+        .unwrap();
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Another writer's signup form doesn't know the address complained.
+    app.post_subscriptions(&serde_json::json!({
+        "name": "le guin",
+        "email": "Ursula@Example.com",
+        "user_id": second_user.user_id,
+    }))
+    .await
+    .error_for_status()
+    .unwrap();
+    app.dispatch_all_pending_confirmation_emails().await;
+
+    let queued = sqlx::query!(r#"SELECT COUNT(*) AS "count!" FROM confirmation_email_outbox"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(queued.count, 0);
+}
+
+#[tokio::test]
+async fn suppressed_addresses_are_not_sent_preference_links() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    app.post_postmark_webhook(&bounce("ursula@example.com", "HardBounce"))
+        .await
+        .error_for_status()
+        .unwrap();
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app
+        .post_subscriptions_preferences(&serde_json::json!({ "email": "ursula@example.com" }))
+        .await;
+    app.dispatch_all_pending_preference_links().await;
+
+    assert_eq!(200, response.status().as_u16());
+    let queued = sqlx::query!(r#"SELECT COUNT(*) AS "count!" FROM preference_link_outbox"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(queued.count, 0);
+}
+
+#[tokio::test]
+async fn soft_bounces_are_acknowledged_and_ignored() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+
+    let response = app
+        .post_postmark_webhook(&bounce("ursula@example.com", "SoftBounce"))
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.status, "confirmed");
+    let suppressed = sqlx::query!(r#"SELECT COUNT(*) AS "count!" FROM suppressed_emails"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(suppressed.count, 0);
+}
+
+#[tokio::test]
+async fn webhooks_with_missing_or_invalid_credentials_are_rejected_with_a_401() {
+    let app = spawn_app().await;
+    app.create_confirmed_subscriber(None, Some("ursula@example.com".into()))
+        .await;
+    let body = bounce("ursula@example.com", "HardBounce");
+    let test_cases = vec![
+        (None, "no credentials"),
+        (Some("Basic not base64!"), "malformed credentials"),
+        // postmark:wrong-secret
+        (Some("Basic cG9zdG1hcms6d3Jvbmctc2VjcmV0"), "wrong password"),
+        // postmark (no password)
+        (Some("Basic cG9zdG1hcms="), "missing password"),
+        (Some("Bearer my-webhook-secret"), "wrong scheme"),
+    ];
+
+    for (authorization, description) in test_cases {
+        let response = app
+            .post_postmark_webhook_with_authorization(&body, authorization)
+            .await;
+
+        assert_eq!(
+            401,
+            response.status().as_u16(),
+            "The webhook was not rejected when sent with {}.",
+            description
+        );
+        assert_eq!(
+            r#"Basic realm="webhooks""#,
+            response.headers()["WWW-Authenticate"]
+        );
+    }
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.status, "confirmed");
+}
+
+#[tokio::test]
+async fn authenticated_webhooks_with_an_invalid_payload_are_rejected_with_a_400() {
+    let app = spawn_app().await;
+
+    let response = app
+        .post_postmark_webhook(&serde_json::json!({ "RecordType": "Bounce" }))
+        .await;
+
+    assert_eq!(400, response.status().as_u16());
+}